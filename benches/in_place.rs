@@ -0,0 +1,86 @@
+//! Compare allocations and time of deserializing into a new value and into an
+//! existing value.
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    hint::black_box,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Instant,
+};
+
+use serde::{Deserialize, Serialize};
+
+/// Allocator counting allocations.
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+#[derive(Default, Deserialize, Serialize)]
+struct Message {
+    id: u64,
+    name: String,
+    tags: Vec<String>,
+    values: Vec<u32>,
+}
+
+const ITERATIONS: usize = 100_000;
+
+/// Run `f` for every message, returning allocations per message and
+/// nanoseconds per message.
+fn measure<F: FnMut(&[u8])>(messages: &[Vec<u8>], mut f: F) -> (f64, f64) {
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+    let start = Instant::now();
+    for index in 0..ITERATIONS {
+        f(&messages[index % messages.len()]);
+    }
+    let elapsed = start.elapsed().as_nanos() as f64;
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - allocations;
+    let iterations = ITERATIONS as f64;
+    (allocations as f64 / iterations, elapsed / iterations)
+}
+
+fn main() {
+    let messages = (0..16)
+        .map(|id| {
+            let message = Message {
+                id,
+                name: format!("message {id}"),
+                tags: (0..8).map(|tag| format!("tag {tag}")).collect(),
+                values: (0..64).collect(),
+            };
+            shaft::to_bytes(&message).expect("serialization error")
+        })
+        .collect::<Vec<_>>();
+
+    let (allocations, time) = measure(&messages, |bytes| {
+        let message: Message = shaft::from_bytes(bytes).expect("deserialization error");
+        black_box(message);
+    });
+    println!("from_bytes:          {allocations:6.2} allocations, {time:8.1} ns per message");
+
+    let mut message = Message::default();
+    let (allocations, time) = measure(&messages, |bytes| {
+        shaft::from_bytes_in_place(&mut message, bytes).expect("deserialization error");
+        black_box(&message);
+    });
+    println!("from_bytes_in_place: {allocations:6.2} allocations, {time:8.1} ns per message");
+}
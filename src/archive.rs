@@ -0,0 +1,417 @@
+//! Random-access containers of serialized records.
+//!
+//! An archive is made of:
+//!
+//! - the records, serialized one after the other with the options of the
+//!   archive (including their checksum trailer),
+//! - the offset table, holding the `u64` offset of the start of each record,
+//! - the footer, made of the `u64` offset of the table, the `u64` record
+//!   count, the envelope flags, checksum and human readable bytes describing
+//!   the options, the format version as a `u8` and the magic bytes `SHFA`.
+//!
+//! Offsets and counts are little-endian whatever the options. The footer has
+//! a fixed length, so a reader locates the table from the end of the archive
+//! and decodes any record without reading the ones before it.
+//!
+//! # Examples
+//!
+//! ```
+//! // Bring serde Serialize/Deserialize derivable traits into scope.
+//! use serde::{Deserialize, Serialize};
+//! // Bring archive items into scope.
+//! use shaft::archive::{Archive, ArchiveWriter};
+//!
+//! // Define custom record.
+//! #[derive(Deserialize, Serialize)]
+//! struct Record {
+//!     id: u32,
+//!     name: String,
+//! }
+//!
+//! // Write records into an archive.
+//! let mut writer = ArchiveWriter::new(Vec::new());
+//! for id in 0..1000 {
+//!     let record = Record { id, name: format!("record {id}") };
+//!     writer.push(&record).expect("serialization error");
+//! }
+//! let bytes = writer.finish().expect("serialization error");
+//!
+//! // Decode a single record.
+//! let archive = Archive::new(&bytes).expect("deserialization error");
+//! assert_eq!(archive.len(), 1000);
+//! let record: Record = archive.get(412).expect("deserialization error");
+//! assert_eq!(record.id, 412);
+//! assert_eq!(record.name, "record 412");
+//! ```
+
+use std::{
+    io::{Read, Seek, SeekFrom, Write},
+    marker::PhantomData,
+};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::{envelope, error::Result, options::Options};
+
+/// Magic bytes ending every archive.
+pub const MAGIC: [u8; 4] = *b"SHFA";
+
+/// Format version written in the archive footer.
+pub const VERSION: u8 = 1;
+
+const OFFSET_LEN: usize = 8;
+
+const FOOTER_LEN: usize = 2 * OFFSET_LEN + 4 + MAGIC.len();
+
+/// Writer appending records to an archive.
+///
+/// [`ArchiveWriter::finish`] must be called to write the offset table and the
+/// footer.
+pub struct ArchiveWriter<W: Write> {
+    writer: W,
+    options: Options,
+    offsets: Vec<u64>,
+    position: u64,
+}
+
+impl<W: Write> ArchiveWriter<W> {
+    /// Create a new archive writer with default options.
+    pub fn new(writer: W) -> Self {
+        Self::with_options(writer, Options::default())
+    }
+
+    /// Create a new archive writer with the given options.
+    ///
+    /// Decoding options such as [`Options::with_trailing_defaults`] are not
+    /// recorded in the footer.
+    pub fn with_options(writer: W, options: Options) -> Self {
+        Self {
+            writer,
+            options,
+            offsets: Vec::new(),
+            position: 0,
+        }
+    }
+
+    /// Serialize a record at the end of the archive.
+    pub fn push<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: Serialize,
+    {
+        let bytes = crate::to_bytes_with(value, self.options)?;
+        self.writer.write_all(&bytes)?;
+        self.offsets.push(self.position);
+        self.position += bytes.len() as u64;
+        Ok(())
+    }
+
+    /// Returns the number of records written.
+    pub fn len(&self) -> usize {
+        self.offsets.len()
+    }
+
+    /// Returns `true` if no record was written.
+    pub fn is_empty(&self) -> bool {
+        self.offsets.is_empty()
+    }
+
+    /// Write the offset table and the footer and return the inner writer.
+    pub fn finish(mut self) -> Result<W> {
+        let mut bytes = Vec::with_capacity(self.offsets.len() * OFFSET_LEN + FOOTER_LEN);
+        for offset in &self.offsets {
+            bytes.extend(offset.to_le_bytes());
+        }
+        bytes.extend(self.position.to_le_bytes());
+        bytes.extend((self.offsets.len() as u64).to_le_bytes());
+        bytes.extend(envelope::encode_options(self.options));
+        bytes.push(VERSION);
+        bytes.extend(MAGIC);
+        self.writer.write_all(&bytes)?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+/// Footer of an archive.
+struct Footer {
+    table: u64,
+    len: u64,
+    options: Options,
+}
+
+impl Footer {
+    fn decode(bytes: &[u8]) -> Result<Self> {
+        let Some(rest) = bytes.strip_suffix(&MAGIC) else {
+            return Err("archive: invalid magic bytes".into());
+        };
+        let [table @ .., flags, checksum, human_readable, version] = rest else {
+            return Err("archive: invalid footer".into());
+        };
+        if *version != VERSION {
+            return Err(format!("archive: unsupported version {version}").into());
+        }
+        let options = envelope::decode_options([*flags, *checksum, *human_readable])?;
+        let (table, len) = table.split_at(OFFSET_LEN);
+        Ok(Self {
+            table: u64::from_le_bytes(table.try_into().unwrap()),
+            len: u64::from_le_bytes(len.try_into().unwrap()),
+            options,
+        })
+    }
+
+    /// Returns the expected length of the archive.
+    fn archive_len(&self) -> Option<u64> {
+        let table_len = self.len.checked_mul(OFFSET_LEN as u64)?;
+        self.table
+            .checked_add(table_len)?
+            .checked_add(FOOTER_LEN as u64)
+    }
+
+    /// Returns the range of a record given its offset and the offset of the
+    /// next one.
+    fn range(&self, start: u64, end: u64) -> Result<(usize, usize)> {
+        if start > end || end > self.table {
+            return Err("archive: invalid record offset".into());
+        }
+        let start = usize::try_from(start).map_err(|_| "archive: offset out of range")?;
+        let end = usize::try_from(end).map_err(|_| "archive: offset out of range")?;
+        Ok((start, end))
+    }
+}
+
+fn read_offset(bytes: &[u8]) -> u64 {
+    u64::from_le_bytes(bytes[..OFFSET_LEN].try_into().unwrap())
+}
+
+/// Archive borrowed from bytes.
+#[derive(Clone, Copy, Debug)]
+pub struct Archive<'a> {
+    records: &'a [u8],
+    table: &'a [u8],
+    options: Options,
+}
+
+impl<'a> Archive<'a> {
+    /// Read the footer of an archive.
+    ///
+    /// Fails if the magic bytes are missing, if the version or the flags are
+    /// not supported, or if the length of the archive does not match the
+    /// footer.
+    pub fn new(bytes: &'a [u8]) -> Result<Self> {
+        if bytes.len() < FOOTER_LEN {
+            return Err("archive: invalid footer".into());
+        }
+        let (rest, footer) = bytes.split_at(bytes.len() - FOOTER_LEN);
+        let footer = Footer::decode(footer)?;
+        if footer.archive_len() != Some(bytes.len() as u64) {
+            return Err("archive: invalid length".into());
+        }
+        let (records, table) = rest.split_at(footer.table as usize);
+        Ok(Self {
+            records,
+            table,
+            options: footer.options,
+        })
+    }
+
+    /// Returns the number of records.
+    pub fn len(&self) -> usize {
+        self.table.len() / OFFSET_LEN
+    }
+
+    /// Returns `true` if the archive holds no record.
+    pub fn is_empty(&self) -> bool {
+        self.table.is_empty()
+    }
+
+    /// Returns the options recorded in the footer.
+    pub fn options(&self) -> Options {
+        self.options
+    }
+
+    /// Returns the bytes of the record at `index`, including its checksum
+    /// trailer.
+    pub fn get_bytes(&self, index: usize) -> Result<&'a [u8]> {
+        if index >= self.len() {
+            return Err(format!("archive: index {index} out of bounds").into());
+        }
+        let start = read_offset(&self.table[index * OFFSET_LEN..]);
+        let end = match self.table.get((index + 1) * OFFSET_LEN..) {
+            Some(next) if !next.is_empty() => read_offset(next),
+            _ => self.records.len() as u64,
+        };
+        if start > end || end > self.records.len() as u64 {
+            return Err("archive: invalid record offset".into());
+        }
+        Ok(&self.records[start as usize..end as usize])
+    }
+
+    /// Deserialize the record at `index`.
+    pub fn get<T>(&self, index: usize) -> Result<T>
+    where
+        T: Deserialize<'a>,
+    {
+        crate::from_bytes_with(self.get_bytes(index)?, self.options)
+    }
+
+    /// Returns an iterator deserializing records in order.
+    pub fn iter<T>(&self) -> Iter<'a, T>
+    where
+        T: Deserialize<'a>,
+    {
+        Iter {
+            archive: *self,
+            index: 0,
+            phantom: PhantomData,
+        }
+    }
+}
+
+/// Iterator over the records of an [`Archive`].
+pub struct Iter<'a, T> {
+    archive: Archive<'a>,
+    index: usize,
+    phantom: PhantomData<fn() -> T>,
+}
+
+impl<'a, T: Deserialize<'a>> Iterator for Iter<'a, T> {
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.index >= self.archive.len() {
+            return None;
+        }
+        self.index += 1;
+        Some(self.archive.get(self.index - 1))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.archive.len() - self.index;
+        (len, Some(len))
+    }
+}
+
+impl<'a, T: Deserialize<'a>> ExactSizeIterator for Iter<'a, T> {}
+
+/// Archive read from a seekable reader.
+///
+/// Only the footer is read on creation, each record is read when decoded.
+pub struct ArchiveReader<R: Read + Seek> {
+    reader: R,
+    footer: Footer,
+}
+
+impl<R: Read + Seek> ArchiveReader<R> {
+    /// Read the footer of an archive.
+    ///
+    /// Fails if the magic bytes are missing, if the version or the flags are
+    /// not supported, or if the length of the archive does not match the
+    /// footer.
+    pub fn new(mut reader: R) -> Result<Self> {
+        let len = reader.seek(SeekFrom::End(0))?;
+        if len < FOOTER_LEN as u64 {
+            return Err("archive: invalid footer".into());
+        }
+        let mut footer = [0; FOOTER_LEN];
+        reader.seek(SeekFrom::Start(len - FOOTER_LEN as u64))?;
+        reader.read_exact(&mut footer)?;
+        let footer = Footer::decode(&footer)?;
+        if footer.archive_len() != Some(len) {
+            return Err("archive: invalid length".into());
+        }
+        Ok(Self { reader, footer })
+    }
+
+    /// Returns the number of records.
+    pub fn len(&self) -> usize {
+        self.footer.len as usize
+    }
+
+    /// Returns `true` if the archive holds no record.
+    pub fn is_empty(&self) -> bool {
+        self.footer.len == 0
+    }
+
+    /// Returns the options recorded in the footer.
+    pub fn options(&self) -> Options {
+        self.footer.options
+    }
+
+    /// Read the bytes of the record at `index`, including its checksum
+    /// trailer.
+    pub fn get_bytes(&mut self, index: usize) -> Result<Vec<u8>> {
+        if index >= self.len() {
+            return Err(format!("archive: index {index} out of bounds").into());
+        }
+        let position = self.footer.table + (index * OFFSET_LEN) as u64;
+        self.reader.seek(SeekFrom::Start(position))?;
+        let mut offsets = [0; 2 * OFFSET_LEN];
+        let offsets = if index + 1 < self.len() {
+            &mut offsets[..]
+        } else {
+            &mut offsets[..OFFSET_LEN]
+        };
+        self.reader.read_exact(offsets)?;
+        let start = read_offset(offsets);
+        let end = match offsets.get(OFFSET_LEN..) {
+            Some(next) if !next.is_empty() => read_offset(next),
+            _ => self.footer.table,
+        };
+        let (start, end) = self.footer.range(start, end)?;
+        let mut bytes = vec![0; end - start];
+        self.reader.seek(SeekFrom::Start(start as u64))?;
+        self.reader.read_exact(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    /// Deserialize the record at `index`.
+    pub fn get<T>(&mut self, index: usize) -> Result<T>
+    where
+        T: DeserializeOwned,
+    {
+        crate::from_bytes_with(&self.get_bytes(index)?, self.footer.options)
+    }
+
+    /// Returns an iterator deserializing records in order.
+    pub fn iter<T>(&mut self) -> ReaderIter<'_, R, T>
+    where
+        T: DeserializeOwned,
+    {
+        ReaderIter {
+            archive: self,
+            index: 0,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Returns the inner reader.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+/// Iterator over the records of an [`ArchiveReader`].
+pub struct ReaderIter<'a, R: Read + Seek, T> {
+    archive: &'a mut ArchiveReader<R>,
+    index: usize,
+    phantom: PhantomData<fn() -> T>,
+}
+
+impl<R: Read + Seek, T: DeserializeOwned> Iterator for ReaderIter<'_, R, T> {
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.index >= self.archive.len() {
+            return None;
+        }
+        self.index += 1;
+        Some(self.archive.get(self.index - 1))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.archive.len() - self.index;
+        (len, Some(len))
+    }
+}
+
+impl<R: Read + Seek, T: DeserializeOwned> ExactSizeIterator for ReaderIter<'_, R, T> {}
@@ -0,0 +1,329 @@
+use crate::{
+    error::{Error, ErrorKind, Result},
+    options::Checksum,
+    write::Writer,
+};
+
+/// Running state of a checksum.
+pub enum Hasher {
+    None,
+    Crc32c(Crc32c),
+    XxHash64(XxHash64),
+}
+
+impl Hasher {
+    pub fn new(checksum: Checksum) -> Self {
+        match checksum {
+            Checksum::None => Hasher::None,
+            Checksum::Crc32c => Hasher::Crc32c(Crc32c::new()),
+            Checksum::XxHash64 => Hasher::XxHash64(XxHash64::new()),
+        }
+    }
+
+    pub fn update(&mut self, bytes: &[u8]) {
+        match self {
+            Hasher::None => {}
+            Hasher::Crc32c(hasher) => hasher.update(bytes),
+            Hasher::XxHash64(hasher) => hasher.update(bytes),
+        }
+    }
+
+    /// Returns the checksum trailer.
+    pub fn finish(&self) -> Vec<u8> {
+        match self {
+            Hasher::None => Vec::new(),
+            Hasher::Crc32c(hasher) => hasher.finish().to_le_bytes().to_vec(),
+            Hasher::XxHash64(hasher) => hasher.finish().to_le_bytes().to_vec(),
+        }
+    }
+}
+
+/// Returns the length in bytes of the checksum trailer.
+pub fn trailer_len(checksum: Checksum) -> usize {
+    match checksum {
+        Checksum::None => 0,
+        Checksum::Crc32c => 4,
+        Checksum::XxHash64 => 8,
+    }
+}
+
+/// Split the checksum trailer from bytes and verify it.
+pub fn verify(bytes: &[u8], checksum: Checksum) -> Result<&[u8]> {
+    let len = trailer_len(checksum);
+    if bytes.len() < len {
+        return Err("checksum: missing trailer".into());
+    }
+    let (body, trailer) = bytes.split_at(bytes.len() - len);
+    let mut hasher = Hasher::new(checksum);
+    hasher.update(body);
+    verify_trailer(&hasher, trailer)?;
+    Ok(body)
+}
+
+/// Verify a checksum trailer against the bytes fed to a hasher.
+pub fn verify_trailer(hasher: &Hasher, trailer: &[u8]) -> Result<()> {
+    let expected = hasher.finish();
+    if expected != trailer {
+        let expected = trailer_value(&expected);
+        let found = trailer_value(trailer);
+        return Err(Error::new(ErrorKind::ChecksumMismatch { expected, found }));
+    }
+    Ok(())
+}
+
+fn trailer_value(trailer: &[u8]) -> u64 {
+    let mut bytes = [0; 8];
+    bytes[..trailer.len()].copy_from_slice(trailer);
+    u64::from_le_bytes(bytes)
+}
+
+/// Writer computing a checksum of the bytes written to the inner writer.
+pub struct ChecksumWriter<W: Writer> {
+    inner: W,
+    hasher: Hasher,
+}
+
+impl<W: Writer> ChecksumWriter<W> {
+    pub fn new(inner: W, checksum: Checksum) -> Self {
+        Self {
+            inner,
+            hasher: Hasher::new(checksum),
+        }
+    }
+
+    /// Returns the inner writer.
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+
+    /// Write the checksum trailer and return the inner writer.
+    pub fn finish(mut self) -> Result<W> {
+        self.inner.write_bytes(&self.hasher.finish())?;
+        Ok(self.inner)
+    }
+}
+
+impl<W: Writer> Writer for ChecksumWriter<W> {
+    fn write_bool(&mut self, value: bool) -> Result<()> {
+        self.hasher.update(&[value as u8]);
+        self.inner.write_bool(value)
+    }
+
+    fn write_i8(&mut self, value: i8) -> Result<()> {
+        self.hasher.update(&value.to_le_bytes());
+        self.inner.write_i8(value)
+    }
+
+    fn write_i16(&mut self, value: i16) -> Result<()> {
+        self.hasher.update(&value.to_le_bytes());
+        self.inner.write_i16(value)
+    }
+
+    fn write_i32(&mut self, value: i32) -> Result<()> {
+        self.hasher.update(&value.to_le_bytes());
+        self.inner.write_i32(value)
+    }
+
+    fn write_i64(&mut self, value: i64) -> Result<()> {
+        self.hasher.update(&value.to_le_bytes());
+        self.inner.write_i64(value)
+    }
+
+    fn write_u8(&mut self, value: u8) -> Result<()> {
+        self.hasher.update(&value.to_le_bytes());
+        self.inner.write_u8(value)
+    }
+
+    fn write_u16(&mut self, value: u16) -> Result<()> {
+        self.hasher.update(&value.to_le_bytes());
+        self.inner.write_u16(value)
+    }
+
+    fn write_u32(&mut self, value: u32) -> Result<()> {
+        self.hasher.update(&value.to_le_bytes());
+        self.inner.write_u32(value)
+    }
+
+    fn write_u64(&mut self, value: u64) -> Result<()> {
+        self.hasher.update(&value.to_le_bytes());
+        self.inner.write_u64(value)
+    }
+
+    fn write_f32(&mut self, value: f32) -> Result<()> {
+        self.hasher.update(&value.to_le_bytes());
+        self.inner.write_f32(value)
+    }
+
+    fn write_f64(&mut self, value: f64) -> Result<()> {
+        self.hasher.update(&value.to_le_bytes());
+        self.inner.write_f64(value)
+    }
+
+    fn write_bytes(&mut self, value: &[u8]) -> Result<()> {
+        self.hasher.update(value);
+        self.inner.write_bytes(value)
+    }
+}
+
+/// CRC-32C (Castagnoli) checksum.
+pub struct Crc32c {
+    crc: u32,
+}
+
+const CRC32C_TABLE: [u32; 256] = crc32c_table();
+
+const fn crc32c_table() -> [u32; 256] {
+    let mut table = [0; 256];
+    let mut index = 0;
+    while index < 256 {
+        let mut crc = index as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0x82f6_3b78
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[index] = crc;
+        index += 1;
+    }
+    table
+}
+
+impl Crc32c {
+    pub fn new() -> Self {
+        Self { crc: !0 }
+    }
+
+    pub fn update(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.crc = CRC32C_TABLE[((self.crc ^ *byte as u32) & 0xff) as usize] ^ (self.crc >> 8);
+        }
+    }
+
+    pub fn finish(&self) -> u32 {
+        !self.crc
+    }
+}
+
+/// XXH64 hash with a zero seed.
+pub struct XxHash64 {
+    acc: [u64; 4],
+    buf: [u8; 32],
+    buf_len: usize,
+    total_len: u64,
+}
+
+const PRIME64_1: u64 = 0x9e37_79b1_85eb_ca87;
+const PRIME64_2: u64 = 0xc2b2_ae3d_27d4_eb4f;
+const PRIME64_3: u64 = 0x1656_67b1_9e37_79f9;
+const PRIME64_4: u64 = 0x85eb_ca77_c2b2_ae63;
+const PRIME64_5: u64 = 0x27d4_eb2f_1656_67c5;
+
+fn xxh64_round(acc: u64, input: u64) -> u64 {
+    acc.wrapping_add(input.wrapping_mul(PRIME64_2))
+        .rotate_left(31)
+        .wrapping_mul(PRIME64_1)
+}
+
+fn xxh64_merge(hash: u64, acc: u64) -> u64 {
+    (hash ^ xxh64_round(0, acc))
+        .wrapping_mul(PRIME64_1)
+        .wrapping_add(PRIME64_4)
+}
+
+fn read_u64(bytes: &[u8]) -> u64 {
+    u64::from_le_bytes(bytes[..8].try_into().unwrap())
+}
+
+impl XxHash64 {
+    pub fn new() -> Self {
+        Self {
+            acc: [
+                PRIME64_1.wrapping_add(PRIME64_2),
+                PRIME64_2,
+                0,
+                PRIME64_1.wrapping_neg(),
+            ],
+            buf: [0; 32],
+            buf_len: 0,
+            total_len: 0,
+        }
+    }
+
+    fn consume(&mut self, stripe: &[u8]) {
+        for (lane, acc) in self.acc.iter_mut().enumerate() {
+            *acc = xxh64_round(*acc, read_u64(&stripe[lane * 8..]));
+        }
+    }
+
+    pub fn update(&mut self, mut bytes: &[u8]) {
+        self.total_len += bytes.len() as u64;
+        if self.buf_len > 0 {
+            let len = bytes.len().min(32 - self.buf_len);
+            self.buf[self.buf_len..self.buf_len + len].copy_from_slice(&bytes[..len]);
+            self.buf_len += len;
+            bytes = &bytes[len..];
+            if self.buf_len < 32 {
+                return;
+            }
+            let stripe = self.buf;
+            self.consume(&stripe);
+            self.buf_len = 0;
+        }
+        while bytes.len() >= 32 {
+            self.consume(&bytes[..32]);
+            bytes = &bytes[32..];
+        }
+        self.buf[..bytes.len()].copy_from_slice(bytes);
+        self.buf_len = bytes.len();
+    }
+
+    pub fn finish(&self) -> u64 {
+        let mut hash = if self.total_len >= 32 {
+            let [v1, v2, v3, v4] = self.acc;
+            let mut hash = v1
+                .rotate_left(1)
+                .wrapping_add(v2.rotate_left(7))
+                .wrapping_add(v3.rotate_left(12))
+                .wrapping_add(v4.rotate_left(18));
+            for acc in self.acc {
+                hash = xxh64_merge(hash, acc);
+            }
+            hash
+        } else {
+            PRIME64_5
+        };
+        hash = hash.wrapping_add(self.total_len);
+        let mut rest = &self.buf[..self.buf_len];
+        while rest.len() >= 8 {
+            hash ^= xxh64_round(0, read_u64(rest));
+            hash = hash
+                .rotate_left(27)
+                .wrapping_mul(PRIME64_1)
+                .wrapping_add(PRIME64_4);
+            rest = &rest[8..];
+        }
+        if rest.len() >= 4 {
+            let value = u32::from_le_bytes(rest[..4].try_into().unwrap()) as u64;
+            hash ^= value.wrapping_mul(PRIME64_1);
+            hash = hash
+                .rotate_left(23)
+                .wrapping_mul(PRIME64_2)
+                .wrapping_add(PRIME64_3);
+            rest = &rest[4..];
+        }
+        for byte in rest {
+            hash ^= (*byte as u64).wrapping_mul(PRIME64_5);
+            hash = hash.rotate_left(11).wrapping_mul(PRIME64_1);
+        }
+        hash ^= hash >> 33;
+        hash = hash.wrapping_mul(PRIME64_2);
+        hash ^= hash >> 29;
+        hash = hash.wrapping_mul(PRIME64_3);
+        hash ^= hash >> 32;
+        hash
+    }
+}
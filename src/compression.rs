@@ -0,0 +1,394 @@
+//! Compression of serialized values.
+//!
+//! Available with the `compression-lz4` and `compression-deflate` features.
+//!
+//! [`to_bytes_compressed`] serializes a value into an
+//! [`envelope`] whose body is compressed, and records the
+//! algorithm in the header so that [`from_bytes_compressed`] and
+//! [`envelope::from_bytes`] can decompress it without being told which
+//! algorithm was used. [`Encoder`] and [`Decoder`]
+//! compress and decompress streams of bytes, and values can be serialized
+//! straight into an encoder with [`to_io_writer_with`](crate::to_io_writer_with).
+//!
+//! # Examples
+//!
+//! ```
+//! // Bring serde Serialize/Deserialize derivable traits into scope.
+//! use serde::{Deserialize, Serialize};
+//! // Bring compression items into scope.
+//! use shaft::compression::{self, Compression};
+//! // Bring options into scope.
+//! use shaft::Options;
+//!
+//! // Define custom struct.
+//! #[derive(Deserialize, Serialize)]
+//! struct MyStruct {
+//!     names: Vec<String>,
+//! }
+//!
+//! // Create value with repeated strings.
+//! let value = MyStruct {
+//!     names: vec!["Ferris".to_owned(); 100],
+//! };
+//!
+//! // Serialize value into a compressed envelope.
+//! # #[cfg(feature = "compression-lz4")]
+//! let compression = Compression::Lz4;
+//! # #[cfg(not(feature = "compression-lz4"))]
+//! # let compression = Compression::Deflate;
+//! let bytes = compression::to_bytes_compressed(&value, Options::new(), compression)
+//!     .expect("serialization error");
+//! assert!(bytes.len() < shaft::to_bytes(&value).expect("serialization error").len());
+//!
+//! // Deserialize value without specifying the algorithm.
+//! let value: MyStruct = compression::from_bytes_compressed(&bytes).expect("deserialization error");
+//! assert_eq!(value.names.len(), 100);
+//! ```
+
+use std::io::{self, BufWriter, Read, Write};
+
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{envelope, error::Result, options::Options};
+
+/// Compression algorithm.
+///
+/// Variants depend on the enabled features, so matches must have a wildcard
+/// arm.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Compression {
+    /// No compression.
+    #[default]
+    None,
+    /// LZ4 frame format.
+    #[cfg(feature = "compression-lz4")]
+    Lz4,
+    /// Raw deflate stream.
+    #[cfg(feature = "compression-deflate")]
+    Deflate,
+}
+
+/// Serialize a value into an envelope compressed with the given algorithm.
+pub fn to_bytes_compressed<T>(
+    value: &T,
+    options: Options,
+    compression: Compression,
+) -> Result<Vec<u8>>
+where
+    T: Serialize,
+{
+    let mut bytes = Vec::new();
+    envelope::write_header(&mut bytes, options, encode(compression));
+    let writer = BufWriter::new(Encoder::new(bytes, compression));
+    let writer = crate::to_io_writer_with(writer, value, options)?;
+    let encoder = writer.into_inner().map_err(|error| error.into_error())?;
+    Ok(encoder.finish()?)
+}
+
+/// Default maximum length of a decompressed body.
+pub const MAX_LEN: usize = 64 * 1024 * 1024;
+
+/// Deserialize a value from an envelope, decompressing its body.
+///
+/// Uncompressed envelopes are also accepted. Fails if the decompressed body
+/// is longer than [`MAX_LEN`].
+pub fn from_bytes_compressed<T>(bytes: &[u8]) -> Result<T>
+where
+    T: DeserializeOwned,
+{
+    from_bytes_compressed_with_limit(bytes, MAX_LEN)
+}
+
+/// Deserialize a value from an envelope, decompressing a body of at most
+/// `max_len` bytes.
+pub fn from_bytes_compressed_with_limit<T>(bytes: &[u8], max_len: usize) -> Result<T>
+where
+    T: DeserializeOwned,
+{
+    let (options, compression, body) = envelope::read_header(bytes)?;
+    let body = decompress(body, compression, max_len)?;
+    crate::from_bytes_with(&body, options)
+}
+
+/// Decompress the body of an envelope given its compression byte, failing if
+/// it is longer than `max_len` bytes.
+pub(crate) fn decompress(body: &[u8], compression: u8, max_len: usize) -> Result<Vec<u8>> {
+    let decoder = Decoder::new(body, decode(compression)?);
+    let mut body = Vec::new();
+    decoder.take(max_len as u64 + 1).read_to_end(&mut body)?;
+    if body.len() > max_len {
+        return Err(format!("compression: body longer than {max_len} bytes").into());
+    }
+    Ok(body)
+}
+
+/// Returns the envelope byte of a compression algorithm.
+pub(crate) fn encode(compression: Compression) -> u8 {
+    match compression {
+        Compression::None => 0,
+        #[cfg(feature = "compression-lz4")]
+        Compression::Lz4 => 1,
+        #[cfg(feature = "compression-deflate")]
+        Compression::Deflate => 2,
+    }
+}
+
+/// Returns the compression algorithm of an envelope byte.
+pub(crate) fn decode(compression: u8) -> Result<Compression> {
+    match compression {
+        0 => Ok(Compression::None),
+        #[cfg(feature = "compression-lz4")]
+        1 => Ok(Compression::Lz4),
+        #[cfg(feature = "compression-deflate")]
+        2 => Ok(Compression::Deflate),
+        #[cfg(not(feature = "compression-lz4"))]
+        1 => Err("compression: feature compression-lz4 not enabled".into()),
+        #[cfg(not(feature = "compression-deflate"))]
+        2 => Err("compression: feature compression-deflate not enabled".into()),
+        _ => Err(format!("compression: unsupported algorithm {compression}").into()),
+    }
+}
+
+/// Writer compressing bytes into an inner writer.
+///
+/// [`Encoder::finish`] must be called to write the end of the stream.
+pub struct Encoder<W: Write> {
+    inner: EncoderInner<W>,
+}
+
+enum EncoderInner<W: Write> {
+    None(W),
+    #[cfg(feature = "compression-lz4")]
+    Lz4(lz4_flex::frame::FrameEncoder<W>),
+    #[cfg(feature = "compression-deflate")]
+    Deflate(deflate::Encoder<W>),
+}
+
+impl<W: Write> Encoder<W> {
+    /// Create a new encoder.
+    pub fn new(writer: W, compression: Compression) -> Self {
+        let inner = match compression {
+            Compression::None => EncoderInner::None(writer),
+            #[cfg(feature = "compression-lz4")]
+            Compression::Lz4 => EncoderInner::Lz4(lz4_flex::frame::FrameEncoder::new(writer)),
+            #[cfg(feature = "compression-deflate")]
+            Compression::Deflate => EncoderInner::Deflate(deflate::Encoder::new(writer)),
+        };
+        Self { inner }
+    }
+
+    /// Write the end of the stream and return the inner writer.
+    pub fn finish(self) -> io::Result<W> {
+        match self.inner {
+            EncoderInner::None(writer) => Ok(writer),
+            #[cfg(feature = "compression-lz4")]
+            EncoderInner::Lz4(encoder) => Ok(encoder.finish()?),
+            #[cfg(feature = "compression-deflate")]
+            EncoderInner::Deflate(encoder) => encoder.finish(),
+        }
+    }
+}
+
+impl<W: Write> Write for Encoder<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match &mut self.inner {
+            EncoderInner::None(writer) => writer.write(buf),
+            #[cfg(feature = "compression-lz4")]
+            EncoderInner::Lz4(encoder) => encoder.write(buf),
+            #[cfg(feature = "compression-deflate")]
+            EncoderInner::Deflate(encoder) => encoder.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match &mut self.inner {
+            EncoderInner::None(writer) => writer.flush(),
+            #[cfg(feature = "compression-lz4")]
+            EncoderInner::Lz4(encoder) => encoder.flush(),
+            #[cfg(feature = "compression-deflate")]
+            EncoderInner::Deflate(encoder) => encoder.flush(),
+        }
+    }
+}
+
+/// Reader decompressing bytes from an inner reader.
+pub struct Decoder<R: Read> {
+    inner: DecoderInner<R>,
+}
+
+enum DecoderInner<R: Read> {
+    None(R),
+    #[cfg(feature = "compression-lz4")]
+    Lz4(lz4_flex::frame::FrameDecoder<R>),
+    #[cfg(feature = "compression-deflate")]
+    Deflate(deflate::Decoder<R>),
+}
+
+impl<R: Read> Decoder<R> {
+    /// Create a new decoder.
+    pub fn new(reader: R, compression: Compression) -> Self {
+        let inner = match compression {
+            Compression::None => DecoderInner::None(reader),
+            #[cfg(feature = "compression-lz4")]
+            Compression::Lz4 => DecoderInner::Lz4(lz4_flex::frame::FrameDecoder::new(reader)),
+            #[cfg(feature = "compression-deflate")]
+            Compression::Deflate => DecoderInner::Deflate(deflate::Decoder::new(reader)),
+        };
+        Self { inner }
+    }
+}
+
+impl<R: Read> Read for Decoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match &mut self.inner {
+            DecoderInner::None(reader) => reader.read(buf),
+            #[cfg(feature = "compression-lz4")]
+            DecoderInner::Lz4(decoder) => decoder.read(buf),
+            #[cfg(feature = "compression-deflate")]
+            DecoderInner::Deflate(decoder) => decoder.read(buf),
+        }
+    }
+}
+
+#[cfg(feature = "compression-deflate")]
+mod deflate {
+    use std::io::{self, Read, Write};
+
+    use miniz_oxide::{
+        deflate::core::{
+            compress, create_comp_flags_from_zip_params, CompressorOxide, TDEFLFlush, TDEFLStatus,
+        },
+        inflate::stream::{inflate, InflateState},
+        DataFormat, MZError, MZFlush, MZStatus,
+    };
+
+    const BUF_LEN: usize = 32 * 1024;
+
+    pub struct Encoder<W: Write> {
+        writer: W,
+        compressor: Box<CompressorOxide>,
+        buf: Vec<u8>,
+    }
+
+    impl<W: Write> Encoder<W> {
+        pub fn new(writer: W) -> Self {
+            // Default level with a negative window size for a raw stream.
+            let flags = create_comp_flags_from_zip_params(6, -15, 0);
+            Self {
+                writer,
+                compressor: Box::new(CompressorOxide::new(flags)),
+                buf: vec![0; BUF_LEN],
+            }
+        }
+
+        /// Compress input, returning the status of the compressor, the number
+        /// of bytes consumed and the number of bytes written.
+        fn compress(
+            &mut self,
+            input: &[u8],
+            flush: TDEFLFlush,
+        ) -> io::Result<(TDEFLStatus, usize, usize)> {
+            let (status, consumed, written) =
+                compress(&mut self.compressor, input, &mut self.buf, flush);
+            if (status as i32) < 0 {
+                return Err(io::Error::other("deflate: compression failed"));
+            }
+            self.writer.write_all(&self.buf[..written])?;
+            Ok((status, consumed, written))
+        }
+
+        pub fn finish(mut self) -> io::Result<W> {
+            loop {
+                let (status, _, _) = self.compress(&[], TDEFLFlush::Finish)?;
+                if status == TDEFLStatus::Done {
+                    return Ok(self.writer);
+                }
+            }
+        }
+    }
+
+    impl<W: Write> Write for Encoder<W> {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            let mut input = buf;
+            while !input.is_empty() {
+                let (_, consumed, _) = self.compress(input, TDEFLFlush::None)?;
+                input = &input[consumed..];
+            }
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            // Output filling the whole buffer may be followed by more output.
+            while self.compress(&[], TDEFLFlush::Sync)?.2 == self.buf.len() {}
+            self.writer.flush()
+        }
+    }
+
+    pub struct Decoder<R: Read> {
+        reader: R,
+        state: Box<InflateState>,
+        buf: Vec<u8>,
+        pos: usize,
+        len: usize,
+        eof: bool,
+        done: bool,
+    }
+
+    impl<R: Read> Decoder<R> {
+        pub fn new(reader: R) -> Self {
+            Self {
+                reader,
+                state: InflateState::new_boxed(DataFormat::Raw),
+                buf: vec![0; BUF_LEN],
+                pos: 0,
+                len: 0,
+                eof: false,
+                done: false,
+            }
+        }
+    }
+
+    impl<R: Read> Read for Decoder<R> {
+        fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+            if out.is_empty() {
+                return Ok(0);
+            }
+            while !self.done {
+                if self.pos == self.len && !self.eof {
+                    self.len = self.reader.read(&mut self.buf)?;
+                    self.pos = 0;
+                    self.eof = self.len == 0;
+                }
+                let flush = if self.eof {
+                    MZFlush::Finish
+                } else {
+                    MZFlush::None
+                };
+                let result = inflate(&mut self.state, &self.buf[self.pos..self.len], out, flush);
+                self.pos += result.bytes_consumed;
+                match result.status {
+                    Ok(MZStatus::StreamEnd) => self.done = true,
+                    Ok(_) | Err(MZError::Buf) => {
+                        if self.eof && result.bytes_consumed == 0 && result.bytes_written == 0 {
+                            return Err(io::Error::new(
+                                io::ErrorKind::UnexpectedEof,
+                                "deflate: truncated stream",
+                            ));
+                        }
+                    }
+                    Err(_) => {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            "deflate: invalid stream",
+                        ))
+                    }
+                }
+                if result.bytes_written > 0 {
+                    return Ok(result.bytes_written);
+                }
+            }
+            Ok(0)
+        }
+    }
+}
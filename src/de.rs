@@ -1,10 +1,28 @@
-use std::marker::PhantomData;
+use std::{
+    cmp::Ordering,
+    fmt::{self, Display},
+    io,
+    marker::PhantomData,
+};
 
 use crate::{
+    checksum,
     error::{Error, Result},
+    lazy,
+    options::{
+        name_hash, Checksum, Endian, FieldTag, IntEncoding, LenWidth, Options, StructEncoding,
+        VariantEncoding,
+    },
+    raw,
     read::{Reader, SliceReader},
+    ser::{canonical_f32, canonical_f64},
+    with,
+};
+use serde::de::{
+    self,
+    value::{BorrowedBytesDeserializer, BorrowedStrDeserializer, U32Deserializer},
+    IntoDeserializer,
 };
-use serde::de::{self, value::U32Deserializer, IntoDeserializer};
 
 /// Deserialize a value from bytes.
 ///
@@ -30,22 +48,523 @@ where
 {
     let reader = SliceReader::new(bytes);
     let mut deserializer = Deserializer::new(reader);
-    let value = de::Deserialize::deserialize(&mut deserializer)?;
+    let value = de::Deserialize::deserialize(&mut deserializer)
+        .map_err(|error| deserializer.locate(error))?;
+    Ok(value)
+}
+
+/// Deserialize a value from bytes with the given options.
+///
+/// # Examples
+///
+/// ```
+/// // Bring serde Serialize/Deserialize derivable traits into scope.
+/// use serde::Deserialize;
+/// // Bring options into scope.
+/// use shaft::{FieldTag, Options, StructEncoding};
+///
+/// // Define custom struct with a field missing from the bytes.
+/// #[derive(Deserialize)]
+/// struct MyStruct {
+///     score: u8,
+///     name: Option<String>,
+/// }
+///
+/// // Create bytes to deserialize.
+/// let bytes = [1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 42];
+///
+/// // Deserialize value from bytes with tagged fields.
+/// let options = Options::new().with_struct_encoding(StructEncoding::Tagged(FieldTag::Index));
+/// let value: MyStruct = shaft::from_bytes_with(&bytes, options).expect("deserialization error");
+///
+/// // Check struct fields.
+/// assert_eq!(value.score, 42);
+/// assert_eq!(value.name, None);
+/// ```
+pub fn from_bytes_with<'a, T>(bytes: &'a [u8], options: Options) -> Result<T>
+where
+    T: de::Deserialize<'a>,
+{
+    let bytes = checksum::verify(bytes, options.checksum())?;
+    let reader = SliceReader::new(bytes);
+    let mut deserializer = Deserializer::with_options(reader, options);
+    let value = de::Deserialize::deserialize(&mut deserializer)
+        .map_err(|error| deserializer.locate(error))?;
+    if options.canonical() {
+        deserializer.end()?;
+    }
+    Ok(value)
+}
+
+/// Deserialize a value from bytes with a seed.
+///
+/// # Examples
+///
+/// ```
+/// // Bring std collections into scope.
+/// use std::collections::HashSet;
+/// // Bring serde deserialization traits into scope.
+/// use serde::de::{Deserialize, DeserializeSeed, Deserializer};
+///
+/// // Define seed interning the deserialized string.
+/// struct Intern<'s>(&'s mut HashSet<String>);
+///
+/// impl<'de> DeserializeSeed<'de> for Intern<'_> {
+///     type Value = bool;
+///
+///     fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<bool, D::Error> {
+///         let value = String::deserialize(deserializer)?;
+///         Ok(self.0.insert(value))
+///     }
+/// }
+///
+/// // Deserialize strings with the interner.
+/// let mut interner = HashSet::new();
+/// let bytes = shaft::to_bytes(&"Ferris").expect("serialization error");
+/// assert!(shaft::from_bytes_seed(Intern(&mut interner), &bytes).expect("deserialization error"));
+/// assert!(!shaft::from_bytes_seed(Intern(&mut interner), &bytes).expect("deserialization error"));
+/// ```
+pub fn from_bytes_seed<'a, S>(seed: S, bytes: &'a [u8]) -> Result<S::Value>
+where
+    S: de::DeserializeSeed<'a>,
+{
+    from_bytes_seed_with(seed, bytes, Options::default())
+}
+
+/// Deserialize a value from bytes with a seed and the given options.
+pub fn from_bytes_seed_with<'a, S>(seed: S, bytes: &'a [u8], options: Options) -> Result<S::Value>
+where
+    S: de::DeserializeSeed<'a>,
+{
+    let mut deserializer = Deserializer::from_bytes_with(bytes, options)?;
+    let value = seed
+        .deserialize(&mut deserializer)
+        .map_err(|error| deserializer.locate(error))?;
+    if options.canonical() {
+        deserializer.end()?;
+    }
     Ok(value)
 }
 
-pub struct Deserializer<'a, R: Reader<'a>> {
+/// Deserialize a value read from a reader with a seed.
+///
+/// The reader is read to its end before decoding, so values borrowing from
+/// the input cannot be produced.
+pub fn from_reader_seed<R, S, V>(seed: S, reader: R) -> Result<V>
+where
+    R: io::Read,
+    S: for<'a> de::DeserializeSeed<'a, Value = V>,
+{
+    from_reader_seed_with(seed, reader, Options::default())
+}
+
+/// Deserialize a value read from a reader with a seed and the given options.
+///
+/// See [`from_reader_seed`].
+pub fn from_reader_seed_with<R, S, V>(seed: S, mut reader: R, options: Options) -> Result<V>
+where
+    R: io::Read,
+    S: for<'a> de::DeserializeSeed<'a, Value = V>,
+{
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes)?;
+    from_bytes_seed_with(seed, &bytes, options)
+}
+
+/// Deserialize bytes into an existing value, reusing its allocations.
+///
+/// Strings and vectors keep their capacity, and existing elements of vectors
+/// are deserialized in place. Derived implementations only deserialize fields
+/// in place when the `deserialize_in_place` feature of `serde_derive` is
+/// enabled, and replace the whole value otherwise.
+///
+/// On error, the value is left in a valid but unspecified state.
+///
+/// # Examples
+///
+/// ```
+/// // Create value to reuse.
+/// let mut names: Vec<String> = Vec::new();
+///
+/// // Deserialize several values into it.
+/// for expected in [vec!["Ferris", "Corro"], vec!["Crab"]] {
+///     let bytes = shaft::to_bytes(&expected).expect("serialization error");
+///     shaft::from_bytes_in_place(&mut names, &bytes).expect("deserialization error");
+///     assert_eq!(names, expected);
+/// }
+///
+/// // The vector kept the capacity of the first value.
+/// assert!(names.capacity() >= 2);
+/// ```
+pub fn from_bytes_in_place<'a, T>(place: &mut T, bytes: &'a [u8]) -> Result<()>
+where
+    T: de::Deserialize<'a>,
+{
+    from_bytes_in_place_with(place, bytes, Options::default())
+}
+
+/// Deserialize bytes into an existing value with the given options, reusing
+/// its allocations.
+///
+/// See [`from_bytes_in_place`].
+pub fn from_bytes_in_place_with<'a, T>(
+    place: &mut T,
+    bytes: &'a [u8],
+    options: Options,
+) -> Result<()>
+where
+    T: de::Deserialize<'a>,
+{
+    let bytes = checksum::verify(bytes, options.checksum())?;
+    let reader = SliceReader::new(bytes);
+    let mut deserializer = Deserializer::with_options(reader, options);
+    de::Deserialize::deserialize_in_place(&mut deserializer, place)
+        .map_err(|error| deserializer.locate(error))?;
+    if options.canonical() {
+        deserializer.end()?;
+    }
+    Ok(())
+}
+
+/// Shaft deserializer.
+///
+/// `&mut Deserializer` implements [`serde::Deserializer`], so values can be
+/// decoded one after the other from the same input, or with a
+/// [`DeserializeSeed`](de::DeserializeSeed). Input is read from a
+/// [`SliceReader`].
+///
+/// # Examples
+///
+/// ```
+/// // Bring serde Deserialize trait into scope.
+/// use serde::Deserialize;
+/// // Bring deserializer into scope.
+/// use shaft::Deserializer;
+///
+/// // Serialize two values one after the other.
+/// let mut bytes = shaft::to_bytes(&42u8).expect("serialization error");
+/// bytes.extend(shaft::to_bytes(&"Ferris").expect("serialization error"));
+///
+/// // Deserialize values one after the other.
+/// let mut deserializer = Deserializer::from_bytes(&bytes);
+/// let score = u8::deserialize(&mut deserializer).expect("deserialization error");
+/// assert_eq!(deserializer.position(), 1);
+/// let name = <&str>::deserialize(&mut deserializer).expect("deserialization error");
+/// deserializer.end().expect("trailing bytes");
+/// assert_eq!((score, name), (42, "Ferris"));
+/// ```
+pub struct Deserializer<'a, R: Reader<'a> = SliceReader<'a>> {
     reader: R,
+    options: Options,
+    /// Offset of the reader in the input.
+    base: usize,
     phantom: PhantomData<&'a ()>,
 }
 
+/// Segment of the path of a value being deserialized.
+#[derive(Clone, Copy)]
+enum Segment<'s> {
+    Field(&'static str),
+    Index(usize),
+    Key(usize),
+    Value(usize),
+    Variant(&'s str),
+}
+
+/// Enum variant read from the input.
+#[derive(Clone, Copy)]
+pub(crate) enum Variant<'a> {
+    Index(u32),
+    Name(&'a str),
+}
+
+impl Display for Segment<'_> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Segment::Field(field) => write!(fmt, ".{field}"),
+            Segment::Index(index) => write!(fmt, "[{index}]"),
+            Segment::Key(index) => write!(fmt, "[{index}].key"),
+            Segment::Value(index) => write!(fmt, "[{index}].value"),
+            Segment::Variant(variant) => write!(fmt, "::{variant}"),
+        }
+    }
+}
+
+impl<'a> Deserializer<'a, SliceReader<'a>> {
+    /// Create a deserializer of bytes with default options.
+    pub fn from_bytes(bytes: &'a [u8]) -> Self {
+        Self::new(SliceReader::new(bytes))
+    }
+
+    /// Create a deserializer of bytes with the given options, verifying and
+    /// splitting the checksum trailer.
+    pub fn from_bytes_with(bytes: &'a [u8], options: Options) -> Result<Self> {
+        let bytes = checksum::verify(bytes, options.checksum())?;
+        Ok(Self::with_options(SliceReader::new(bytes), options))
+    }
+
+    /// Returns whether decoding failed because it ran past the end of the
+    /// bytes.
+    pub(crate) fn reached_end(&self) -> bool {
+        self.reader.reached_end()
+    }
+}
+
 impl<'a, R: Reader<'a>> Deserializer<'a, R> {
+    /// Create a deserializer with default options.
     pub fn new(reader: R) -> Self {
+        Self::with_options(reader, Options::default())
+    }
+
+    /// Create a deserializer with the given options.
+    pub fn with_options(reader: R, options: Options) -> Self {
         Self {
             reader,
+            options,
+            base: 0,
             phantom: PhantomData,
         }
     }
+
+    /// Attach the current offset to an error.
+    pub(crate) fn locate(&self, error: Error) -> Error {
+        error.locate(self.position())
+    }
+
+    /// Deserialize a value nested under a path segment, locating errors and
+    /// prepending the segment to their path.
+    fn nested<T, F>(&mut self, segment: Segment<'_>, deserialize: F) -> Result<T>
+    where
+        F: FnOnce(&mut Self) -> Result<T>,
+    {
+        deserialize(self).map_err(|error| self.locate(error).prefix(segment))
+    }
+
+    fn is_varint(&self) -> bool {
+        self.options.int_encoding() == IntEncoding::Varint
+    }
+
+    fn is_big_endian(&self) -> bool {
+        self.options.endian() == Endian::Big
+    }
+
+    fn read_array<const N: usize>(&mut self) -> Result<[u8; N]> {
+        let bytes = self.reader.read_bytes(N)?;
+        Ok(bytes.try_into().unwrap())
+    }
+
+    fn read_varint(&mut self) -> Result<u64> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.reader.read_u8()?;
+            if shift == 63 && byte > 1 {
+                break;
+            }
+            value |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                if self.options.canonical() && byte == 0 && shift > 0 {
+                    return Err("deserialize: non-canonical varint".into());
+                }
+                return Ok(value);
+            }
+        }
+        Err("deserialize: invalid varint".into())
+    }
+
+    fn read_i16(&mut self) -> Result<i16> {
+        if self.is_varint() {
+            let value = unzigzag(self.read_varint()?);
+            i16::try_from(value).map_err(|_| "deserialize: varint out of range".into())
+        } else if self.is_big_endian() {
+            Ok(i16::from_be_bytes(self.read_array()?))
+        } else {
+            self.reader.read_i16()
+        }
+    }
+
+    fn read_i32(&mut self) -> Result<i32> {
+        if self.is_varint() {
+            let value = unzigzag(self.read_varint()?);
+            i32::try_from(value).map_err(|_| "deserialize: varint out of range".into())
+        } else if self.is_big_endian() {
+            Ok(i32::from_be_bytes(self.read_array()?))
+        } else {
+            self.reader.read_i32()
+        }
+    }
+
+    fn read_i64(&mut self) -> Result<i64> {
+        if self.is_varint() {
+            Ok(unzigzag(self.read_varint()?))
+        } else if self.is_big_endian() {
+            Ok(i64::from_be_bytes(self.read_array()?))
+        } else {
+            self.reader.read_i64()
+        }
+    }
+
+    fn read_u16(&mut self) -> Result<u16> {
+        if self.is_varint() {
+            let value = self.read_varint()?;
+            u16::try_from(value).map_err(|_| "deserialize: varint out of range".into())
+        } else if self.is_big_endian() {
+            Ok(u16::from_be_bytes(self.read_array()?))
+        } else {
+            self.reader.read_u16()
+        }
+    }
+
+    fn read_u32(&mut self) -> Result<u32> {
+        if self.is_varint() {
+            let value = self.read_varint()?;
+            u32::try_from(value).map_err(|_| "deserialize: varint out of range".into())
+        } else if self.is_big_endian() {
+            Ok(u32::from_be_bytes(self.read_array()?))
+        } else {
+            self.reader.read_u32()
+        }
+    }
+
+    fn read_u64(&mut self) -> Result<u64> {
+        if self.is_varint() {
+            self.read_varint()
+        } else if self.is_big_endian() {
+            Ok(u64::from_be_bytes(self.read_array()?))
+        } else {
+            self.reader.read_u64()
+        }
+    }
+
+    fn read_f32(&mut self) -> Result<f32> {
+        let value = if self.is_big_endian() {
+            f32::from_be_bytes(self.read_array()?)
+        } else {
+            self.reader.read_f32()?
+        };
+        if self.options.canonical() && value.to_bits() != canonical_f32(value).to_bits() {
+            return Err("deserialize: non-canonical float".into());
+        }
+        Ok(value)
+    }
+
+    fn read_f64(&mut self) -> Result<f64> {
+        let value = if self.is_big_endian() {
+            f64::from_be_bytes(self.read_array()?)
+        } else {
+            self.reader.read_f64()?
+        };
+        if self.options.canonical() && value.to_bits() != canonical_f64(value).to_bits() {
+            return Err("deserialize: non-canonical float".into());
+        }
+        Ok(value)
+    }
+
+    /// Returns the offset of the deserializer in the input, the offset
+    /// reported by errors.
+    pub fn position(&self) -> usize {
+        self.base + self.reader.position()
+    }
+
+    /// Returns the options of the deserializer.
+    pub fn options(&self) -> Options {
+        self.options
+    }
+
+    /// Check that all bytes were read.
+    pub fn end(&mut self) -> Result<()> {
+        if self.reader.is_empty()? {
+            Ok(())
+        } else {
+            Err(self.locate("deserialize: trailing bytes".into()))
+        }
+    }
+
+    pub(crate) fn set_options(&mut self, options: Options) {
+        self.options = options;
+    }
+
+    pub(crate) fn read_bool(&mut self) -> Result<bool> {
+        self.reader.read_bool()
+    }
+
+    pub(crate) fn read_bytes(&mut self, len: usize) -> Result<&'a [u8]> {
+        self.reader.read_bytes(len)
+    }
+
+    /// Read an enum variant, returning its index or its name.
+    ///
+    /// Names are not resolved, so that aliases and fallback variants apply.
+    /// Hashes are resolved to the name of the variant they match.
+    pub(crate) fn read_variant(
+        &mut self,
+        variants: &'static [&'static str],
+    ) -> Result<Variant<'a>> {
+        match self.options.variant_encoding() {
+            VariantEncoding::Index => Ok(Variant::Index(self.read_u32()?)),
+            VariantEncoding::Name => {
+                let len = self.read_len()?;
+                let bytes = self.reader.read_bytes(len)?;
+                match std::str::from_utf8(bytes) {
+                    Ok(name) => Ok(Variant::Name(name)),
+                    Err(_) => Err("deserialize: invalid variant name".into()),
+                }
+            }
+            VariantEncoding::Hash => {
+                let hash = self.read_u32()?;
+                match variants.iter().find(|variant| name_hash(variant) == hash) {
+                    Some(variant) => Ok(Variant::Name(variant)),
+                    None => Err("deserialize: unknown variant".into()),
+                }
+            }
+        }
+    }
+
+    /// Deserialize the element at `index` of a top-level sequence, locating
+    /// errors.
+    pub(crate) fn element<T>(&mut self, index: usize) -> Result<T>
+    where
+        T: de::Deserialize<'a>,
+    {
+        self.nested(Segment::Index(index), |de| T::deserialize(de))
+    }
+
+    /// Set the offset of the reader in the input.
+    pub(crate) fn set_base(&mut self, base: usize) {
+        self.base = base;
+    }
+
+    pub(crate) fn read_len(&mut self) -> Result<usize> {
+        let len = match self.options.len_width() {
+            LenWidth::U8 => self.reader.read_u8()? as u64,
+            LenWidth::U16 => self.read_u16()? as u64,
+            LenWidth::U32 => self.read_u32()? as u64,
+            LenWidth::U64 => self.read_u64()?,
+        };
+        usize::try_from(len).map_err(|_| "deserialize: length out of range".into())
+    }
+
+    pub(crate) fn deserialize_fields<V>(
+        &mut self,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: de::Visitor<'a>,
+    {
+        match self.options.struct_encoding() {
+            StructEncoding::Positional => {
+                let trailing = self.options.trailing_defaults();
+                let mut access = SeqAccess::new(self, fields.len());
+                access.fields = Some(fields);
+                access.trailing = trailing;
+                visitor.visit_seq(access)
+            }
+            StructEncoding::Tagged(tag) => {
+                let len = self.read_len()?;
+                visitor.visit_map(FieldAccess::new(self, fields, tag, len))
+            }
+        }
+    }
 }
 
 impl<'de: 'a, 'a, R: Reader<'de>> de::Deserializer<'de> for &'a mut Deserializer<'de, R> {
@@ -78,7 +597,7 @@ impl<'de: 'a, 'a, R: Reader<'de>> de::Deserializer<'de> for &'a mut Deserializer
     where
         V: de::Visitor<'de>,
     {
-        let value = self.reader.read_i16()?;
+        let value = self.read_i16()?;
         visitor.visit_i16(value)
     }
 
@@ -86,7 +605,7 @@ impl<'de: 'a, 'a, R: Reader<'de>> de::Deserializer<'de> for &'a mut Deserializer
     where
         V: de::Visitor<'de>,
     {
-        let value = self.reader.read_i32()?;
+        let value = self.read_i32()?;
         visitor.visit_i32(value)
     }
 
@@ -94,7 +613,7 @@ impl<'de: 'a, 'a, R: Reader<'de>> de::Deserializer<'de> for &'a mut Deserializer
     where
         V: de::Visitor<'de>,
     {
-        let value = self.reader.read_i64()?;
+        let value = self.read_i64()?;
         visitor.visit_i64(value)
     }
 
@@ -110,7 +629,7 @@ impl<'de: 'a, 'a, R: Reader<'de>> de::Deserializer<'de> for &'a mut Deserializer
     where
         V: de::Visitor<'de>,
     {
-        let value = self.reader.read_u16()?;
+        let value = self.read_u16()?;
         visitor.visit_u16(value)
     }
 
@@ -118,7 +637,7 @@ impl<'de: 'a, 'a, R: Reader<'de>> de::Deserializer<'de> for &'a mut Deserializer
     where
         V: de::Visitor<'de>,
     {
-        let value = self.reader.read_u32()?;
+        let value = self.read_u32()?;
         visitor.visit_u32(value)
     }
 
@@ -126,7 +645,7 @@ impl<'de: 'a, 'a, R: Reader<'de>> de::Deserializer<'de> for &'a mut Deserializer
     where
         V: de::Visitor<'de>,
     {
-        let value = self.reader.read_u64()?;
+        let value = self.read_u64()?;
         visitor.visit_u64(value)
     }
 
@@ -134,7 +653,7 @@ impl<'de: 'a, 'a, R: Reader<'de>> de::Deserializer<'de> for &'a mut Deserializer
     where
         V: de::Visitor<'de>,
     {
-        let value = self.reader.read_f32()?;
+        let value = self.read_f32()?;
         visitor.visit_f32(value)
     }
 
@@ -142,7 +661,7 @@ impl<'de: 'a, 'a, R: Reader<'de>> de::Deserializer<'de> for &'a mut Deserializer
     where
         V: de::Visitor<'de>,
     {
-        let value = self.reader.read_f64()?;
+        let value = self.read_f64()?;
         visitor.visit_f64(value)
     }
 
@@ -150,7 +669,7 @@ impl<'de: 'a, 'a, R: Reader<'de>> de::Deserializer<'de> for &'a mut Deserializer
     where
         V: de::Visitor<'de>,
     {
-        let value = self.reader.read_u32()?;
+        let value = self.read_u32()?;
         match char::from_u32(value) {
             Some(value) => visitor.visit_char(value),
             None => Err("deserialize: invalid char".into()),
@@ -161,7 +680,7 @@ impl<'de: 'a, 'a, R: Reader<'de>> de::Deserializer<'de> for &'a mut Deserializer
     where
         V: de::Visitor<'de>,
     {
-        let len = self.reader.read_u64()? as usize;
+        let len = self.read_len()?;
         let bytes = self.reader.read_bytes(len)?;
         let value = match std::str::from_utf8(bytes) {
             Ok(value) => value,
@@ -181,7 +700,7 @@ impl<'de: 'a, 'a, R: Reader<'de>> de::Deserializer<'de> for &'a mut Deserializer
     where
         V: de::Visitor<'de>,
     {
-        let len = self.reader.read_u64()? as usize;
+        let len = self.read_len()?;
         let bytes = self.reader.read_bytes(len)?;
         visitor.visit_borrowed_bytes(bytes)
     }
@@ -219,18 +738,37 @@ impl<'de: 'a, 'a, R: Reader<'de>> de::Deserializer<'de> for &'a mut Deserializer
         visitor.visit_unit()
     }
 
-    fn deserialize_newtype_struct<V>(self, _: &'static str, visitor: V) -> Result<V::Value>
+    fn deserialize_newtype_struct<V>(self, name: &'static str, visitor: V) -> Result<V::Value>
     where
         V: de::Visitor<'de>,
     {
-        visitor.visit_newtype_struct(self)
+        if name == lazy::NAME {
+            let len = self.read_len()?;
+            let bytes = self.reader.read_bytes(len)?;
+            return lazy::visit(visitor, bytes, self.options);
+        }
+        if name == raw::NAME {
+            let start = self.reader.position();
+            return visitor.visit_seq(RawAccess {
+                de: self,
+                start,
+                index: 0,
+            });
+        }
+        let Some(options) = with::adapt(name, self.options) else {
+            return visitor.visit_newtype_struct(self);
+        };
+        let outer = std::mem::replace(&mut self.options, options);
+        let result = visitor.visit_newtype_struct(&mut *self);
+        self.options = outer;
+        result
     }
 
     fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value>
     where
         V: de::Visitor<'de>,
     {
-        let len = self.reader.read_u64()? as usize;
+        let len = self.read_len()?;
         visitor.visit_seq(SeqAccess::new(self, len))
     }
 
@@ -257,7 +795,7 @@ impl<'de: 'a, 'a, R: Reader<'de>> de::Deserializer<'de> for &'a mut Deserializer
     where
         V: de::Visitor<'de>,
     {
-        let len = self.reader.read_u64()? as usize;
+        let len = self.read_len()?;
         visitor.visit_map(MapAccess::new(self, len))
     }
 
@@ -270,19 +808,19 @@ impl<'de: 'a, 'a, R: Reader<'de>> de::Deserializer<'de> for &'a mut Deserializer
     where
         V: de::Visitor<'de>,
     {
-        self.deserialize_tuple(fields.len(), visitor)
+        self.deserialize_fields(fields, visitor)
     }
 
     fn deserialize_enum<V>(
         self,
         _: &'static str,
-        _: &'static [&'static str],
+        variants: &'static [&'static str],
         visitor: V,
     ) -> Result<V::Value>
     where
         V: de::Visitor<'de>,
     {
-        visitor.visit_enum(EnumAccess::new(self))
+        visitor.visit_enum(EnumAccess::new(self, variants))
     }
 
     fn deserialize_identifier<V>(self, _: V) -> Result<V::Value>
@@ -298,11 +836,20 @@ impl<'de: 'a, 'a, R: Reader<'de>> de::Deserializer<'de> for &'a mut Deserializer
     {
         unimplemented!()
     }
+
+    fn is_human_readable(&self) -> bool {
+        self.options.human_readable()
+    }
 }
 
 struct SeqAccess<'a, 'b: 'a, R: Reader<'b>> {
     de: &'a mut Deserializer<'b, R>,
     len: usize,
+    index: usize,
+    /// Names of the elements when they are the fields of a struct.
+    fields: Option<&'static [&'static str]>,
+    /// End the sequence early when the input is exhausted.
+    trailing: bool,
 }
 
 impl<'a, 'b, R: Reader<'b>> SeqAccess<'a, 'b, R> {
@@ -310,6 +857,9 @@ impl<'a, 'b, R: Reader<'b>> SeqAccess<'a, 'b, R> {
         Self {
             de: deserializer,
             len,
+            index: 0,
+            fields: None,
+            trailing: false,
         }
     }
 }
@@ -321,12 +871,20 @@ impl<'a, 'b, R: Reader<'b>> de::SeqAccess<'b> for SeqAccess<'a, 'b, R> {
     where
         T: de::DeserializeSeed<'b>,
     {
-        if self.len > 0 {
-            self.len -= 1;
-            Ok(Some(seed.deserialize(&mut *self.de)?))
-        } else {
-            Ok(None)
+        if self.trailing && self.len > 0 && self.de.reader.is_empty()? {
+            self.len = 0;
         }
+        if self.len == 0 {
+            return Ok(None);
+        }
+        self.len -= 1;
+        let segment = match self.fields {
+            Some(fields) => Segment::Field(fields[self.index]),
+            None => Segment::Index(self.index),
+        };
+        self.index += 1;
+        let value = self.de.nested(segment, |de| seed.deserialize(de))?;
+        Ok(Some(value))
     }
 
     fn size_hint(&self) -> Option<usize> {
@@ -334,9 +892,43 @@ impl<'a, 'b, R: Reader<'b>> de::SeqAccess<'b> for SeqAccess<'a, 'b, R> {
     }
 }
 
+/// Access to a raw value: the value itself, the options and the bytes it
+/// spans.
+struct RawAccess<'a, 'b: 'a, R: Reader<'b>> {
+    de: &'a mut Deserializer<'b, R>,
+    start: usize,
+    index: usize,
+}
+
+impl<'a, 'b, R: Reader<'b>> de::SeqAccess<'b> for RawAccess<'a, 'b, R> {
+    type Error = Error;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>>
+    where
+        T: de::DeserializeSeed<'b>,
+    {
+        self.index += 1;
+        let value = match self.index {
+            1 => seed.deserialize(&mut *self.de)?,
+            2 => {
+                let bits = self.de.options.with_checksum(Checksum::None).to_bits();
+                seed.deserialize(U32Deserializer::<Error>::new(bits))?
+            }
+            3 => {
+                let bytes = self.de.reader.slice(self.start, self.de.reader.position());
+                seed.deserialize(BorrowedBytesDeserializer::<Error>::new(bytes))?
+            }
+            _ => return Ok(None),
+        };
+        Ok(Some(value))
+    }
+}
+
 struct MapAccess<'a, 'b: 'a, R: Reader<'b>> {
     de: &'a mut Deserializer<'b, R>,
     len: usize,
+    index: usize,
+    last_key: Option<&'b [u8]>,
 }
 
 impl<'a, 'b, R: Reader<'b>> MapAccess<'a, 'b, R> {
@@ -344,6 +936,8 @@ impl<'a, 'b, R: Reader<'b>> MapAccess<'a, 'b, R> {
         Self {
             de: deserializer,
             len,
+            index: 0,
+            last_key: None,
         }
     }
 }
@@ -355,19 +949,115 @@ impl<'a, 'b, R: Reader<'b>> de::MapAccess<'b> for MapAccess<'a, 'b, R> {
     where
         K: de::DeserializeSeed<'b>,
     {
-        if self.len > 0 {
+        if self.len == 0 {
+            return Ok(None);
+        }
+        self.len -= 1;
+        let last_key = self.last_key;
+        let (key, bytes) = self.de.nested(Segment::Key(self.index), |de| {
+            let start = de.reader.position();
+            let key = seed.deserialize(&mut *de)?;
+            let bytes = de.reader.slice(start, de.reader.position());
+            if let Some(last_key) = last_key.filter(|_| de.options.canonical()) {
+                match last_key.cmp(bytes) {
+                    Ordering::Less => {}
+                    Ordering::Equal => return Err("deserialize: duplicate map key".into()),
+                    Ordering::Greater => return Err("deserialize: unsorted map keys".into()),
+                }
+            }
+            Ok((key, bytes))
+        })?;
+        self.last_key = Some(bytes);
+        Ok(Some(key))
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value>
+    where
+        V: de::DeserializeSeed<'b>,
+    {
+        let index = self.index;
+        self.index += 1;
+        self.de
+            .nested(Segment::Value(index), |de| seed.deserialize(de))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.len)
+    }
+}
+
+struct FieldAccess<'a, 'b: 'a, R: Reader<'b>> {
+    de: &'a mut Deserializer<'b, R>,
+    fields: &'static [&'static str],
+    tag: FieldTag,
+    len: usize,
+    /// Position in `fields` of the last field read.
+    last: Option<usize>,
+    /// Name, offset and bytes of the value of the current field.
+    value: Option<(&'static str, usize, &'b [u8])>,
+}
+
+impl<'a, 'b, R: Reader<'b>> FieldAccess<'a, 'b, R> {
+    fn new(
+        deserializer: &'a mut Deserializer<'b, R>,
+        fields: &'static [&'static str],
+        tag: FieldTag,
+        len: usize,
+    ) -> Self {
+        Self {
+            de: deserializer,
+            fields,
+            tag,
+            len,
+            last: None,
+            value: None,
+        }
+    }
+}
+
+impl<'a, 'b, R: Reader<'b>> de::MapAccess<'b> for FieldAccess<'a, 'b, R> {
+    type Error = Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>>
+    where
+        K: de::DeserializeSeed<'b>,
+    {
+        while self.len > 0 {
             self.len -= 1;
-            Ok(Some(seed.deserialize(&mut *self.de)?))
-        } else {
-            Ok(None)
+            let tag = self.de.read_u32()?;
+            let len = self.de.read_len()?;
+            let offset = self.de.position();
+            let bytes = self.de.reader.read_bytes(len)?;
+            let position = match self.tag {
+                FieldTag::Hash => self.fields.iter().position(|field| name_hash(field) == tag),
+                FieldTag::Index => Some(tag as usize).filter(|&index| index < self.fields.len()),
+            };
+            if let Some(position) = position {
+                if self.de.options.canonical() && self.last.is_some_and(|last| last >= position) {
+                    return Err("deserialize: unsorted or duplicate field".into());
+                }
+                self.last = Some(position);
+                let field = self.fields[position];
+                self.value = Some((field, offset, bytes));
+                let key = BorrowedStrDeserializer::<Error>::new(field);
+                return Ok(Some(seed.deserialize(key)?));
+            }
         }
+        Ok(None)
     }
 
     fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value>
     where
         V: de::DeserializeSeed<'b>,
     {
-        seed.deserialize(&mut *self.de)
+        let (field, offset, bytes) = match self.value.take() {
+            Some(value) => value,
+            None => return Err("deserialize: missing field value".into()),
+        };
+        let reader = SliceReader::new(bytes);
+        let mut deserializer = Deserializer::with_options(reader, self.de.options);
+        deserializer.base = offset;
+        deserializer.nested(Segment::Field(field), |de| seed.deserialize(de))
     }
 
     fn size_hint(&self) -> Option<usize> {
@@ -377,11 +1067,17 @@ impl<'a, 'b, R: Reader<'b>> de::MapAccess<'b> for MapAccess<'a, 'b, R> {
 
 struct EnumAccess<'a, 'b: 'a, R: Reader<'b>> {
     de: &'a mut Deserializer<'b, R>,
+    variants: &'static [&'static str],
+    variant: &'b str,
 }
 
 impl<'a, 'b: 'a, R: Reader<'b>> EnumAccess<'a, 'b, R> {
-    fn new(de: &'a mut Deserializer<'b, R>) -> Self {
-        Self { de }
+    fn new(de: &'a mut Deserializer<'b, R>, variants: &'static [&'static str]) -> Self {
+        Self {
+            de,
+            variants,
+            variant: "",
+        }
     }
 }
 
@@ -390,12 +1086,20 @@ impl<'a, 'b: 'a, R: Reader<'b>> de::EnumAccess<'b> for EnumAccess<'a, 'b, R> {
 
     type Variant = Self;
 
-    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self::Variant)>
+    fn variant_seed<V>(mut self, seed: V) -> Result<(V::Value, Self::Variant)>
     where
         V: de::DeserializeSeed<'b>,
     {
-        let variant = self.de.reader.read_u32()?;
-        let value = seed.deserialize::<U32Deserializer<Error>>(variant.into_deserializer())?;
+        let value = match self.de.read_variant(self.variants)? {
+            Variant::Index(index) => {
+                self.variant = self.variants.get(index as usize).copied().unwrap_or("");
+                seed.deserialize::<U32Deserializer<Error>>(index.into_deserializer())?
+            }
+            Variant::Name(name) => {
+                self.variant = name;
+                seed.deserialize(BorrowedStrDeserializer::<Error>::new(name))?
+            }
+        };
         Ok((value, self))
     }
 }
@@ -411,14 +1115,18 @@ impl<'a, 'b: 'a, R: Reader<'b>> de::VariantAccess<'b> for EnumAccess<'a, 'b, R>
     where
         T: de::DeserializeSeed<'b>,
     {
-        seed.deserialize(&mut *self.de)
+        let segment = Segment::Variant(self.variant);
+        self.de.nested(segment, |de| seed.deserialize(de))
     }
 
     fn tuple_variant<V>(self, len: usize, visitor: V) -> Result<V::Value>
     where
         V: de::Visitor<'b>,
     {
-        de::Deserializer::deserialize_tuple(self.de, len, visitor)
+        let segment = Segment::Variant(self.variant);
+        self.de.nested(segment, |de| {
+            de::Deserializer::deserialize_tuple(de, len, visitor)
+        })
     }
 
     fn struct_variant<V>(
@@ -429,6 +1137,13 @@ impl<'a, 'b: 'a, R: Reader<'b>> de::VariantAccess<'b> for EnumAccess<'a, 'b, R>
     where
         V: de::Visitor<'b>,
     {
-        de::Deserializer::deserialize_tuple(self.de, fields.len(), visitor)
+        let segment = Segment::Variant(self.variant);
+        self.de
+            .nested(segment, |de| de.deserialize_fields(fields, visitor))
     }
 }
+
+/// Reverse the zigzag mapping of signed integers.
+fn unzigzag(value: u64) -> i64 {
+    ((value >> 1) as i64) ^ -((value & 1) as i64)
+}
@@ -0,0 +1,729 @@
+//! Annotated dumps of serialized bytes.
+//!
+//! [`annotate`] decodes bytes as a given type and describes every region of
+//! the input on its own line, with its byte range in hexadecimal, the path of
+//! the field in the value, its type and its decoded value. When decoding
+//! fails, the dump ends with an `<error>` line at the offset where it failed.
+//!
+//! # Examples
+//!
+//! ```
+//! // Bring serde Serialize/Deserialize derivable traits into scope.
+//! use serde::{Deserialize, Serialize};
+//!
+//! // Define custom struct.
+//! #[derive(Deserialize, Serialize)]
+//! struct MyStruct {
+//!     name: String,
+//!     score: u32,
+//! }
+//!
+//! // Serialize value into bytes.
+//! let value = MyStruct {
+//!     name: "Ferris".to_owned(),
+//!     score: 42,
+//! };
+//! let bytes = shaft::to_bytes(&value).expect("serialization error");
+//!
+//! // Annotate bytes.
+//! let dump = shaft::debug::annotate::<MyStruct>(&bytes);
+//! assert_eq!(
+//!     dump,
+//!     "0000..0008 .name len=6\n\
+//!      0008..000e .name str \"Ferris\"\n\
+//!      000e..0012 .score u32 42\n"
+//! );
+//!
+//! // Annotate truncated bytes.
+//! let dump = shaft::debug::annotate::<MyStruct>(&bytes[..16]);
+//! assert!(dump.ends_with("000e.. <error> reader: reached end of slice\n"));
+//! ```
+
+use std::fmt::{self, Debug, Write};
+
+use serde::de::{
+    self,
+    value::{BorrowedStrDeserializer, U32Deserializer},
+    IntoDeserializer,
+};
+
+use crate::{
+    checksum,
+    de::{Deserializer, Variant},
+    error::{Error, Result},
+    lazy,
+    options::{Options, StructEncoding},
+    raw,
+    read::SliceReader,
+    with,
+};
+
+/// Annotate bytes decoded as a value of type `T`.
+pub fn annotate<'a, T>(bytes: &'a [u8]) -> String
+where
+    T: de::Deserialize<'a>,
+{
+    annotate_with::<T>(bytes, Options::default())
+}
+
+/// Annotate bytes decoded as a value of type `T` with the given options.
+///
+/// The checksum trailer is annotated without being verified. Structs with
+/// tagged fields are annotated as a single region.
+pub fn annotate_with<'a, T>(bytes: &'a [u8], options: Options) -> String
+where
+    T: de::Deserialize<'a>,
+{
+    let trailer_len = checksum::trailer_len(options.checksum()).min(bytes.len());
+    let (body, trailer) = bytes.split_at(bytes.len() - trailer_len);
+    let mut trace = Trace {
+        de: Deserializer::with_options(SliceReader::new(body), options),
+        len: body.len(),
+        out: String::new(),
+    };
+    let tracer = Tracer {
+        trace: &mut trace,
+        path: String::new(),
+    };
+    match T::deserialize(tracer) {
+        Ok(_) => {
+            let start = trace.de.position();
+            if start < trace.len {
+                let len = trace.len - start;
+                trace.region(start, "", format_args!("<trailing> {len} bytes"));
+            }
+            if !trailer.is_empty() {
+                let start = trace.len;
+                let hex = Hex(trailer);
+                trace.out += &format!("{start:04x}..{:04x} <checksum> {hex:?}\n", bytes.len());
+            }
+        }
+        Err(error) => {
+            let start = trace.de.position();
+            trace.out += &format!("{start:04x}.. <error> {error}\n");
+        }
+    }
+    trace.out
+}
+
+/// Shared state of an annotation.
+struct Trace<'de> {
+    de: Deserializer<'de, SliceReader<'de>>,
+    len: usize,
+    out: String,
+}
+
+impl Trace<'_> {
+    /// Describe the region from `start` to the current position.
+    fn region(&mut self, start: usize, path: &str, description: fmt::Arguments) {
+        let end = self.de.position();
+        let path = if path.is_empty() { "." } else { path };
+        let _ = writeln!(self.out, "{start:04x}..{end:04x} {path} {description}");
+    }
+}
+
+/// Deserializer describing the regions of a value at a given path.
+struct Tracer<'a, 'de> {
+    trace: &'a mut Trace<'de>,
+    path: String,
+}
+
+impl<'de> Tracer<'_, 'de> {
+    fn child(&mut self, segment: fmt::Arguments) -> Tracer<'_, 'de> {
+        Tracer {
+            trace: &mut *self.trace,
+            path: format!("{}{segment}", self.path),
+        }
+    }
+
+    /// Deserialize a primitive with the inner deserializer and describe it.
+    fn primitive<V, F>(self, ty: &str, visitor: V, deserialize: F) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+        F: FnOnce(&mut Deserializer<'de, SliceReader<'de>>, Capture<'_, V>) -> Result<V::Value>,
+    {
+        let start = self.trace.de.position();
+        let mut value = String::new();
+        let capture = Capture {
+            visitor,
+            value: &mut value,
+        };
+        let result = deserialize(&mut self.trace.de, capture)?;
+        self.trace
+            .region(start, &self.path, format_args!("{ty} {value}"));
+        Ok(result)
+    }
+
+    fn read_len(&mut self) -> Result<usize> {
+        let start = self.trace.de.position();
+        let len = self.trace.de.read_len()?;
+        self.trace
+            .region(start, &self.path, format_args!("len={len}"));
+        Ok(len)
+    }
+}
+
+impl<'de> de::Deserializer<'de> for Tracer<'_, 'de> {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, _: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        Err("debug: deserialize_any not supported".into())
+    }
+
+    fn deserialize_bool<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.primitive("bool", visitor, |de, visitor| {
+            de::Deserializer::deserialize_bool(de, visitor)
+        })
+    }
+
+    fn deserialize_i8<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.primitive("i8", visitor, |de, visitor| {
+            de::Deserializer::deserialize_i8(de, visitor)
+        })
+    }
+
+    fn deserialize_i16<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.primitive("i16", visitor, |de, visitor| {
+            de::Deserializer::deserialize_i16(de, visitor)
+        })
+    }
+
+    fn deserialize_i32<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.primitive("i32", visitor, |de, visitor| {
+            de::Deserializer::deserialize_i32(de, visitor)
+        })
+    }
+
+    fn deserialize_i64<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.primitive("i64", visitor, |de, visitor| {
+            de::Deserializer::deserialize_i64(de, visitor)
+        })
+    }
+
+    fn deserialize_u8<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.primitive("u8", visitor, |de, visitor| {
+            de::Deserializer::deserialize_u8(de, visitor)
+        })
+    }
+
+    fn deserialize_u16<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.primitive("u16", visitor, |de, visitor| {
+            de::Deserializer::deserialize_u16(de, visitor)
+        })
+    }
+
+    fn deserialize_u32<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.primitive("u32", visitor, |de, visitor| {
+            de::Deserializer::deserialize_u32(de, visitor)
+        })
+    }
+
+    fn deserialize_u64<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.primitive("u64", visitor, |de, visitor| {
+            de::Deserializer::deserialize_u64(de, visitor)
+        })
+    }
+
+    fn deserialize_f32<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.primitive("f32", visitor, |de, visitor| {
+            de::Deserializer::deserialize_f32(de, visitor)
+        })
+    }
+
+    fn deserialize_f64<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.primitive("f64", visitor, |de, visitor| {
+            de::Deserializer::deserialize_f64(de, visitor)
+        })
+    }
+
+    fn deserialize_char<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.primitive("char", visitor, |de, visitor| {
+            de::Deserializer::deserialize_char(de, visitor)
+        })
+    }
+
+    fn deserialize_str<V>(mut self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        let len = self.read_len()?;
+        let start = self.trace.de.position();
+        let bytes = self.trace.de.read_bytes(len)?;
+        let value = match std::str::from_utf8(bytes) {
+            Ok(value) => value,
+            Err(_) => return Err("deserialize: invalid str".into()),
+        };
+        self.trace
+            .region(start, &self.path, format_args!("str {value:?}"));
+        visitor.visit_borrowed_str(value)
+    }
+
+    fn deserialize_string<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_bytes<V>(mut self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        let len = self.read_len()?;
+        let start = self.trace.de.position();
+        let bytes = self.trace.de.read_bytes(len)?;
+        self.trace
+            .region(start, &self.path, format_args!("bytes {:?}", Hex(bytes)));
+        visitor.visit_borrowed_bytes(bytes)
+    }
+
+    fn deserialize_byte_buf<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.deserialize_bytes(visitor)
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        let start = self.trace.de.position();
+        if self.trace.de.read_bool()? {
+            self.trace
+                .region(start, &self.path, format_args!("option=Some"));
+            visitor.visit_some(self)
+        } else {
+            self.trace
+                .region(start, &self.path, format_args!("option=None"));
+            visitor.visit_none()
+        }
+    }
+
+    fn deserialize_unit<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V>(self, _: &'static str, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V>(mut self, name: &'static str, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        let outer = self.trace.de.options();
+        if name == lazy::NAME {
+            let len = self.read_len()?;
+            let start = self.trace.de.position();
+            let bytes = self.trace.de.read_bytes(len)?;
+            self.trace
+                .region(start, &self.path, format_args!("lazy {len} bytes"));
+            return lazy::visit(visitor, bytes, outer);
+        }
+        if name == raw::NAME {
+            let start = self.trace.de.position();
+            let value =
+                de::Deserializer::deserialize_newtype_struct(&mut self.trace.de, name, visitor)?;
+            let len = self.trace.de.position() - start;
+            self.trace
+                .region(start, &self.path, format_args!("raw {len} bytes"));
+            return Ok(value);
+        }
+        let Some(options) = with::adapt(name, outer) else {
+            return visitor.visit_newtype_struct(self);
+        };
+        self.trace.de.set_options(options);
+        let trace = &mut *self.trace;
+        let tracer = Tracer {
+            trace: &mut *trace,
+            path: self.path,
+        };
+        let result = visitor.visit_newtype_struct(tracer);
+        trace.de.set_options(outer);
+        result
+    }
+
+    fn deserialize_seq<V>(mut self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        let len = self.read_len()?;
+        visitor.visit_seq(SeqTracer {
+            tracer: self,
+            fields: None,
+            index: 0,
+            len,
+        })
+    }
+
+    fn deserialize_tuple<V>(self, len: usize, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        visitor.visit_seq(SeqTracer {
+            tracer: self,
+            fields: None,
+            index: 0,
+            len,
+        })
+    }
+
+    fn deserialize_tuple_struct<V>(
+        self,
+        _: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.deserialize_tuple(len, visitor)
+    }
+
+    fn deserialize_map<V>(mut self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        let len = self.read_len()?;
+        visitor.visit_map(MapTracer {
+            tracer: self,
+            index: 0,
+            len,
+        })
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        _: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        match self.trace.de.options().struct_encoding() {
+            StructEncoding::Positional => visitor.visit_seq(SeqTracer {
+                tracer: self,
+                fields: Some(fields),
+                index: 0,
+                len: fields.len(),
+            }),
+            StructEncoding::Tagged(_) => {
+                let start = self.trace.de.position();
+                let value = self.trace.de.deserialize_fields(fields, visitor)?;
+                self.trace
+                    .region(start, &self.path, format_args!("struct (tagged)"));
+                Ok(value)
+            }
+        }
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        _: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        let start = self.trace.de.position();
+        let variant = self.trace.de.read_variant(variants)?;
+        let name = match variant {
+            Variant::Index(index) => variants.get(index as usize).copied().unwrap_or("?"),
+            Variant::Name(name) => name,
+        };
+        self.trace
+            .region(start, &self.path, format_args!("variant={name}"));
+        visitor.visit_enum(EnumTracer {
+            tracer: self,
+            variant,
+            name,
+        })
+    }
+
+    fn deserialize_identifier<V>(self, _: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        Err("debug: deserialize_identifier not supported".into())
+    }
+
+    fn deserialize_ignored_any<V>(self, _: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        Err("debug: deserialize_ignored_any not supported".into())
+    }
+
+    fn is_human_readable(&self) -> bool {
+        self.trace.de.options().human_readable()
+    }
+}
+
+/// Access to the elements of a sequence or tuple, or the fields of a struct.
+struct SeqTracer<'a, 'de> {
+    tracer: Tracer<'a, 'de>,
+    fields: Option<&'static [&'static str]>,
+    index: usize,
+    len: usize,
+}
+
+impl<'de> de::SeqAccess<'de> for SeqTracer<'_, 'de> {
+    type Error = Error;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>>
+    where
+        T: de::DeserializeSeed<'de>,
+    {
+        if self.index == self.len {
+            return Ok(None);
+        }
+        let trace = &self.tracer.trace;
+        if self.fields.is_some()
+            && trace.de.options().trailing_defaults()
+            && trace.de.position() == trace.len
+        {
+            return Ok(None);
+        }
+        let index = self.index;
+        self.index += 1;
+        let tracer = match self.fields {
+            Some(fields) => self.tracer.child(format_args!(".{}", fields[index])),
+            None => self.tracer.child(format_args!("[{index}]")),
+        };
+        seed.deserialize(tracer).map(Some)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.len - self.index)
+    }
+}
+
+/// Access to the entries of a map.
+struct MapTracer<'a, 'de> {
+    tracer: Tracer<'a, 'de>,
+    index: usize,
+    len: usize,
+}
+
+impl<'de> de::MapAccess<'de> for MapTracer<'_, 'de> {
+    type Error = Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>>
+    where
+        K: de::DeserializeSeed<'de>,
+    {
+        if self.index == self.len {
+            return Ok(None);
+        }
+        let tracer = self.tracer.child(format_args!("[{}].key", self.index));
+        seed.deserialize(tracer).map(Some)
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value>
+    where
+        V: de::DeserializeSeed<'de>,
+    {
+        let index = self.index;
+        self.index += 1;
+        let tracer = self.tracer.child(format_args!("[{index}].value"));
+        seed.deserialize(tracer)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.len - self.index)
+    }
+}
+
+/// Access to the variant of an enum.
+struct EnumTracer<'a, 'de> {
+    tracer: Tracer<'a, 'de>,
+    variant: Variant<'de>,
+    name: &'de str,
+}
+
+impl<'a, 'de> de::EnumAccess<'de> for EnumTracer<'a, 'de> {
+    type Error = Error;
+
+    type Variant = Tracer<'a, 'de>;
+
+    fn variant_seed<V>(mut self, seed: V) -> Result<(V::Value, Self::Variant)>
+    where
+        V: de::DeserializeSeed<'de>,
+    {
+        let value = match self.variant {
+            Variant::Index(index) => {
+                seed.deserialize::<U32Deserializer<Error>>(index.into_deserializer())?
+            }
+            Variant::Name(name) => seed.deserialize(BorrowedStrDeserializer::<Error>::new(name))?,
+        };
+        self.tracer.path = format!("{}::{}", self.tracer.path, self.name);
+        Ok((value, self.tracer))
+    }
+}
+
+impl<'de> de::VariantAccess<'de> for Tracer<'_, 'de> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<()> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value>
+    where
+        T: de::DeserializeSeed<'de>,
+    {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V>(self, len: usize, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        de::Deserializer::deserialize_tuple(self, len, visitor)
+    }
+
+    fn struct_variant<V>(self, fields: &'static [&'static str], visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        de::Deserializer::deserialize_struct(self, "", fields, visitor)
+    }
+}
+
+/// Visitor recording the primitive value it visits.
+struct Capture<'a, V> {
+    visitor: V,
+    value: &'a mut String,
+}
+
+impl<'de, V: de::Visitor<'de>> de::Visitor<'de> for Capture<'_, V> {
+    type Value = V::Value;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        self.visitor.expecting(formatter)
+    }
+
+    fn visit_bool<E: de::Error>(self, value: bool) -> std::result::Result<V::Value, E> {
+        *self.value = value.to_string();
+        self.visitor.visit_bool(value)
+    }
+
+    fn visit_i8<E: de::Error>(self, value: i8) -> std::result::Result<V::Value, E> {
+        *self.value = value.to_string();
+        self.visitor.visit_i8(value)
+    }
+
+    fn visit_i16<E: de::Error>(self, value: i16) -> std::result::Result<V::Value, E> {
+        *self.value = value.to_string();
+        self.visitor.visit_i16(value)
+    }
+
+    fn visit_i32<E: de::Error>(self, value: i32) -> std::result::Result<V::Value, E> {
+        *self.value = value.to_string();
+        self.visitor.visit_i32(value)
+    }
+
+    fn visit_i64<E: de::Error>(self, value: i64) -> std::result::Result<V::Value, E> {
+        *self.value = value.to_string();
+        self.visitor.visit_i64(value)
+    }
+
+    fn visit_u8<E: de::Error>(self, value: u8) -> std::result::Result<V::Value, E> {
+        *self.value = value.to_string();
+        self.visitor.visit_u8(value)
+    }
+
+    fn visit_u16<E: de::Error>(self, value: u16) -> std::result::Result<V::Value, E> {
+        *self.value = value.to_string();
+        self.visitor.visit_u16(value)
+    }
+
+    fn visit_u32<E: de::Error>(self, value: u32) -> std::result::Result<V::Value, E> {
+        *self.value = value.to_string();
+        self.visitor.visit_u32(value)
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> std::result::Result<V::Value, E> {
+        *self.value = value.to_string();
+        self.visitor.visit_u64(value)
+    }
+
+    fn visit_f32<E: de::Error>(self, value: f32) -> std::result::Result<V::Value, E> {
+        *self.value = format!("{value:?}");
+        self.visitor.visit_f32(value)
+    }
+
+    fn visit_f64<E: de::Error>(self, value: f64) -> std::result::Result<V::Value, E> {
+        *self.value = format!("{value:?}");
+        self.visitor.visit_f64(value)
+    }
+
+    fn visit_char<E: de::Error>(self, value: char) -> std::result::Result<V::Value, E> {
+        *self.value = format!("{value:?}");
+        self.visitor.visit_char(value)
+    }
+}
+
+/// Bytes formatted in hexadecimal.
+struct Hex<'a>(&'a [u8]);
+
+impl Debug for Hex<'_> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.write_str("[")?;
+        for (index, byte) in self.0.iter().enumerate() {
+            if index > 0 {
+                fmt.write_str(" ")?;
+            }
+            write!(fmt, "{byte:02x}")?;
+        }
+        fmt.write_str("]")
+    }
+}
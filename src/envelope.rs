@@ -0,0 +1,271 @@
+//! Self-describing envelope around serialized values.
+//!
+//! Bytes produced by [`crate::to_bytes`] carry no indication of the options
+//! they were written with. The envelope prefixes them with a header made of:
+//!
+//! - the magic bytes `SHFT`,
+//! - the format version as a `u8`,
+//! - a flags `u8` describing the encoding options,
+//! - the checksum `u8` describing the checksum trailer,
+//! - the compression `u8` describing the compression of the body,
+//! - a second flags `u8` describing whether the value was written as human
+//!   readable.
+//!
+//! When reading, the header is checked and the deserializer is configured
+//! from the flags, so enveloped data can be decoded without knowing the
+//! options it was written with.
+//!
+//! # Examples
+//!
+//! ```
+//! // Bring serde Serialize/Deserialize derivable traits into scope.
+//! use serde::{Deserialize, Serialize};
+//! // Bring options into scope.
+//! use shaft::{Endian, IntEncoding, Options};
+//!
+//! // Define custom struct.
+//! #[derive(Deserialize, Serialize)]
+//! struct MyStruct {
+//!     name: String,
+//!     score: u32,
+//! }
+//!
+//! // Create value to serialize.
+//! let value = MyStruct {
+//!     name: "Ferris".to_owned(),
+//!     score: 42,
+//! };
+//!
+//! // Serialize value with options into an envelope.
+//! let options = Options::new()
+//!     .with_endian(Endian::Big)
+//!     .with_int_encoding(IntEncoding::Varint);
+//! let bytes = shaft::envelope::to_bytes_with(&value, options).expect("serialization error");
+//! assert_eq!(&bytes[..4], b"SHFT");
+//!
+//! // Deserialize value without specifying options.
+//! let value: MyStruct = shaft::envelope::from_bytes(&bytes).expect("deserialization error");
+//! assert_eq!(value.name, "Ferris");
+//! assert_eq!(value.score, 42);
+//! ```
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::{
+    error::Result,
+    options::{
+        Checksum, Endian, FieldTag, IntEncoding, LenWidth, Options, StructEncoding, VariantEncoding,
+    },
+};
+
+/// Magic bytes starting every envelope.
+pub const MAGIC: [u8; 4] = *b"SHFT";
+
+/// Format version written in the envelope header.
+pub const VERSION: u8 = 1;
+
+const HEADER_LEN: usize = MAGIC.len() + 5;
+
+/// Serialize a value into an envelope with default options.
+pub fn to_bytes<T>(value: &T) -> Result<Vec<u8>>
+where
+    T: Serialize,
+{
+    to_bytes_with(value, Options::default())
+}
+
+/// Serialize a value into an envelope with the given options.
+///
+/// Decoding options such as [`Options::with_trailing_defaults`] are not
+/// recorded in the header.
+pub fn to_bytes_with<T>(value: &T, options: Options) -> Result<Vec<u8>>
+where
+    T: Serialize,
+{
+    let mut bytes = Vec::new();
+    write_header(&mut bytes, options, 0);
+    bytes.extend(crate::to_bytes_with(value, options)?);
+    Ok(bytes)
+}
+
+/// Deserialize a value from an envelope.
+///
+/// The body is decompressed if the header records a compression algorithm,
+/// which requires the matching compression feature. Fails if the magic bytes
+/// are missing, or if the version or the flags are not supported.
+pub fn from_bytes<T>(bytes: &[u8]) -> Result<T>
+where
+    T: DeserializeOwned,
+{
+    let (options, compression, body) = read_header(bytes)?;
+    if compression == 0 {
+        return crate::from_bytes_with(body, options);
+    }
+    let body = decompress(body, compression)?;
+    crate::from_bytes_with(&body, options)
+}
+
+/// Deserialize a value borrowing from an uncompressed envelope.
+///
+/// Fails if the body is compressed, see [`from_bytes`].
+pub fn from_bytes_borrowed<'a, T>(bytes: &'a [u8]) -> Result<T>
+where
+    T: Deserialize<'a>,
+{
+    let (options, compression, body) = read_header(bytes)?;
+    if compression != 0 {
+        return Err("envelope: compressed body cannot be borrowed".into());
+    }
+    crate::from_bytes_with(body, options)
+}
+
+#[cfg(any(feature = "compression-lz4", feature = "compression-deflate"))]
+fn decompress(body: &[u8], compression: u8) -> Result<Vec<u8>> {
+    crate::compression::decompress(body, compression, crate::compression::MAX_LEN)
+}
+
+#[cfg(not(any(feature = "compression-lz4", feature = "compression-deflate")))]
+fn decompress(_: &[u8], compression: u8) -> Result<Vec<u8>> {
+    Err(format!("envelope: compression {compression} not enabled").into())
+}
+
+/// Returns the options recorded in the header of an envelope.
+pub fn options(bytes: &[u8]) -> Result<Options> {
+    let (options, _, _) = read_header(bytes)?;
+    Ok(options)
+}
+
+/// Write the header of an envelope with the given compression byte.
+pub(crate) fn write_header(bytes: &mut Vec<u8>, options: Options, compression: u8) {
+    bytes.reserve(HEADER_LEN);
+    bytes.extend(MAGIC);
+    bytes.push(VERSION);
+    let [flags, checksum, human_readable] = encode_options(options);
+    bytes.extend([flags, checksum, compression, human_readable]);
+}
+
+/// Read the header of an envelope, returning the options, the compression
+/// byte and the body.
+pub(crate) fn read_header(bytes: &[u8]) -> Result<(Options, u8, &[u8])> {
+    let Some(rest) = bytes.strip_prefix(&MAGIC) else {
+        return Err("envelope: invalid magic bytes".into());
+    };
+    match rest {
+        [VERSION, flags, checksum, compression, human_readable, body @ ..] => {
+            let options = decode_options([*flags, *checksum, *human_readable])?;
+            Ok((options, *compression, body))
+        }
+        [version, ..] if *version != VERSION => {
+            Err(format!("envelope: unsupported version {version}").into())
+        }
+        _ => Err("envelope: invalid header".into()),
+    }
+}
+
+/// Encode options as their flags, checksum and human readable bytes, shared
+/// by the envelope header, the archive footer and the log header.
+pub(crate) fn encode_options(options: Options) -> [u8; 3] {
+    [
+        encode_flags(options),
+        encode_checksum(options.checksum()),
+        options.human_readable() as u8,
+    ]
+}
+
+/// Decode options from the bytes written by [`encode_options`].
+pub(crate) fn decode_options([flags, checksum, human_readable]: [u8; 3]) -> Result<Options> {
+    let human_readable = match human_readable {
+        0 => false,
+        1 => true,
+        _ => return Err("envelope: invalid human readable flag".into()),
+    };
+    Ok(decode_flags(flags)?
+        .with_checksum(decode_checksum(checksum)?)
+        .with_human_readable(human_readable))
+}
+
+// Flags layout, from the least significant bit:
+// - bit 0: big endian,
+// - bit 1: variable-length integers,
+// - bits 2-3: length width (u64, u32, u16, u8),
+// - bits 4-5: struct encoding (positional, tagged by hash, tagged by index),
+// - bits 6-7: variant encoding (index, name, hash).
+fn encode_flags(options: Options) -> u8 {
+    let endian = match options.endian() {
+        Endian::Little => 0,
+        Endian::Big => 1,
+    };
+    let int_encoding = match options.int_encoding() {
+        IntEncoding::Fixed => 0,
+        IntEncoding::Varint => 1,
+    };
+    let len_width = match options.len_width() {
+        LenWidth::U64 => 0,
+        LenWidth::U32 => 1,
+        LenWidth::U16 => 2,
+        LenWidth::U8 => 3,
+    };
+    let struct_encoding = match options.struct_encoding() {
+        StructEncoding::Positional => 0,
+        StructEncoding::Tagged(FieldTag::Hash) => 1,
+        StructEncoding::Tagged(FieldTag::Index) => 2,
+    };
+    let variant_encoding = match options.variant_encoding() {
+        VariantEncoding::Index => 0,
+        VariantEncoding::Name => 1,
+        VariantEncoding::Hash => 2,
+    };
+    endian | int_encoding << 1 | len_width << 2 | struct_encoding << 4 | variant_encoding << 6
+}
+
+fn decode_flags(flags: u8) -> Result<Options> {
+    let endian = match flags & 1 {
+        0 => Endian::Little,
+        _ => Endian::Big,
+    };
+    let int_encoding = match flags >> 1 & 1 {
+        0 => IntEncoding::Fixed,
+        _ => IntEncoding::Varint,
+    };
+    let len_width = match flags >> 2 & 3 {
+        0 => LenWidth::U64,
+        1 => LenWidth::U32,
+        2 => LenWidth::U16,
+        _ => LenWidth::U8,
+    };
+    let struct_encoding = match flags >> 4 & 3 {
+        0 => StructEncoding::Positional,
+        1 => StructEncoding::Tagged(FieldTag::Hash),
+        2 => StructEncoding::Tagged(FieldTag::Index),
+        _ => return Err("envelope: invalid struct encoding flags".into()),
+    };
+    let variant_encoding = match flags >> 6 & 3 {
+        0 => VariantEncoding::Index,
+        1 => VariantEncoding::Name,
+        2 => VariantEncoding::Hash,
+        _ => return Err("envelope: invalid variant encoding flags".into()),
+    };
+    Ok(Options::new()
+        .with_endian(endian)
+        .with_int_encoding(int_encoding)
+        .with_len_width(len_width)
+        .with_struct_encoding(struct_encoding)
+        .with_variant_encoding(variant_encoding))
+}
+
+fn encode_checksum(checksum: Checksum) -> u8 {
+    match checksum {
+        Checksum::None => 0,
+        Checksum::Crc32c => 1,
+        Checksum::XxHash64 => 2,
+    }
+}
+
+fn decode_checksum(checksum: u8) -> Result<Checksum> {
+    match checksum {
+        0 => Ok(Checksum::None),
+        1 => Ok(Checksum::Crc32c),
+        2 => Ok(Checksum::XxHash64),
+        _ => Err("envelope: invalid checksum".into()),
+    }
+}
@@ -6,14 +6,81 @@ use serde::{de, ser};
 
 /// Shaft Error type.
 #[derive(Debug)]
-pub struct Error(String);
+pub struct Error {
+    kind: ErrorKind,
+    location: Option<(String, usize)>,
+}
+
+/// Kind of [`Error`].
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// Error described by a message.
+    Message(String),
+    /// Type fingerprint of the payload does not match the expected type.
+    TypeMismatch { expected: u64, found: u64 },
+    /// Checksum of the payload does not match its checksum trailer.
+    ChecksumMismatch { expected: u64, found: u64 },
+    /// Sealed payload was tampered with, or opened with the wrong key or
+    /// associated data.
+    AuthenticationFailed,
+    /// Reading or writing a file failed.
+    Io(std::io::Error),
+}
+
+impl Error {
+    pub(crate) fn new(kind: ErrorKind) -> Self {
+        Self {
+            kind,
+            location: None,
+        }
+    }
+
+    /// Attach the byte offset of the value being deserialized, unless the
+    /// error was already located.
+    pub(crate) fn locate(mut self, offset: usize) -> Self {
+        if self.location.is_none() {
+            self.location = Some((String::new(), offset));
+        }
+        self
+    }
+
+    /// Prepend a segment to the path of a located error.
+    ///
+    /// The path is built while the error propagates out of nested values, so
+    /// that decoding without errors never builds it.
+    pub(crate) fn prefix(mut self, segment: impl Display) -> Self {
+        if let Some((path, _)) = &mut self.location {
+            path.insert_str(0, &segment.to_string());
+        }
+        self
+    }
+
+    /// Returns the kind of error.
+    pub fn kind(&self) -> &ErrorKind {
+        &self.kind
+    }
+
+    /// Returns the path of the value being deserialized when the error
+    /// occurred, such as `.orders[412].items[3].sku`.
+    ///
+    /// The path is empty for the top-level value.
+    pub fn path(&self) -> Option<&str> {
+        self.location.as_ref().map(|(path, _)| path.as_str())
+    }
+
+    /// Returns the byte offset in the input when the error occurred.
+    pub fn offset(&self) -> Option<usize> {
+        self.location.as_ref().map(|(_, offset)| *offset)
+    }
+}
 
 impl ser::Error for Error {
     fn custom<T>(msg: T) -> Self
     where
         T: Display,
     {
-        Error(msg.to_string())
+        Error::new(ErrorKind::Message(msg.to_string()))
     }
 }
 
@@ -22,20 +89,56 @@ impl de::Error for Error {
     where
         T: Display,
     {
-        Error(msg.to_string())
+        Error::new(ErrorKind::Message(msg.to_string()))
     }
 }
 
 impl Display for Error {
     fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        fmt.write_str(&self.0)
+        match &self.kind {
+            ErrorKind::Message(message) => fmt.write_str(message),
+            ErrorKind::TypeMismatch { expected, found } => write!(
+                fmt,
+                "typed: type mismatch, expected fingerprint {expected:016x}, found {found:016x}"
+            ),
+            ErrorKind::ChecksumMismatch { expected, found } => write!(
+                fmt,
+                "checksum: mismatch, expected {expected:016x}, found {found:016x}"
+            ),
+            ErrorKind::AuthenticationFailed => fmt.write_str("sealed: authentication failed"),
+            ErrorKind::Io(error) => write!(fmt, "io: {error}"),
+        }?;
+        match &self.location {
+            Some((path, offset)) if path.is_empty() => write!(fmt, " at offset {offset}"),
+            Some((path, offset)) => write!(fmt, " at {path} (offset {offset})"),
+            None => Ok(()),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match &self.kind {
+            ErrorKind::Io(error) => Some(error),
+            _ => None,
+        }
     }
 }
 
-impl std::error::Error for Error {}
+impl From<std::io::Error> for Error {
+    fn from(error: std::io::Error) -> Self {
+        Error::new(ErrorKind::Io(error))
+    }
+}
 
 impl From<&str> for Error {
     fn from(value: &str) -> Self {
-        Self(value.to_string())
+        Error::new(ErrorKind::Message(value.to_string()))
+    }
+}
+
+impl From<String> for Error {
+    fn from(value: String) -> Self {
+        Error::new(ErrorKind::Message(value))
     }
 }
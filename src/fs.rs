@@ -0,0 +1,147 @@
+//! Atomic saving and loading of values to files.
+//!
+//! [`save`] serializes a value into an [`envelope`] streamed to a temporary
+//! file next to the destination, synchronizes it to the storage device and
+//! renames it over the destination. Readers of the destination see either the
+//! previous file or the complete new one, never a partially written file.
+//!
+//! Failures to read or write files are reported as
+//! [`ErrorKind::Io`](crate::ErrorKind::Io), other errors come from
+//! serialization or deserialization.
+//!
+//! # Examples
+//!
+//! ```
+//! // Bring serde Serialize/Deserialize derivable traits into scope.
+//! use serde::{Deserialize, Serialize};
+//! // Bring error kind into scope.
+//! use shaft::ErrorKind;
+//!
+//! // Define custom struct.
+//! #[derive(Debug, PartialEq, Deserialize, Serialize)]
+//! struct Settings {
+//!     name: String,
+//!     volume: u8,
+//! }
+//!
+//! # let dir = std::env::temp_dir().join(format!("shaft-fs-doc-{}", std::process::id()));
+//! # std::fs::create_dir_all(&dir).unwrap();
+//! # let path = dir.join("settings.bin");
+//! // Save value to file.
+//! let settings = Settings {
+//!     name: "Ferris".to_owned(),
+//!     volume: 42,
+//! };
+//! shaft::fs::save(&path, &settings).expect("save error");
+//!
+//! // Load value from file.
+//! let loaded: Settings = shaft::fs::load(&path).expect("load error");
+//! assert_eq!(loaded, settings);
+//!
+//! // Missing files are reported as I/O errors.
+//! let error = shaft::fs::load::<Settings>(dir.join("missing.bin")).unwrap_err();
+//! assert!(matches!(error.kind(), ErrorKind::Io(_)));
+//! # std::fs::remove_dir_all(&dir).unwrap();
+//! ```
+
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{envelope, error::Result, options::Options, ser, write::IoWriter};
+
+/// Save a value to a file with default options.
+pub fn save<T>(path: impl AsRef<Path>, value: &T) -> Result<()>
+where
+    T: ?Sized + Serialize,
+{
+    save_with(path, value, Options::default())
+}
+
+/// Save a value to a file with the given options.
+///
+/// The file is replaced atomically: the value is written to a temporary file
+/// in the same directory, which is renamed over `path` once synchronized.
+pub fn save_with<T>(path: impl AsRef<Path>, value: &T, options: Options) -> Result<()>
+where
+    T: ?Sized + Serialize,
+{
+    let path = path.as_ref();
+    let temp = temp_path(path)?;
+    let file = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&temp)?;
+    let result = write_file(file, value, options).and_then(|()| {
+        fs::rename(&temp, path)?;
+        sync_parent(path)
+    });
+    if result.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    result
+}
+
+/// Load a value from a file saved by [`save`] or [`save_with`].
+///
+/// The options are read from the envelope header.
+pub fn load<T>(path: impl AsRef<Path>) -> Result<T>
+where
+    T: DeserializeOwned,
+{
+    let bytes = fs::read(path)?;
+    envelope::from_bytes(&bytes)
+}
+
+/// Counter distinguishing the temporary files of concurrent saves within the
+/// process.
+static TEMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Returns a path for a new temporary file used to save to `path`.
+///
+/// Each call returns a different path, so concurrent saves to the same path
+/// never write the same temporary file.
+fn temp_path(path: &Path) -> Result<PathBuf> {
+    let Some(name) = path.file_name() else {
+        return Err(format!("fs: invalid file path {}", path.display()).into());
+    };
+    let count = TEMP_COUNTER.fetch_add(1, Ordering::Relaxed);
+    let mut temp = std::ffi::OsString::from(".");
+    temp.push(name);
+    temp.push(format!(".{}.{count}.tmp", std::process::id()));
+    Ok(path.with_file_name(temp))
+}
+
+fn write_file<T>(file: File, value: &T, options: Options) -> Result<()>
+where
+    T: ?Sized + Serialize,
+{
+    let mut writer = BufWriter::new(file);
+    let mut header = Vec::new();
+    envelope::write_header(&mut header, options, 0);
+    writer.write_all(&header)?;
+    let writer = ser::to_writer_with(IoWriter::new(writer), value, options)?.into_inner();
+    let file = writer.into_inner().map_err(|error| error.into_error())?;
+    Ok(file.sync_all()?)
+}
+
+/// Synchronize the directory holding `path`, so that the rename survives a
+/// crash.
+#[cfg(unix)]
+fn sync_parent(path: &Path) -> Result<()> {
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    Ok(File::open(parent)?.sync_all()?)
+}
+
+#[cfg(not(unix))]
+fn sync_parent(_: &Path) -> Result<()> {
+    Ok(())
+}
@@ -0,0 +1,167 @@
+use std::hash::Hasher;
+
+use serde::Serialize;
+
+use crate::{error::Result, options::Options, ser::to_writer_with, write::Writer};
+
+/// Hash function fed with serialized bytes.
+///
+/// With the `digest` feature, it is implemented for every hasher of the
+/// RustCrypto `digest` crate, such as SHA-256 or BLAKE3. Hashers of
+/// [`std::hash::Hasher`] are supported through [`StdHasher`].
+pub trait HashSink {
+    /// Feed bytes into the hash function.
+    fn update(&mut self, bytes: &[u8]);
+}
+
+#[cfg(feature = "digest")]
+impl<D: digest::Update> HashSink for D {
+    fn update(&mut self, bytes: &[u8]) {
+        digest::Update::update(self, bytes)
+    }
+}
+
+/// Adapter for hashers of [`std::hash::Hasher`].
+///
+/// [`Hasher`] does not guarantee that bytes fed in several writes hash the
+/// same as when fed at once, so the hash of a value may differ from the hash
+/// of its serialized bytes. It is still the same for equal serialized bytes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct StdHasher<H>(pub H);
+
+impl<H: Hasher> StdHasher<H> {
+    /// Returns the hash value of the bytes fed so far.
+    pub fn finish(&self) -> u64 {
+        self.0.finish()
+    }
+}
+
+impl<H: Hasher> HashSink for StdHasher<H> {
+    fn update(&mut self, bytes: &[u8]) {
+        self.0.write(bytes)
+    }
+}
+
+/// Writer feeding serialized bytes into a hash function.
+///
+/// The hash function receives the exact bytes [`to_bytes`](crate::to_bytes)
+/// would return, without allocating them.
+#[derive(Clone, Debug, Default)]
+pub struct HashWriter<H> {
+    hasher: H,
+}
+
+impl<H: HashSink> HashWriter<H> {
+    /// Create a new writer.
+    pub fn new(hasher: H) -> Self {
+        Self { hasher }
+    }
+
+    /// Returns the inner hash function.
+    pub fn into_inner(self) -> H {
+        self.hasher
+    }
+}
+
+impl<H: HashSink> Writer for HashWriter<H> {
+    fn write_bool(&mut self, value: bool) -> Result<()> {
+        self.hasher.update(&[value as u8]);
+        Ok(())
+    }
+
+    fn write_i8(&mut self, value: i8) -> Result<()> {
+        self.hasher.update(&value.to_le_bytes());
+        Ok(())
+    }
+
+    fn write_i16(&mut self, value: i16) -> Result<()> {
+        self.hasher.update(&value.to_le_bytes());
+        Ok(())
+    }
+
+    fn write_i32(&mut self, value: i32) -> Result<()> {
+        self.hasher.update(&value.to_le_bytes());
+        Ok(())
+    }
+
+    fn write_i64(&mut self, value: i64) -> Result<()> {
+        self.hasher.update(&value.to_le_bytes());
+        Ok(())
+    }
+
+    fn write_u8(&mut self, value: u8) -> Result<()> {
+        self.hasher.update(&value.to_le_bytes());
+        Ok(())
+    }
+
+    fn write_u16(&mut self, value: u16) -> Result<()> {
+        self.hasher.update(&value.to_le_bytes());
+        Ok(())
+    }
+
+    fn write_u32(&mut self, value: u32) -> Result<()> {
+        self.hasher.update(&value.to_le_bytes());
+        Ok(())
+    }
+
+    fn write_u64(&mut self, value: u64) -> Result<()> {
+        self.hasher.update(&value.to_le_bytes());
+        Ok(())
+    }
+
+    fn write_f32(&mut self, value: f32) -> Result<()> {
+        self.hasher.update(&value.to_le_bytes());
+        Ok(())
+    }
+
+    fn write_f64(&mut self, value: f64) -> Result<()> {
+        self.hasher.update(&value.to_le_bytes());
+        Ok(())
+    }
+
+    fn write_bytes(&mut self, value: &[u8]) -> Result<()> {
+        self.hasher.update(value);
+        Ok(())
+    }
+}
+
+/// Feed the serialized bytes of a value into a new hash function and return
+/// it.
+///
+/// # Examples
+///
+/// ```
+/// // Bring std DefaultHasher type into scope.
+/// use std::collections::hash_map::DefaultHasher;
+/// // Bring hasher adapter into scope.
+/// use shaft::StdHasher;
+///
+/// // Hash value without materializing its bytes.
+/// let value = ("Ferris", 42u32);
+/// let hasher: StdHasher<DefaultHasher> = shaft::hash(&value).expect("serialization error");
+///
+/// // Equal values have the same hash.
+/// let other: StdHasher<DefaultHasher> =
+///     shaft::hash(&("Ferris".to_owned(), 42u32)).expect("serialization error");
+/// assert_eq!(hasher.finish(), other.finish());
+/// ```
+pub fn hash<H, T>(value: &T) -> Result<H>
+where
+    H: HashSink + Default,
+    T: ?Sized + Serialize,
+{
+    hash_with(value, H::default(), Options::default())
+}
+
+/// Feed the serialized bytes of a value with the given options into a hash
+/// function and return it.
+///
+/// Use [`Options::with_canonical`] for equal values to always have the same
+/// hash.
+pub fn hash_with<H, T>(value: &T, hasher: H, options: Options) -> Result<H>
+where
+    H: HashSink,
+    T: ?Sized + Serialize,
+{
+    Ok(to_writer_with(HashWriter::new(hasher), value, options)?.into_inner())
+}
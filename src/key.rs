@@ -0,0 +1,86 @@
+//! Order-preserving encoding of keys.
+//!
+//! Keys are encoded so that comparing their bytes gives the same order as
+//! comparing the values with their derived `Ord`, which makes them suitable
+//! for ordered key-value stores:
+//!
+//! - integers are written big-endian, with the sign bit flipped for signed
+//!   integers,
+//! - floats are written big-endian, with the sign bit flipped for positive
+//!   floats and all bits flipped for negative floats, which orders them as
+//!   [`f64::total_cmp`] does,
+//! - strings and byte arrays escape `0x00` as `0x00 0xff` and are terminated
+//!   by `0x00 0x01`,
+//! - options are written as a `0` or `1` byte followed by the value,
+//! - sequences and maps write a `1` byte before each element or entry and a
+//!   `0` byte at the end,
+//! - tuples and structs are the concatenation of their fields,
+//! - enum variants are written as their big-endian `u32` index followed by
+//!   their content.
+//!
+//! Key encoding is independent of [`Options`](crate::Options).
+//!
+//! # Examples
+//!
+//! ```
+//! // Bring serde Serialize/Deserialize derivable traits into scope.
+//! use serde::{Deserialize, Serialize};
+//!
+//! // Define custom key ordered by user then timestamp.
+//! #[derive(Debug, PartialEq, PartialOrd, Deserialize, Serialize)]
+//! struct EventKey {
+//!     user: String,
+//!     timestamp: i64,
+//! }
+//!
+//! // Encode keys.
+//! let first = EventKey { user: "Ferris".to_owned(), timestamp: -1 };
+//! let second = EventKey { user: "Ferris".to_owned(), timestamp: 1 };
+//! let first_key = shaft::key::to_key(&first).expect("serialization error");
+//! let second_key = shaft::key::to_key(&second).expect("serialization error");
+//!
+//! // Encoded keys have the same order as values.
+//! assert!(first < second);
+//! assert!(first_key < second_key);
+//!
+//! // Decode key.
+//! let value: EventKey = shaft::key::from_key(&first_key).expect("deserialization error");
+//! assert_eq!(value, first);
+//! ```
+
+mod de;
+mod ser;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::Result;
+
+/// Escape byte of strings and byte arrays.
+const ESCAPE: u8 = 0x00;
+/// Byte following an escape byte for an escaped `0x00`.
+const ESCAPED: u8 = 0xff;
+/// Byte following an escape byte at the end of strings and byte arrays.
+const TERMINATOR: u8 = 0x01;
+
+/// Encode a value into an order-preserving key.
+pub fn to_key<T>(value: &T) -> Result<Vec<u8>>
+where
+    T: ?Sized + Serialize,
+{
+    let mut serializer = ser::Serializer::new();
+    value.serialize(&mut serializer)?;
+    Ok(serializer.into_vec())
+}
+
+/// Decode a value from an order-preserving key.
+pub fn from_key<'a, T>(bytes: &'a [u8]) -> Result<T>
+where
+    T: Deserialize<'a>,
+{
+    let mut deserializer = de::Deserializer::new(bytes);
+    let value = T::deserialize(&mut deserializer)?;
+    if !deserializer.is_empty() {
+        return Err("key: trailing bytes".into());
+    }
+    Ok(value)
+}
@@ -0,0 +1,262 @@
+//! Lazily decoded values.
+
+use std::{borrow::Cow, fmt, marker::PhantomData};
+
+use serde::{
+    de::{
+        self,
+        value::{BorrowedBytesDeserializer, U32Deserializer},
+        IntoDeserializer,
+    },
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+
+use crate::{
+    error::Error,
+    options::{Checksum, Options},
+};
+
+/// Reserved newtype struct name recognized by the shaft deserializer.
+pub(crate) const NAME: &str = "$shaft::Lazy";
+
+/// Value decoded on demand.
+///
+/// A `Lazy<T>` is serialized as a length-prefixed blob holding the encoding
+/// of `T`. Deserializing it only borrows the blob, without parsing it, and
+/// [`Lazy::get`] decodes the value later. Serializing a deserialized `Lazy`
+/// writes the blob untouched, which allows forwarding a value without
+/// decoding it.
+///
+/// The value is encoded with the options of the `Lazy` itself, which must be
+/// the options the enclosing value is serialized with. When deserialized, it
+/// takes the options of the enclosing value. Checksums only apply to the
+/// enclosing value.
+///
+/// # Examples
+///
+/// ```
+/// // Bring serde Serialize/Deserialize derivable traits into scope.
+/// use serde::{Deserialize, Serialize};
+/// // Bring lazy values into scope.
+/// use shaft::Lazy;
+///
+/// // Define custom message with a lazily decoded body.
+/// #[derive(Deserialize, Serialize)]
+/// struct Message<'a> {
+///     route: u8,
+///     #[serde(borrow)]
+///     body: Lazy<'a, Vec<String>>,
+/// }
+///
+/// // Serialize message.
+/// let body = vec!["Ferris".to_owned(); 100];
+/// let message = Message {
+///     route: 1,
+///     body: Lazy::new(&body).expect("serialization error"),
+/// };
+/// let bytes = shaft::to_bytes(&message).expect("serialization error");
+///
+/// // Deserialize message without decoding the body.
+/// let message: Message = shaft::from_bytes(&bytes).expect("deserialization error");
+/// assert_eq!(message.route, 1);
+///
+/// // Forward message untouched.
+/// let forwarded = shaft::to_bytes(&message).expect("serialization error");
+/// assert_eq!(forwarded, bytes);
+///
+/// // Decode body.
+/// assert_eq!(message.body.get().expect("deserialization error"), body);
+/// ```
+pub struct Lazy<'a, T> {
+    bytes: Cow<'a, [u8]>,
+    options: Options,
+    phantom: PhantomData<fn() -> T>,
+}
+
+impl<T: Serialize> Lazy<'static, T> {
+    /// Encode a value with default options.
+    pub fn new(value: &T) -> Result<Self, Error> {
+        Self::with_options(value, Options::default())
+    }
+
+    /// Encode a value with the given options.
+    pub fn with_options(value: &T, options: Options) -> Result<Self, Error> {
+        let options = options.with_checksum(Checksum::None);
+        Ok(Self {
+            bytes: Cow::Owned(crate::to_bytes_with(value, options)?),
+            options,
+            phantom: PhantomData,
+        })
+    }
+}
+
+impl<'a, T> Lazy<'a, T> {
+    /// Decode the value.
+    pub fn get<'b>(&'b self) -> Result<T, Error>
+    where
+        T: Deserialize<'b>,
+    {
+        crate::from_bytes_with(&self.bytes, self.options)
+    }
+
+    /// Returns the encoding of the value.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Returns the options the value is encoded with.
+    pub fn options(&self) -> Options {
+        self.options
+    }
+
+    /// Copy the encoding of the value if it is borrowed.
+    pub fn into_owned(self) -> Lazy<'static, T> {
+        Lazy {
+            bytes: Cow::Owned(self.bytes.into_owned()),
+            options: self.options,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<T> Clone for Lazy<'_, T> {
+    fn clone(&self) -> Self {
+        Self {
+            bytes: self.bytes.clone(),
+            options: self.options,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<T> fmt::Debug for Lazy<'_, T> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("Lazy")
+            .field("bytes", &self.bytes)
+            .field("options", &self.options)
+            .finish()
+    }
+}
+
+impl<T> Serialize for Lazy<'_, T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(&self.bytes)
+    }
+}
+
+impl<'de: 'a, 'a, T> Deserialize<'de> for Lazy<'a, T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct Visitor<'a, T>(PhantomData<Lazy<'a, T>>);
+
+        impl<'de: 'a, 'a, T> de::Visitor<'de> for Visitor<'a, T> {
+            type Value = Lazy<'a, T>;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a lazy value")
+            }
+
+            // Shaft passes the options and the borrowed encoding.
+            fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+            where
+                A: de::SeqAccess<'de>,
+            {
+                let bits = seq
+                    .next_element()?
+                    .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+                let bytes: &'de [u8] = seq
+                    .next_element()?
+                    .ok_or_else(|| de::Error::invalid_length(1, &self))?;
+                Ok(Lazy {
+                    bytes: Cow::Borrowed(bytes),
+                    options: Options::from_bits(bits),
+                    phantom: PhantomData,
+                })
+            }
+
+            // Other formats pass the encoding as bytes.
+            fn visit_newtype_struct<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+            where
+                D: Deserializer<'de>,
+            {
+                Ok(Lazy {
+                    bytes: deserialize_bytes(deserializer)?,
+                    options: Options::default(),
+                    phantom: PhantomData,
+                })
+            }
+        }
+
+        deserializer.deserialize_newtype_struct(NAME, Visitor(PhantomData))
+    }
+}
+
+/// Deserialize bytes, borrowing them if possible.
+pub(crate) fn deserialize_bytes<'de, D>(deserializer: D) -> Result<Cow<'de, [u8]>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserializer.deserialize_bytes(BytesVisitor)
+}
+
+struct BytesVisitor;
+
+impl<'de> de::Visitor<'de> for BytesVisitor {
+    type Value = Cow<'de, [u8]>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("bytes")
+    }
+
+    fn visit_borrowed_bytes<E: de::Error>(self, value: &'de [u8]) -> Result<Self::Value, E> {
+        Ok(Cow::Borrowed(value))
+    }
+
+    fn visit_bytes<E: de::Error>(self, value: &[u8]) -> Result<Self::Value, E> {
+        Ok(Cow::Owned(value.to_vec()))
+    }
+
+    fn visit_byte_buf<E: de::Error>(self, value: Vec<u8>) -> Result<Self::Value, E> {
+        Ok(Cow::Owned(value))
+    }
+}
+
+/// Visit a lazy value encoded in `bytes` with the given options.
+pub(crate) fn visit<'de, V>(
+    visitor: V,
+    bytes: &'de [u8],
+    options: Options,
+) -> Result<V::Value, Error>
+where
+    V: de::Visitor<'de>,
+{
+    let options = options.with_checksum(Checksum::None);
+    visitor.visit_seq(LazyAccess {
+        bits: Some(options.to_bits()),
+        bytes: Some(bytes),
+    })
+}
+
+struct LazyAccess<'de> {
+    bits: Option<u32>,
+    bytes: Option<&'de [u8]>,
+}
+
+impl<'de> de::SeqAccess<'de> for LazyAccess<'de> {
+    type Error = Error;
+
+    fn next_element_seed<S>(&mut self, seed: S) -> Result<Option<S::Value>, Error>
+    where
+        S: de::DeserializeSeed<'de>,
+    {
+        if let Some(bits) = self.bits.take() {
+            let deserializer: U32Deserializer<Error> = bits.into_deserializer();
+            return seed.deserialize(deserializer).map(Some);
+        }
+        match self.bytes.take() {
+            Some(bytes) => seed
+                .deserialize(BorrowedBytesDeserializer::new(bytes))
+                .map(Some),
+            None => Ok(None),
+        }
+    }
+}
@@ -47,14 +47,57 @@
 //! }
 //! ```
 
+pub mod archive;
+mod checksum;
+#[cfg(any(feature = "compression-lz4", feature = "compression-deflate"))]
+pub mod compression;
 mod de;
+pub mod debug;
+pub mod envelope;
 mod error;
+pub mod fs;
+mod hash;
+pub mod key;
+mod lazy;
+pub mod log;
+#[cfg(feature = "mmap")]
+pub mod mmap;
+mod options;
+mod raw;
 mod read;
+pub mod schema;
+#[cfg(feature = "crypto")]
+pub mod sealed;
+mod seq;
 mod ser;
+pub mod typed;
+pub mod with;
 mod write;
 
-pub use error::Error;
+pub use error::{Error, ErrorKind};
 
-pub use ser::to_bytes;
+pub use hash::{hash, hash_with, HashSink, HashWriter, StdHasher};
 
-pub use de::from_bytes;
+pub use lazy::Lazy;
+
+pub use raw::Raw;
+
+pub use options::{
+    Checksum, Endian, FieldTag, IntEncoding, LenWidth, Options, StructEncoding, VariantEncoding,
+};
+
+pub use ser::{to_bytes, to_bytes_with, to_io_writer, to_io_writer_with};
+
+pub use seq::{
+    iter_seq, iter_seq_from_reader, iter_seq_from_reader_with, iter_seq_with, ReaderSeqIter,
+    SeqIter, SeqWriter,
+};
+
+pub use de::{
+    from_bytes, from_bytes_in_place, from_bytes_in_place_with, from_bytes_seed,
+    from_bytes_seed_with, from_bytes_with, from_reader_seed, from_reader_seed_with, Deserializer,
+};
+
+pub use read::SliceReader;
+
+pub use write::Writer;
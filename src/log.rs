@@ -0,0 +1,384 @@
+//! Append-only logs of serialized records.
+//!
+//! A log file starts with a header made of the magic bytes `SHFL`, the format
+//! version as a `u8`, and the envelope flags, checksum and human readable
+//! bytes describing the options. Each record follows, framed by its `u32`
+//! length, the `u32` CRC-32C of its bytes and the `u32` CRC-32C of the two
+//! previous fields, all little-endian whatever the options.
+//!
+//! A crash in the middle of an append leaves a torn record at the end of the
+//! file. [`recover`] scans the log and truncates it after the last valid
+//! record; [`LogWriter::open`] recovers the log before appending to it. A
+//! checksum mismatch, on a frame or a record, before the end of the file is
+//! reported as corruption
+//! instead, leaving the file untouched.
+//!
+//! # Examples
+//!
+//! ```
+//! // Bring serde Serialize/Deserialize derivable traits into scope.
+//! use serde::{Deserialize, Serialize};
+//! // Bring log items into scope.
+//! use shaft::log::{self, LogReader, LogWriter};
+//!
+//! // Define custom record.
+//! #[derive(Debug, PartialEq, Deserialize, Serialize)]
+//! enum Entry {
+//!     Set { key: String, value: u32 },
+//!     Remove { key: String },
+//! }
+//!
+//! # let dir = std::env::temp_dir().join(format!("shaft-log-doc-{}", std::process::id()));
+//! # std::fs::create_dir_all(&dir).unwrap();
+//! # let path = dir.join("entries.log");
+//! // Append records to the log.
+//! let mut writer = LogWriter::open(&path).expect("log error");
+//! writer.append(&Entry::Set { key: "Ferris".to_owned(), value: 42 }).expect("log error");
+//! writer.append(&Entry::Remove { key: "Ferris".to_owned() }).expect("log error");
+//! drop(writer);
+//!
+//! // Recover the log after a crash.
+//! assert_eq!(log::recover(&path).expect("log error"), 2);
+//!
+//! // Read records back.
+//! let reader = LogReader::<Entry>::open(&path).expect("log error");
+//! let entries = reader.collect::<Result<Vec<_>, _>>().expect("log error");
+//! assert_eq!(entries[1], Entry::Remove { key: "Ferris".to_owned() });
+//! # std::fs::remove_dir_all(&dir).unwrap();
+//! ```
+
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, BufRead, BufReader, BufWriter, Read, Seek, SeekFrom, Write},
+    marker::PhantomData,
+    path::Path,
+};
+
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{checksum::Crc32c, envelope, error::Result, options::Options};
+
+/// Magic bytes starting every log.
+pub const MAGIC: [u8; 4] = *b"SHFL";
+
+/// Format version written in the log header.
+pub const VERSION: u8 = 1;
+
+const HEADER_LEN: usize = MAGIC.len() + 4;
+
+const FRAME_LEN: usize = 12;
+
+/// When appended records are synchronized to the storage device.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum SyncPolicy {
+    /// Synchronize after every record (default).
+    #[default]
+    Always,
+    /// Synchronize after every given number of records.
+    Every(usize),
+    /// Only synchronize on [`LogWriter::sync`].
+    Never,
+}
+
+fn encode_header(options: Options) -> [u8; HEADER_LEN] {
+    let mut header = [0; HEADER_LEN];
+    header[..MAGIC.len()].copy_from_slice(&MAGIC);
+    header[4] = VERSION;
+    header[5..].copy_from_slice(&envelope::encode_options(options));
+    header
+}
+
+fn decode_header(header: &[u8; HEADER_LEN]) -> Result<Options> {
+    let Some(rest) = header.strip_prefix(&MAGIC) else {
+        return Err("log: invalid magic bytes".into());
+    };
+    let [version, flags, checksum, human_readable] = rest else {
+        unreachable!()
+    };
+    if *version != VERSION {
+        return Err(format!("log: unsupported version {version}").into());
+    }
+    envelope::decode_options([*flags, *checksum, *human_readable])
+}
+
+/// Outcome of reading a record frame.
+enum Frame {
+    /// Valid record, its bytes are in the buffer.
+    Record,
+    /// Clean end of the log.
+    End,
+    /// Truncated record behind a valid frame, or checksum mismatch on the
+    /// last frame or record.
+    Torn,
+    /// Checksum mismatch on a frame or record followed by more bytes.
+    Corrupted,
+}
+
+/// Read `buf.len()` bytes, returning `false` if the reader ends first.
+fn read_full<R: Read>(reader: &mut R, mut buf: &mut [u8]) -> Result<bool> {
+    while !buf.is_empty() {
+        match reader.read(buf) {
+            Ok(0) => return Ok(false),
+            Ok(n) => buf = &mut buf[n..],
+            Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+            Err(error) => return Err(error.into()),
+        }
+    }
+    Ok(true)
+}
+
+fn read_frame<R: BufRead>(reader: &mut R, buf: &mut Vec<u8>) -> Result<Frame> {
+    let mut frame = [0; FRAME_LEN];
+    let mut first = [0; 1];
+    if !read_full(reader, &mut first)? {
+        return Ok(Frame::End);
+    }
+    frame[0] = first[0];
+    if !read_full(reader, &mut frame[1..])? {
+        return Ok(Frame::Torn);
+    }
+    let mut hasher = Crc32c::new();
+    hasher.update(&frame[..8]);
+    if hasher.finish() != u32::from_le_bytes(frame[8..].try_into().unwrap()) {
+        return torn_or_corrupted(reader);
+    }
+    let len = u32::from_le_bytes(frame[..4].try_into().unwrap()) as usize;
+    let crc = u32::from_le_bytes(frame[4..8].try_into().unwrap());
+    buf.clear();
+    let read = reader.take(len as u64).read_to_end(buf)?;
+    if read < len {
+        return Ok(Frame::Torn);
+    }
+    let mut hasher = Crc32c::new();
+    hasher.update(buf);
+    if hasher.finish() != crc {
+        return torn_or_corrupted(reader);
+    }
+    Ok(Frame::Record)
+}
+
+/// A checksum mismatch is a torn write only at the end of the log.
+fn torn_or_corrupted<R: BufRead>(reader: &mut R) -> Result<Frame> {
+    Ok(if reader.fill_buf()?.is_empty() {
+        Frame::Torn
+    } else {
+        Frame::Corrupted
+    })
+}
+
+/// Scan a log from the start of the file, returning the options, the number
+/// of valid records and the length of the valid part of the log.
+fn scan(file: &mut File) -> Result<(Options, usize, u64)> {
+    file.seek(SeekFrom::Start(0))?;
+    let mut reader = BufReader::new(file);
+    let mut header = [0; HEADER_LEN];
+    if !read_full(&mut reader, &mut header)? {
+        return Err("log: invalid header".into());
+    }
+    let options = decode_header(&header)?;
+    let mut buf = Vec::new();
+    let mut count = 0;
+    let mut len = HEADER_LEN as u64;
+    loop {
+        match read_frame(&mut reader, &mut buf)? {
+            Frame::Record => {
+                count += 1;
+                len += (FRAME_LEN + buf.len()) as u64;
+            }
+            Frame::End | Frame::Torn => return Ok((options, count, len)),
+            Frame::Corrupted => return Err("log: corrupted record".into()),
+        }
+    }
+}
+
+/// Recover a log after a crash.
+///
+/// The log is truncated after the last valid record, discarding a torn record
+/// at the end of the file. Returns the number of valid records.
+///
+/// A record failing its checksum before the end of the file is not the
+/// result of a crash: an error is returned and the file is left untouched.
+pub fn recover<P: AsRef<Path>>(path: P) -> Result<usize> {
+    let mut file = OpenOptions::new().read(true).write(true).open(path)?;
+    recover_file(&mut file).map(|(_, count)| count)
+}
+
+fn recover_file(file: &mut File) -> Result<(Options, usize)> {
+    let (options, count, len) = scan(file)?;
+    if file.metadata()?.len() > len {
+        file.set_len(len)?;
+        file.sync_all()?;
+    }
+    Ok((options, count))
+}
+
+/// Returns `true` if the file is empty or holds the beginning of the given
+/// header, as left by a crash while creating the log.
+fn is_torn_header(file: &mut File, header: &[u8; HEADER_LEN]) -> Result<bool> {
+    let len = file.metadata()?.len();
+    if len >= HEADER_LEN as u64 {
+        return Ok(false);
+    }
+    let mut bytes = Vec::new();
+    file.read_to_end(&mut bytes)?;
+    Ok(header.starts_with(&bytes))
+}
+
+/// Writer appending records to a log file.
+pub struct LogWriter<T> {
+    writer: BufWriter<File>,
+    options: Options,
+    sync: SyncPolicy,
+    len: usize,
+    unsynced: usize,
+    phantom: PhantomData<fn(&T)>,
+}
+
+impl<T: Serialize> LogWriter<T> {
+    /// Open a log with default options, creating it if it does not exist.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::open_with(path, Options::default(), SyncPolicy::default())
+    }
+
+    /// Open a log with the given options and sync policy, creating it if it
+    /// does not exist.
+    ///
+    /// An existing log is recovered first, and must have been written with
+    /// the same options. Decoding options such as
+    /// [`Options::with_trailing_defaults`] are not recorded in the header.
+    pub fn open_with<P: AsRef<Path>>(path: P, options: Options, sync: SyncPolicy) -> Result<Self> {
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)?;
+        let header = encode_header(options);
+        let len = if is_torn_header(&mut file, &header)? {
+            file.set_len(0)?;
+            file.seek(SeekFrom::Start(0))?;
+            file.write_all(&header)?;
+            file.sync_all()?;
+            0
+        } else {
+            let (existing, len) = recover_file(&mut file)?;
+            if encode_header(existing) != header {
+                return Err("log: options mismatch".into());
+            }
+            len
+        };
+        file.seek(SeekFrom::End(0))?;
+        Ok(Self {
+            writer: BufWriter::new(file),
+            options,
+            sync,
+            len,
+            unsynced: 0,
+            phantom: PhantomData,
+        })
+    }
+
+    /// Serialize a record at the end of the log.
+    pub fn append(&mut self, value: &T) -> Result<()> {
+        let bytes = crate::to_bytes_with(value, self.options)?;
+        let len = u32::try_from(bytes.len()).map_err(|_| "log: record too large")?;
+        let mut hasher = Crc32c::new();
+        hasher.update(&bytes);
+        let mut frame = [0; FRAME_LEN];
+        frame[..4].copy_from_slice(&len.to_le_bytes());
+        frame[4..8].copy_from_slice(&hasher.finish().to_le_bytes());
+        let mut hasher = Crc32c::new();
+        hasher.update(&frame[..8]);
+        frame[8..].copy_from_slice(&hasher.finish().to_le_bytes());
+        self.writer.write_all(&frame)?;
+        self.writer.write_all(&bytes)?;
+        self.len += 1;
+        self.unsynced += 1;
+        match self.sync {
+            SyncPolicy::Always => self.sync(),
+            SyncPolicy::Every(records) if self.unsynced >= records => self.sync(),
+            _ => Ok(()),
+        }
+    }
+
+    /// Write buffered records and synchronize them to the storage device.
+    pub fn sync(&mut self) -> Result<()> {
+        self.writer.flush()?;
+        self.writer.get_ref().sync_data()?;
+        self.unsynced = 0;
+        Ok(())
+    }
+
+    /// Returns the number of records in the log.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the log holds no record.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the options records are serialized with.
+    pub fn options(&self) -> Options {
+        self.options
+    }
+}
+
+/// Reader iterating over the records of a log file.
+///
+/// A torn record is reported as an error and ends the iteration; [`recover`]
+/// the log first to read it up to its last valid record.
+pub struct LogReader<T> {
+    reader: BufReader<File>,
+    options: Options,
+    buf: Vec<u8>,
+    done: bool,
+    phantom: PhantomData<fn() -> T>,
+}
+
+impl<T: DeserializeOwned> LogReader<T> {
+    /// Open a log.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let file = File::open(path)?;
+        let mut reader = BufReader::new(file);
+        let mut header = [0; HEADER_LEN];
+        if !read_full(&mut reader, &mut header)? {
+            return Err("log: invalid header".into());
+        }
+        Ok(Self {
+            reader,
+            options: decode_header(&header)?,
+            buf: Vec::new(),
+            done: false,
+            phantom: PhantomData,
+        })
+    }
+
+    /// Returns the options recorded in the header.
+    pub fn options(&self) -> Options {
+        self.options
+    }
+}
+
+impl<T: DeserializeOwned> Iterator for LogReader<T> {
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let result = match read_frame(&mut self.reader, &mut self.buf) {
+            Ok(Frame::Record) => return Some(crate::from_bytes_with(&self.buf, self.options)),
+            Ok(Frame::End) => {
+                self.done = true;
+                return None;
+            }
+            Ok(Frame::Torn) => Err("log: torn record".into()),
+            Ok(Frame::Corrupted) => Err("log: corrupted record".into()),
+            Err(error) => Err(error),
+        };
+        self.done = true;
+        Some(result)
+    }
+}
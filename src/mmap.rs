@@ -0,0 +1,115 @@
+//! Zero-copy decoding of memory-mapped files.
+//!
+//! Available with the `mmap` feature.
+//!
+//! [`Mapped`] maps a file into memory and decodes values straight out of the
+//! mapping: borrowed `&str` and `&[u8]` fields point into the file instead of
+//! being copied, and only the pages actually read are loaded from disk.
+//!
+//! # Examples
+//!
+//! ```
+//! // Bring serde Serialize/Deserialize derivable traits into scope.
+//! use serde::{Deserialize, Serialize};
+//! // Bring mapped files into scope.
+//! use shaft::mmap::Mapped;
+//!
+//! // Define custom struct with borrowed fields.
+//! #[derive(Deserialize, Serialize)]
+//! struct Dataset<'a> {
+//!     name: &'a str,
+//!     samples: &'a [u8],
+//! }
+//!
+//! # let path = std::env::temp_dir().join(format!("shaft-mmap-doc-{}.bin", std::process::id()));
+//! // Write dataset to file.
+//! let dataset = Dataset { name: "Ferris", samples: &[1, 2, 3] };
+//! std::fs::write(&path, shaft::to_bytes(&dataset).expect("serialization error")).unwrap();
+//!
+//! // Map file and decode dataset borrowing from the mapping.
+//! // SAFETY: the file is not modified while it is mapped.
+//! let mapped: Mapped<Dataset> = unsafe { Mapped::open(&path) }.expect("mmap error");
+//! let dataset = mapped.get().expect("deserialization error");
+//! assert_eq!(dataset.name, "Ferris");
+//! assert_eq!(dataset.samples, [1, 2, 3]);
+//! # drop(mapped);
+//! # std::fs::remove_file(&path).unwrap();
+//! ```
+
+use std::{fs::File, marker::PhantomData, path::Path};
+
+use memmap2::Mmap;
+use serde::Deserialize;
+
+use crate::{error::Result, options::Options};
+
+/// Memory-mapped file holding a serialized value of type `T`.
+///
+/// The mapping is owned by `Mapped`, and values returned by [`Mapped::get`]
+/// borrow from it.
+pub struct Mapped<T> {
+    map: Mmap,
+    options: Options,
+    phantom: PhantomData<fn() -> T>,
+}
+
+impl<T> Mapped<T> {
+    /// Map a file holding a value serialized with default options.
+    ///
+    /// # Safety
+    ///
+    /// The file must not be modified or truncated while it is mapped,
+    /// otherwise decoded values may change or reading them may crash the
+    /// process. See [`Mapped::from_file`].
+    pub unsafe fn open(path: impl AsRef<Path>) -> Result<Self> {
+        Self::open_with(path, Options::default())
+    }
+
+    /// Map a file holding a value serialized with the given options.
+    ///
+    /// # Safety
+    ///
+    /// See [`Mapped::open`].
+    pub unsafe fn open_with(path: impl AsRef<Path>, options: Options) -> Result<Self> {
+        let file = File::open(path)?;
+        Self::from_file(&file, options)
+    }
+
+    /// Map an open file holding a value serialized with the given options.
+    ///
+    /// The mapping stays valid after the file is closed.
+    ///
+    /// # Safety
+    ///
+    /// The file must not be modified or truncated while it is mapped, by this
+    /// process or another one. The mapping reflects such modifications, which
+    /// breaks the immutability of borrowed values, and accessing pages past
+    /// the end of a truncated file raises a bus error.
+    pub unsafe fn from_file(file: &File, options: Options) -> Result<Self> {
+        // SAFETY: guaranteed by the caller.
+        let map = unsafe { Mmap::map(file) }?;
+        Ok(Self {
+            map,
+            options,
+            phantom: PhantomData,
+        })
+    }
+
+    /// Decode the value, borrowing strings and bytes from the mapping.
+    pub fn get<'a>(&'a self) -> Result<T>
+    where
+        T: Deserialize<'a>,
+    {
+        crate::from_bytes_with(&self.map, self.options)
+    }
+
+    /// Returns the mapped bytes.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.map
+    }
+
+    /// Returns the options the value is decoded with.
+    pub fn options(&self) -> Options {
+        self.options
+    }
+}
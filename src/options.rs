@@ -0,0 +1,492 @@
+/// Encoding options.
+///
+/// Data must be deserialized with the options it was serialized with.
+///
+/// # Examples
+///
+/// ```
+/// // Bring serde Serialize/Deserialize derivable traits into scope.
+/// use serde::{Deserialize, Serialize};
+/// // Bring options into scope.
+/// use shaft::{FieldTag, Options, StructEncoding};
+///
+/// // Define custom struct.
+/// #[derive(Deserialize, Serialize)]
+/// struct MyStruct {
+///     name: String,
+///     score: u32,
+/// }
+///
+/// // Create value to serialize.
+/// let value = MyStruct {
+///     name: "Ferris".to_owned(),
+///     score: 42,
+/// };
+///
+/// // Create options.
+/// let options = Options::new().with_struct_encoding(StructEncoding::Tagged(FieldTag::Hash));
+///
+/// // Serialize and deserialize value with options.
+/// let bytes = shaft::to_bytes_with(&value, options).expect("serialization error");
+/// let value: MyStruct = shaft::from_bytes_with(&bytes, options).expect("deserialization error");
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Options {
+    endian: Endian,
+    int_encoding: IntEncoding,
+    len_width: LenWidth,
+    struct_encoding: StructEncoding,
+    variant_encoding: VariantEncoding,
+    trailing_defaults: bool,
+    checksum: Checksum,
+    canonical: bool,
+    human_readable: bool,
+}
+
+impl Options {
+    /// Create default options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set byte order of integers and floats.
+    pub fn with_endian(mut self, endian: Endian) -> Self {
+        self.endian = endian;
+        self
+    }
+
+    /// Returns byte order of integers and floats.
+    pub fn endian(&self) -> Endian {
+        self.endian
+    }
+
+    /// Set integer encoding.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// // Bring options into scope.
+    /// use shaft::{IntEncoding, Options};
+    ///
+    /// // Serialize small integer with variable-length encoding.
+    /// let options = Options::new().with_int_encoding(IntEncoding::Varint);
+    /// let bytes = shaft::to_bytes_with(&42u64, options).expect("serialization error");
+    /// assert_eq!(bytes, [42]);
+    /// ```
+    pub fn with_int_encoding(mut self, encoding: IntEncoding) -> Self {
+        self.int_encoding = encoding;
+        self
+    }
+
+    /// Returns integer encoding.
+    pub fn int_encoding(&self) -> IntEncoding {
+        self.int_encoding
+    }
+
+    /// Set width of length prefixes.
+    ///
+    /// Lengths of strings, bytes, sequences, maps and tagged struct fields are
+    /// written as an unsigned integer of this width, following the integer
+    /// encoding. Serialization fails on lengths that do not fit.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// // Bring options into scope.
+    /// use shaft::{LenWidth, Options};
+    ///
+    /// // Serialize string with a single byte length prefix.
+    /// let options = Options::new().with_len_width(LenWidth::U8);
+    /// let bytes = shaft::to_bytes_with(&"abc", options).expect("serialization error");
+    /// assert_eq!(bytes, [3, b'a', b'b', b'c']);
+    /// ```
+    pub fn with_len_width(mut self, width: LenWidth) -> Self {
+        self.len_width = width;
+        self
+    }
+
+    /// Returns width of length prefixes.
+    pub fn len_width(&self) -> LenWidth {
+        self.len_width
+    }
+
+    /// Set struct encoding.
+    pub fn with_struct_encoding(mut self, encoding: StructEncoding) -> Self {
+        self.struct_encoding = encoding;
+        self
+    }
+
+    /// Returns struct encoding.
+    pub fn struct_encoding(&self) -> StructEncoding {
+        self.struct_encoding
+    }
+
+    /// Set enum variant encoding.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// // Bring serde Serialize/Deserialize derivable traits into scope.
+    /// use serde::{Deserialize, Serialize};
+    /// // Bring options into scope.
+    /// use shaft::{Options, VariantEncoding};
+    ///
+    /// // Define old version of enum.
+    /// #[derive(Serialize)]
+    /// enum Old {
+    ///     Circle,
+    ///     Square,
+    /// }
+    ///
+    /// // Define new version of enum with an inserted variant.
+    /// #[derive(Debug, PartialEq, Deserialize)]
+    /// enum New {
+    ///     Circle,
+    ///     Triangle,
+    ///     Square,
+    /// }
+    ///
+    /// // Serialize old value with variant names.
+    /// let options = Options::new().with_variant_encoding(VariantEncoding::Name);
+    /// let bytes = shaft::to_bytes_with(&Old::Square, options).expect("serialization error");
+    ///
+    /// // Deserialize new value from old bytes.
+    /// let value: New = shaft::from_bytes_with(&bytes, options).expect("deserialization error");
+    /// assert_eq!(value, New::Square);
+    /// ```
+    pub fn with_variant_encoding(mut self, encoding: VariantEncoding) -> Self {
+        self.variant_encoding = encoding;
+        self
+    }
+
+    /// Returns enum variant encoding.
+    pub fn variant_encoding(&self) -> VariantEncoding {
+        self.variant_encoding
+    }
+
+    /// Set whether missing trailing struct fields are decoded as defaults.
+    ///
+    /// When enabled, decoding a positional struct stops at the end of the
+    /// input (or at the end of the enclosing tagged field) and the remaining
+    /// fields fall back to their `#[serde(default)]` value. This allows fields
+    /// to be appended to a struct while still decoding data written before.
+    ///
+    /// Appended fields must be annotated with `#[serde(default)]`, including
+    /// `Option` fields, and they must be the last values of the input.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// // Bring serde Serialize/Deserialize derivable traits into scope.
+    /// use serde::{Deserialize, Serialize};
+    /// // Bring options into scope.
+    /// use shaft::Options;
+    ///
+    /// // Define old version of struct.
+    /// #[derive(Serialize)]
+    /// struct Old {
+    ///     score: u32,
+    /// }
+    ///
+    /// // Define new version of struct with an appended field.
+    /// #[derive(Deserialize)]
+    /// struct New {
+    ///     score: u32,
+    ///     #[serde(default)]
+    ///     level: Option<u8>,
+    /// }
+    ///
+    /// // Serialize old value.
+    /// let bytes = shaft::to_bytes(&Old { score: 42 }).expect("serialization error");
+    ///
+    /// // Deserialize new value from old bytes.
+    /// let options = Options::new().with_trailing_defaults(true);
+    /// let value: New = shaft::from_bytes_with(&bytes, options).expect("deserialization error");
+    /// assert_eq!(value.score, 42);
+    /// assert_eq!(value.level, None);
+    /// ```
+    pub fn with_trailing_defaults(mut self, enabled: bool) -> Self {
+        self.trailing_defaults = enabled;
+        self
+    }
+
+    /// Returns whether missing trailing struct fields are decoded as defaults.
+    pub fn trailing_defaults(&self) -> bool {
+        self.trailing_defaults
+    }
+
+    /// Set checksum appended to serialized values.
+    ///
+    /// The checksum is computed while serializing and written as a trailer
+    /// after the value. It is verified before deserializing, and a mismatch
+    /// is reported as [`ErrorKind::ChecksumMismatch`](crate::ErrorKind::ChecksumMismatch).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// // Bring options into scope.
+    /// use shaft::{Checksum, ErrorKind, Options};
+    ///
+    /// // Serialize value with a CRC-32C trailer.
+    /// let options = Options::new().with_checksum(Checksum::Crc32c);
+    /// let mut bytes = shaft::to_bytes_with(&42u32, options).expect("serialization error");
+    /// assert_eq!(bytes.len(), 8);
+    ///
+    /// // Corrupted bytes are rejected.
+    /// bytes[0] ^= 1;
+    /// let error = shaft::from_bytes_with::<u32>(&bytes, options).unwrap_err();
+    /// assert!(matches!(error.kind(), ErrorKind::ChecksumMismatch { .. }));
+    /// ```
+    pub fn with_checksum(mut self, checksum: Checksum) -> Self {
+        self.checksum = checksum;
+        self
+    }
+
+    /// Returns checksum appended to serialized values.
+    pub fn checksum(&self) -> Checksum {
+        self.checksum
+    }
+
+    /// Set whether values are encoded canonically.
+    ///
+    /// When enabled, equal values always serialize to identical bytes:
+    ///
+    /// - map entries are sorted by the bytes of their encoded key, and
+    ///   serialization fails on duplicate keys,
+    /// - NaN floats are written as the canonical quiet NaN and `-0.0` is
+    ///   written as `0.0`.
+    ///
+    /// Deserialization is strict and rejects input that is not canonical:
+    /// unsorted or duplicate map keys, tagged struct fields out of order or
+    /// duplicated, non-canonical floats, variable-length integers written with
+    /// more bytes than needed, and trailing bytes.
+    ///
+    /// Sequences are written in iteration order, so sets must be ordered
+    /// (such as `BTreeSet`) to be encoded canonically.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// // Bring std HashMap type into scope.
+    /// use std::collections::HashMap;
+    /// // Bring options into scope.
+    /// use shaft::Options;
+    ///
+    /// // Create map with random iteration order.
+    /// let map: HashMap<String, u32> = (0..100).map(|index| (index.to_string(), index)).collect();
+    ///
+    /// // Serialize map canonically.
+    /// let options = Options::new().with_canonical(true);
+    /// let bytes = shaft::to_bytes_with(&map, options).expect("serialization error");
+    ///
+    /// // Equal maps have identical bytes.
+    /// let other: HashMap<String, u32> = map.clone().into_iter().collect();
+    /// assert_eq!(bytes, shaft::to_bytes_with(&other, options).expect("serialization error"));
+    /// ```
+    pub fn with_canonical(mut self, enabled: bool) -> Self {
+        self.canonical = enabled;
+        self
+    }
+
+    /// Returns whether values are encoded canonically.
+    pub fn canonical(&self) -> bool {
+        self.canonical
+    }
+
+    /// Set whether the serializer and deserializer report being human
+    /// readable.
+    ///
+    /// Types such as `IpAddr`, `SocketAddr` or UUIDs serialize as text when
+    /// the format is human readable and in a compact binary form otherwise.
+    /// Shaft is not human readable by default. Enable this option to read
+    /// data written by earlier versions of shaft, which reported being human
+    /// readable.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// // Bring std IpAddr type into scope.
+    /// use std::net::{IpAddr, Ipv4Addr};
+    /// // Bring options into scope.
+    /// use shaft::Options;
+    ///
+    /// // Addresses are written as octets by default.
+    /// let address = IpAddr::V4(Ipv4Addr::LOCALHOST);
+    /// let bytes = shaft::to_bytes(&address).expect("serialization error");
+    /// assert_eq!(bytes, [0, 0, 0, 0, 127, 0, 0, 1]);
+    ///
+    /// // Addresses are written as text when human readable.
+    /// let options = Options::new().with_human_readable(true);
+    /// let bytes = shaft::to_bytes_with(&address, options).expect("serialization error");
+    /// assert!(bytes.ends_with(b"127.0.0.1"));
+    /// ```
+    pub fn with_human_readable(mut self, enabled: bool) -> Self {
+        self.human_readable = enabled;
+        self
+    }
+
+    /// Returns whether the serializer and deserializer report being human
+    /// readable.
+    pub fn human_readable(&self) -> bool {
+        self.human_readable
+    }
+
+    /// Pack options into bits, to pass them through serde between shaft
+    /// internals.
+    pub(crate) fn to_bits(self) -> u32 {
+        let struct_encoding = match self.struct_encoding {
+            StructEncoding::Positional => 0,
+            StructEncoding::Tagged(FieldTag::Hash) => 1,
+            StructEncoding::Tagged(FieldTag::Index) => 2,
+        };
+        (self.endian as u32)
+            | (self.int_encoding as u32) << 1
+            | (self.len_width as u32) << 2
+            | struct_encoding << 4
+            | (self.variant_encoding as u32) << 6
+            | (self.trailing_defaults as u32) << 8
+            | (self.checksum as u32) << 9
+            | (self.canonical as u32) << 11
+            | (self.human_readable as u32) << 12
+    }
+
+    /// Unpack options packed with [`Options::to_bits`].
+    pub(crate) fn from_bits(bits: u32) -> Self {
+        let field = |shift: u32, mask: u32| (bits >> shift) & mask;
+        Self {
+            endian: [Endian::Little, Endian::Big][field(0, 1) as usize],
+            int_encoding: [IntEncoding::Fixed, IntEncoding::Varint][field(1, 1) as usize],
+            len_width: [LenWidth::U8, LenWidth::U16, LenWidth::U32, LenWidth::U64]
+                [field(2, 3) as usize],
+            struct_encoding: match field(4, 3) {
+                1 => StructEncoding::Tagged(FieldTag::Hash),
+                2 => StructEncoding::Tagged(FieldTag::Index),
+                _ => StructEncoding::Positional,
+            },
+            variant_encoding: match field(6, 3) {
+                1 => VariantEncoding::Name,
+                2 => VariantEncoding::Hash,
+                _ => VariantEncoding::Index,
+            },
+            trailing_defaults: field(8, 1) == 1,
+            checksum: match field(9, 3) {
+                1 => Checksum::Crc32c,
+                2 => Checksum::XxHash64,
+                _ => Checksum::None,
+            },
+            canonical: field(11, 1) == 1,
+            human_readable: field(12, 1) == 1,
+        }
+    }
+}
+
+/// Byte order of integers and floats.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Endian {
+    /// Least significant byte first.
+    #[default]
+    Little,
+    /// Most significant byte first.
+    Big,
+}
+
+/// Encoding of integers.
+///
+/// `u8` and `i8` values are always written as a single byte.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum IntEncoding {
+    /// Integers are written with their full width.
+    #[default]
+    Fixed,
+    /// Integers are written as LEB128 variable-length integers.
+    ///
+    /// Signed integers are zigzag encoded first so that small negative values
+    /// stay small. The endianness does not apply to variable-length integers.
+    Varint,
+}
+
+/// Width of length prefixes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum LenWidth {
+    U8,
+    U16,
+    U32,
+    #[default]
+    U64,
+}
+
+/// Encoding of struct fields.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum StructEncoding {
+    /// Fields are written one after the other in declaration order.
+    ///
+    /// This is the most compact encoding but fields cannot be added, removed
+    /// or reordered without breaking previously written data.
+    #[default]
+    Positional,
+    /// Fields are written with a tag and a length.
+    ///
+    /// The field count is written first, then each field is written as its
+    /// `u32` tag, its length in bytes and its value. The count and lengths
+    /// are written like other lengths, following [`LenWidth`] and
+    /// [`IntEncoding`], and the tag like other `u32`. Unknown fields are
+    /// skipped and missing fields fall back to `Option::None` or to their
+    /// `#[serde(default)]` value.
+    Tagged(FieldTag),
+}
+
+/// Tag identifying a struct field.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum FieldTag {
+    /// Hash of the field name.
+    ///
+    /// Fields can be reordered but not renamed.
+    Hash,
+    /// Position of the field in the struct declaration.
+    ///
+    /// Fields can be renamed but not reordered. Fields with `#[serde(skip)]`
+    /// take no position, and fields omitted by `skip_serializing_if` keep
+    /// theirs. A field with only `skip_serializing` or only
+    /// `skip_deserializing` is counted on one side only, so the following
+    /// fields are read into the wrong place: use [`FieldTag::Hash`] for such
+    /// structs.
+    Index,
+}
+
+/// Encoding of enum variants.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum VariantEncoding {
+    /// Variants are written as their `u32` index in the enum declaration.
+    ///
+    /// Variants can be renamed and appended but not reordered or inserted.
+    #[default]
+    Index,
+    /// Variants are written as their name, like a `str`.
+    ///
+    /// Variants can be reordered and inserted but not renamed.
+    Name,
+    /// Variants are written as the `u32` hash of their name.
+    ///
+    /// Variants can be reordered and inserted but not renamed. This is more
+    /// compact than [`VariantEncoding::Name`] for long variant names.
+    Hash,
+}
+
+/// Checksum appended to serialized values.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Checksum {
+    /// No checksum.
+    #[default]
+    None,
+    /// CRC-32C (Castagnoli), written as a `u32`.
+    Crc32c,
+    /// XXH64 with a zero seed, written as a `u64`.
+    XxHash64,
+}
+
+/// Hash a field or variant name (32-bit FNV-1a).
+pub(crate) fn name_hash(name: &str) -> u32 {
+    name.bytes().fold(0x811c_9dc5, |hash, byte| {
+        (hash ^ byte as u32).wrapping_mul(0x0100_0193)
+    })
+}
@@ -0,0 +1,216 @@
+//! Pre-encoded values.
+
+use std::{borrow::Cow, fmt, marker::PhantomData};
+
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{
+    error::Error,
+    options::{Checksum, Options},
+};
+
+/// Reserved newtype struct name recognized by the shaft serializer and
+/// deserializer.
+pub(crate) const NAME: &str = "$shaft::Raw";
+
+/// Value kept in its encoded form.
+///
+/// A `Raw<T>` is serialized by writing the encoding of `T` verbatim, so a
+/// value encoded once can be spliced into larger values without being decoded
+/// and encoded again. Deserializing it decodes `T` to find the end of its
+/// encoding, then borrows the exact bytes it spans, which [`Raw::get`] decodes
+/// again later.
+///
+/// The encoding is written as is: it must use the options the enclosing value
+/// is serialized with. When deserialized, it takes the options of the
+/// enclosing value. Checksums only apply to the enclosing value.
+///
+/// # Examples
+///
+/// ```
+/// // Bring serde Serialize/Deserialize derivable traits into scope.
+/// use serde::{Deserialize, Serialize};
+/// // Bring raw values into scope.
+/// use shaft::Raw;
+///
+/// // Define custom header.
+/// #[derive(Deserialize, Serialize)]
+/// struct Header {
+///     name: String,
+///     size: u32,
+/// }
+///
+/// // Define custom message with a pre-encoded header.
+/// #[derive(Deserialize, Serialize)]
+/// struct Message<'a> {
+///     #[serde(borrow)]
+///     header: Raw<'a, Header>,
+///     body: Vec<u8>,
+/// }
+///
+/// // Encode header once.
+/// let header = Header {
+///     name: "Ferris".to_owned(),
+///     size: 42,
+/// };
+/// let header = Raw::new(&header).expect("serialization error");
+///
+/// // Splice header into messages.
+/// let message = Message { header, body: vec![1, 2, 3] };
+/// let bytes = shaft::to_bytes(&message).expect("serialization error");
+/// assert_eq!(bytes.len(), message.header.as_bytes().len() + 8 + 3);
+///
+/// // Capture header span.
+/// let message: Message = shaft::from_bytes(&bytes).expect("deserialization error");
+/// assert_eq!(message.header.as_bytes(), &bytes[..message.header.as_bytes().len()]);
+///
+/// // Decode header.
+/// let header = message.header.get().expect("deserialization error");
+/// assert_eq!(header.name, "Ferris");
+/// ```
+pub struct Raw<'a, T> {
+    bytes: Cow<'a, [u8]>,
+    options: Options,
+    phantom: PhantomData<fn() -> T>,
+}
+
+impl<T: Serialize> Raw<'static, T> {
+    /// Encode a value with default options.
+    pub fn new(value: &T) -> Result<Self, Error> {
+        Self::with_options(value, Options::default())
+    }
+
+    /// Encode a value with the given options.
+    pub fn with_options(value: &T, options: Options) -> Result<Self, Error> {
+        let options = options.with_checksum(Checksum::None);
+        Ok(Self {
+            bytes: Cow::Owned(crate::to_bytes_with(value, options)?),
+            options,
+            phantom: PhantomData,
+        })
+    }
+}
+
+impl<'a, T> Raw<'a, T> {
+    /// Wrap bytes holding the encoding of a value with the given options.
+    ///
+    /// The bytes are not checked: invalid bytes are reported by
+    /// [`Raw::get`], or by the decoding of the enclosing value once
+    /// serialized.
+    pub fn from_encoded(bytes: impl Into<Cow<'a, [u8]>>, options: Options) -> Self {
+        Self {
+            bytes: bytes.into(),
+            options: options.with_checksum(Checksum::None),
+            phantom: PhantomData,
+        }
+    }
+
+    /// Decode the value.
+    pub fn get<'b>(&'b self) -> Result<T, Error>
+    where
+        T: Deserialize<'b>,
+    {
+        crate::from_bytes_with(&self.bytes, self.options)
+    }
+
+    /// Returns the encoding of the value.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Returns the options the value is encoded with.
+    pub fn options(&self) -> Options {
+        self.options
+    }
+
+    /// Copy the encoding of the value if it is borrowed.
+    pub fn into_owned(self) -> Raw<'static, T> {
+        Raw {
+            bytes: Cow::Owned(self.bytes.into_owned()),
+            options: self.options,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<T> Clone for Raw<'_, T> {
+    fn clone(&self) -> Self {
+        Self {
+            bytes: self.bytes.clone(),
+            options: self.options,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<T> fmt::Debug for Raw<'_, T> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("Raw")
+            .field("bytes", &self.bytes)
+            .field("options", &self.options)
+            .finish()
+    }
+}
+
+struct Bytes<'a>(&'a [u8]);
+
+impl Serialize for Bytes<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(self.0)
+    }
+}
+
+impl<T> Serialize for Raw<'_, T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_newtype_struct(NAME, &Bytes(&self.bytes))
+    }
+}
+
+impl<'de: 'a, 'a, T: Deserialize<'de>> Deserialize<'de> for Raw<'a, T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct Visitor<'a, T>(PhantomData<Raw<'a, T>>);
+
+        impl<'de: 'a, 'a, T: Deserialize<'de>> de::Visitor<'de> for Visitor<'a, T> {
+            type Value = Raw<'a, T>;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a raw value")
+            }
+
+            // Shaft passes the value, the options and the bytes the value
+            // spans.
+            fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+            where
+                A: de::SeqAccess<'de>,
+            {
+                seq.next_element::<T>()?
+                    .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+                let bits = seq
+                    .next_element()?
+                    .ok_or_else(|| de::Error::invalid_length(1, &self))?;
+                let bytes: &'de [u8] = seq
+                    .next_element()?
+                    .ok_or_else(|| de::Error::invalid_length(2, &self))?;
+                Ok(Raw {
+                    bytes: Cow::Borrowed(bytes),
+                    options: Options::from_bits(bits),
+                    phantom: PhantomData,
+                })
+            }
+
+            // Other formats pass the encoding as bytes.
+            fn visit_newtype_struct<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+            where
+                D: Deserializer<'de>,
+            {
+                Ok(Raw {
+                    bytes: crate::lazy::deserialize_bytes(deserializer)?,
+                    options: Options::default(),
+                    phantom: PhantomData,
+                })
+            }
+        }
+
+        deserializer.deserialize_newtype_struct(NAME, Visitor(PhantomData))
+    }
+}
@@ -1,5 +1,9 @@
 use crate::error::Result;
 
+/// Source of serialized bytes.
+///
+/// Not exported: bytes are borrowed for `'a` and [`Reader::slice`] returns
+/// bytes already read, which only [`SliceReader`] can provide.
 pub trait Reader<'a> {
     fn read_bool(&mut self) -> Result<bool>;
     fn read_i8(&mut self) -> Result<i8>;
@@ -13,23 +17,43 @@ pub trait Reader<'a> {
     fn read_f32(&mut self) -> Result<f32>;
     fn read_f64(&mut self) -> Result<f64>;
     fn read_bytes(&mut self, len: usize) -> Result<&'a [u8]>;
+    fn is_empty(&mut self) -> Result<bool>;
+    fn position(&self) -> usize;
+    fn slice(&self, start: usize, end: usize) -> &'a [u8];
 }
 
+/// Reader of a slice of bytes.
 pub struct SliceReader<'a> {
     slice: &'a [u8],
     pos: usize,
+    end: bool,
 }
 
 impl<'a> SliceReader<'a> {
     pub fn new(slice: &'a [u8]) -> Self {
-        Self { slice, pos: 0 }
+        Self {
+            slice,
+            pos: 0,
+            end: false,
+        }
+    }
+
+    /// Returns whether a read failed because it ran past the end of the
+    /// slice, or the slice was found empty, as when taking trailing defaults.
+    pub(crate) fn reached_end(&self) -> bool {
+        self.end
+    }
+
+    fn end_of_slice<T>(&mut self) -> Result<T> {
+        self.end = true;
+        Err("reader: reached end of slice".into())
     }
 }
 
 impl<'a> Reader<'a> for SliceReader<'a> {
     fn read_bool(&mut self) -> Result<bool> {
         if self.pos >= self.slice.len() {
-            return Err("reader: reached end of slice".into());
+            return self.end_of_slice();
         }
         let value = match self.slice[self.pos] {
             0 => false,
@@ -42,7 +66,7 @@ impl<'a> Reader<'a> for SliceReader<'a> {
 
     fn read_i8(&mut self) -> Result<i8> {
         if self.pos >= self.slice.len() {
-            return Err("reader: reached end of slice".into());
+            return self.end_of_slice();
         }
         let byte = self.slice[self.pos];
         let value = byte as i8;
@@ -53,7 +77,7 @@ impl<'a> Reader<'a> for SliceReader<'a> {
     fn read_i16(&mut self) -> Result<i16> {
         let end = self.pos + 2;
         if end > self.slice.len() {
-            return Err("reader: reached end of slice".into());
+            return self.end_of_slice();
         }
         let bytes = &self.slice[self.pos..end];
         let value = i16::from_le_bytes(bytes.try_into().unwrap());
@@ -64,7 +88,7 @@ impl<'a> Reader<'a> for SliceReader<'a> {
     fn read_i32(&mut self) -> Result<i32> {
         let end = self.pos + 4;
         if end > self.slice.len() {
-            return Err("reader: reached end of slice".into());
+            return self.end_of_slice();
         }
         let bytes = &self.slice[self.pos..end];
         let value = i32::from_le_bytes(bytes.try_into().unwrap());
@@ -75,7 +99,7 @@ impl<'a> Reader<'a> for SliceReader<'a> {
     fn read_i64(&mut self) -> Result<i64> {
         let end = self.pos + 8;
         if end > self.slice.len() {
-            return Err("reader: reached end of slice".into());
+            return self.end_of_slice();
         }
         let bytes = &self.slice[self.pos..end];
         let value = i64::from_le_bytes(bytes.try_into().unwrap());
@@ -85,7 +109,7 @@ impl<'a> Reader<'a> for SliceReader<'a> {
 
     fn read_u8(&mut self) -> Result<u8> {
         if self.pos >= self.slice.len() {
-            return Err("reader: reached end of slice".into());
+            return self.end_of_slice();
         }
         let value = self.slice[self.pos];
         self.pos += 1;
@@ -95,7 +119,7 @@ impl<'a> Reader<'a> for SliceReader<'a> {
     fn read_u16(&mut self) -> Result<u16> {
         let end = self.pos + 2;
         if end > self.slice.len() {
-            return Err("reader: reached end of slice".into());
+            return self.end_of_slice();
         }
         let bytes = &self.slice[self.pos..end];
         let value = u16::from_le_bytes(bytes.try_into().unwrap());
@@ -106,7 +130,7 @@ impl<'a> Reader<'a> for SliceReader<'a> {
     fn read_u32(&mut self) -> Result<u32> {
         let end = self.pos + 4;
         if end > self.slice.len() {
-            return Err("reader: reached end of slice".into());
+            return self.end_of_slice();
         }
         let bytes = &self.slice[self.pos..end];
         let value = u32::from_le_bytes(bytes.try_into().unwrap());
@@ -117,7 +141,7 @@ impl<'a> Reader<'a> for SliceReader<'a> {
     fn read_u64(&mut self) -> Result<u64> {
         let end = self.pos + 8;
         if end > self.slice.len() {
-            return Err("reader: reached end of slice".into());
+            return self.end_of_slice();
         }
         let bytes = &self.slice[self.pos..end];
         let value = u64::from_le_bytes(bytes.try_into().unwrap());
@@ -128,7 +152,7 @@ impl<'a> Reader<'a> for SliceReader<'a> {
     fn read_f32(&mut self) -> Result<f32> {
         let end = self.pos + 4;
         if end > self.slice.len() {
-            return Err("reader: reached end of slice".into());
+            return self.end_of_slice();
         }
         let bytes = &self.slice[self.pos..end];
         let value = f32::from_le_bytes(bytes.try_into().unwrap());
@@ -139,7 +163,7 @@ impl<'a> Reader<'a> for SliceReader<'a> {
     fn read_f64(&mut self) -> Result<f64> {
         let end = self.pos + 8;
         if end > self.slice.len() {
-            return Err("reader: reached end of slice".into());
+            return self.end_of_slice();
         }
         let bytes = &self.slice[self.pos..end];
         let value = f64::from_le_bytes(bytes.try_into().unwrap());
@@ -148,12 +172,25 @@ impl<'a> Reader<'a> for SliceReader<'a> {
     }
 
     fn read_bytes(&mut self, len: usize) -> Result<&'a [u8]> {
-        let end = self.pos + len;
-        if end > self.slice.len() {
-            return Err("reader: reached end of slice".into());
+        if len > self.slice.len() - self.pos {
+            return self.end_of_slice();
         }
-        let value = &self.slice[self.pos..end];
+        let value = &self.slice[self.pos..self.pos + len];
         self.pos += len;
         Ok(value)
     }
+
+    fn is_empty(&mut self) -> Result<bool> {
+        let empty = self.pos >= self.slice.len();
+        self.end |= empty;
+        Ok(empty)
+    }
+
+    fn position(&self) -> usize {
+        self.pos
+    }
+
+    fn slice(&self, start: usize, end: usize) -> &'a [u8] {
+        &self.slice[start..end]
+    }
 }
@@ -0,0 +1,344 @@
+//! Schema description of the shaft wire format.
+//!
+//! A [`Schema`] describes the layout a type produces on the wire. It can be
+//! declared by hand or traced from a type implementing `Deserialize`, and two
+//! schemas can be compared with [`check_compat`] to find out whether data
+//! written with one can still be decoded with the other.
+//!
+//! # Examples
+//!
+//! ```
+//! // Bring serde Deserialize derivable trait into scope.
+//! use serde::Deserialize;
+//! // Bring schema items into scope.
+//! use shaft::schema::{self, Compatibility, Schema};
+//!
+//! // Define old version of struct.
+//! #[derive(Deserialize)]
+//! #[serde(rename = "Player")]
+//! struct PlayerV1 {
+//!     name: String,
+//!     score: u32,
+//! }
+//!
+//! // Define new version of struct.
+//! #[derive(Deserialize)]
+//! #[serde(rename = "Player")]
+//! struct PlayerV2 {
+//!     name: String,
+//!     score: u64,
+//! }
+//!
+//! // Trace both schemas.
+//! let old = Schema::trace::<PlayerV1>().expect("trace error");
+//! let new = Schema::trace::<PlayerV2>().expect("trace error");
+//!
+//! // Changing an integer width breaks previously written data.
+//! let report = schema::check_compat(&old, &new);
+//! assert_eq!(report.compatibility(), Compatibility::Breaking);
+//! ```
+
+mod compat;
+mod fingerprint;
+mod trace;
+
+use std::collections::BTreeMap;
+use std::fmt::{self, Display};
+
+use serde::Deserialize;
+
+use crate::error::Result;
+
+pub use compat::{check_compat, check_compat_with, Change, ChangeKind, Compatibility, Report};
+
+/// Layout of a value on the wire.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Format {
+    Unit,
+    Bool,
+    I8,
+    I16,
+    I32,
+    I64,
+    U8,
+    U16,
+    U32,
+    U64,
+    F32,
+    F64,
+    Char,
+    Str,
+    Bytes,
+    Option(Box<Format>),
+    Seq(Box<Format>),
+    Map(Box<Format>, Box<Format>),
+    Tuple(Vec<Format>),
+    /// Reference to a named [`Container`] of the schema.
+    Named(String),
+    /// Value encoded by a [`with`](crate::with) adapter, named by its module
+    /// (e.g. `varint`).
+    Adapted(String, Box<Format>),
+}
+
+impl Format {
+    /// Returns the width in bytes of an integer format.
+    pub fn integer_width(&self) -> Option<usize> {
+        match self {
+            Format::I8 | Format::U8 => Some(1),
+            Format::I16 | Format::U16 => Some(2),
+            Format::I32 | Format::U32 => Some(4),
+            Format::I64 | Format::U64 => Some(8),
+            _ => None,
+        }
+    }
+}
+
+impl Display for Format {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Format::Unit => fmt.write_str("()"),
+            Format::Bool => fmt.write_str("bool"),
+            Format::I8 => fmt.write_str("i8"),
+            Format::I16 => fmt.write_str("i16"),
+            Format::I32 => fmt.write_str("i32"),
+            Format::I64 => fmt.write_str("i64"),
+            Format::U8 => fmt.write_str("u8"),
+            Format::U16 => fmt.write_str("u16"),
+            Format::U32 => fmt.write_str("u32"),
+            Format::U64 => fmt.write_str("u64"),
+            Format::F32 => fmt.write_str("f32"),
+            Format::F64 => fmt.write_str("f64"),
+            Format::Char => fmt.write_str("char"),
+            Format::Str => fmt.write_str("str"),
+            Format::Bytes => fmt.write_str("bytes"),
+            Format::Option(inner) => write!(fmt, "option<{inner}>"),
+            Format::Seq(inner) => write!(fmt, "seq<{inner}>"),
+            Format::Map(key, value) => write!(fmt, "map<{key}, {value}>"),
+            Format::Tuple(formats) => {
+                fmt.write_str("(")?;
+                for (index, format) in formats.iter().enumerate() {
+                    if index > 0 {
+                        fmt.write_str(", ")?;
+                    }
+                    write!(fmt, "{format}")?;
+                }
+                fmt.write_str(")")
+            }
+            Format::Named(name) => fmt.write_str(name),
+            Format::Adapted(adapter, inner) => write!(fmt, "with::{adapter}<{inner}>"),
+        }
+    }
+}
+
+/// Named struct field.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Field {
+    pub name: String,
+    pub format: Format,
+    /// Whether the field can be missing from tagged input, as with `Option`
+    /// fields and `#[serde(default)]`.
+    pub optional: bool,
+    /// Whether the field and the fields after it can be missing from the end
+    /// of positional input, as with `#[serde(default)]`.
+    pub default: bool,
+}
+
+impl Field {
+    /// Create a new required field.
+    pub fn new(name: impl Into<String>, format: Format) -> Self {
+        Self {
+            name: name.into(),
+            format,
+            optional: false,
+            default: false,
+        }
+    }
+
+    /// Set whether the field can be missing from tagged input.
+    pub fn with_optional(mut self, optional: bool) -> Self {
+        self.optional = optional;
+        self
+    }
+
+    /// Set whether the field and the fields after it can be missing from the
+    /// end of positional input.
+    pub fn with_default(mut self, default: bool) -> Self {
+        self.default = default;
+        self
+    }
+}
+
+/// Named enum variant.
+///
+/// The wire index of a variant is its position in [`Container::Enum`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Variant {
+    pub name: String,
+    pub payload: Payload,
+}
+
+impl Variant {
+    /// Create a new variant.
+    pub fn new(name: impl Into<String>, payload: Payload) -> Self {
+        Self {
+            name: name.into(),
+            payload,
+        }
+    }
+}
+
+/// Payload carried by an enum variant.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Payload {
+    Unit,
+    Newtype(Format),
+    Tuple(Vec<Format>),
+    Struct(Vec<Field>),
+}
+
+/// Named type definition.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Container {
+    UnitStruct,
+    NewtypeStruct(Format),
+    TupleStruct(Vec<Format>),
+    Struct(Vec<Field>),
+    Enum(Vec<Variant>),
+}
+
+/// Description of the wire layout of a type.
+///
+/// A schema is made of a root [`Format`] and the [`Container`] definitions
+/// referenced through [`Format::Named`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Schema {
+    root: Format,
+    containers: BTreeMap<String, Container>,
+}
+
+impl Schema {
+    /// Create a new schema with the given root format and no containers.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// // Bring schema items into scope.
+    /// use shaft::schema::{Container, Field, Format, Schema};
+    ///
+    /// // Declare schema by hand.
+    /// let schema = Schema::new(Format::Named("Player".to_owned())).with_container(
+    ///     "Player",
+    ///     Container::Struct(vec![
+    ///         Field::new("name", Format::Str),
+    ///         Field::new("score", Format::U32),
+    ///     ]),
+    /// );
+    /// ```
+    pub fn new(root: Format) -> Self {
+        Self {
+            root,
+            containers: BTreeMap::new(),
+        }
+    }
+
+    /// Trace the schema of a type.
+    ///
+    /// Tracing drives the `Deserialize` implementation of the type with
+    /// placeholder values, so types rejecting zero or empty values (such as
+    /// `NonZeroU32`) cannot be traced. Enums are traced once per variant and
+    /// recursive types are supported as long as every recursive path goes
+    /// through an option, a sequence, a map or an enum.
+    ///
+    /// Whether struct fields can be missing is found by tracing the type again
+    /// with each field left out. Fields that later passes cannot reach again,
+    /// such as fields of an enum variant only reachable through another
+    /// variant, are considered required.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// // Bring serde Deserialize derivable trait into scope.
+    /// use serde::Deserialize;
+    /// // Bring schema items into scope.
+    /// use shaft::schema::{Container, Field, Format, Schema};
+    ///
+    /// // Define custom struct.
+    /// #[derive(Deserialize)]
+    /// struct Player {
+    ///     name: String,
+    ///     score: u32,
+    /// }
+    ///
+    /// // Trace schema.
+    /// let schema = Schema::trace::<Player>().expect("trace error");
+    ///
+    /// // Check traced layout.
+    /// assert_eq!(schema.root(), &Format::Named("Player".to_owned()));
+    /// assert_eq!(
+    ///     schema.container("Player"),
+    ///     Some(&Container::Struct(vec![
+    ///         Field::new("name", Format::Str),
+    ///         Field::new("score", Format::U32),
+    ///     ]))
+    /// );
+    /// ```
+    pub fn trace<'de, T>() -> Result<Self>
+    where
+        T: Deserialize<'de>,
+    {
+        trace::trace::<T>()
+    }
+
+    /// Add a container definition to the schema.
+    pub fn with_container(mut self, name: impl Into<String>, container: Container) -> Self {
+        self.insert(name, container);
+        self
+    }
+
+    /// Insert a container definition, returning the previous definition.
+    pub fn insert(&mut self, name: impl Into<String>, container: Container) -> Option<Container> {
+        self.containers.insert(name.into(), container)
+    }
+
+    /// Returns the root format.
+    pub fn root(&self) -> &Format {
+        &self.root
+    }
+
+    /// Returns the definition of a container.
+    pub fn container(&self, name: &str) -> Option<&Container> {
+        self.containers.get(name)
+    }
+
+    /// Returns a 64-bit fingerprint of the layout described by the schema.
+    ///
+    /// The fingerprint covers formats, field names and variant names reachable
+    /// from the root. Container names are not covered, so renaming a type
+    /// keeps its fingerprint.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// // Bring schema items into scope.
+    /// use shaft::schema::{Container, Field, Format, Schema};
+    ///
+    /// // Declare two schemas differing by a field type.
+    /// let old = Schema::new(Format::Named("Player".to_owned()))
+    ///     .with_container("Player", Container::Struct(vec![Field::new("score", Format::U32)]));
+    /// let new = Schema::new(Format::Named("Player".to_owned()))
+    ///     .with_container("Player", Container::Struct(vec![Field::new("score", Format::U64)]));
+    ///
+    /// // Fingerprints differ.
+    /// assert_ne!(old.fingerprint(), new.fingerprint());
+    /// ```
+    pub fn fingerprint(&self) -> u64 {
+        fingerprint::fingerprint(self)
+    }
+
+    /// Returns an iterator over container definitions sorted by name.
+    pub fn containers(&self) -> impl Iterator<Item = (&str, &Container)> {
+        self.containers
+            .iter()
+            .map(|(name, container)| (name.as_str(), container))
+    }
+}
@@ -0,0 +1,763 @@
+use std::collections::HashSet;
+use std::fmt::{self, Display};
+
+use crate::{
+    options::{FieldTag, Options, StructEncoding, VariantEncoding},
+    schema::{Container, Field, Format, Payload, Schema, Variant},
+};
+
+/// Compare two schemas and report the changes affecting the wire format.
+///
+/// With default options, struct fields are encoded positionally and enum
+/// variants by index, so field and variant names only matter to match items
+/// between the schemas: renaming is compatible while moving, inserting or
+/// removing is breaking. Appending an enum variant is forward-only. Adding,
+/// removing or changing the [`with`](crate::with) adapter of a value is
+/// breaking.
+///
+/// # Examples
+///
+/// ```
+/// // Bring schema items into scope.
+/// use shaft::schema::{self, ChangeKind, Compatibility, Container, Format, Payload, Schema, Variant};
+///
+/// // Declare old enum with two variants.
+/// let old = Schema::new(Format::Named("Shape".to_owned())).with_container(
+///     "Shape",
+///     Container::Enum(vec![
+///         Variant::new("Circle", Payload::Newtype(Format::F64)),
+///         Variant::new("Square", Payload::Newtype(Format::F64)),
+///     ]),
+/// );
+///
+/// // Declare new enum with an appended variant.
+/// let new = Schema::new(Format::Named("Shape".to_owned())).with_container(
+///     "Shape",
+///     Container::Enum(vec![
+///         Variant::new("Circle", Payload::Newtype(Format::F64)),
+///         Variant::new("Square", Payload::Newtype(Format::F64)),
+///         Variant::new("Point", Payload::Unit),
+///     ]),
+/// );
+///
+/// // Old data can still be decoded, but old readers cannot decode points.
+/// let report = schema::check_compat(&old, &new);
+/// assert_eq!(report.compatibility(), Compatibility::ForwardOnly);
+/// assert_eq!(
+///     report.changes()[0].kind(),
+///     &ChangeKind::VariantAppended {
+///         name: "Point".to_owned()
+///     }
+/// );
+/// ```
+pub fn check_compat(old: &Schema, new: &Schema) -> Report {
+    check_compat_with(old, new, Options::default())
+}
+
+/// Compare two schemas for data encoded with the given options.
+///
+/// With [`StructEncoding::Tagged`], adding an optional field or a field with a
+/// default value is forward-only, see [`Field::optional`]. Likewise with
+/// [`Options::with_trailing_defaults`], appending fields with a default value
+/// to a struct ending its input is forward-only, see [`Field::default`].
+/// Adding any other field is breaking. Fields tagged by
+/// [`FieldTag::Hash`] can be moved but not renamed while fields tagged by
+/// [`FieldTag::Index`] can be renamed but not moved. Likewise, enum variants
+/// encoded by [`VariantEncoding::Name`] or [`VariantEncoding::Hash`] can be
+/// moved or inserted but not renamed.
+///
+/// # Examples
+///
+/// ```
+/// // Bring schema items into scope.
+/// use shaft::schema::{self, Compatibility, Container, Field, Format, Schema};
+/// // Bring options into scope.
+/// use shaft::{FieldTag, Options, StructEncoding};
+///
+/// // Declare old struct.
+/// let old = Schema::new(Format::Named("Player".to_owned())).with_container(
+///     "Player",
+///     Container::Struct(vec![
+///         Field::new("name", Format::Str),
+///         Field::new("score", Format::U32),
+///     ]),
+/// );
+///
+/// // Declare new struct with reordered fields.
+/// let new = Schema::new(Format::Named("Player".to_owned())).with_container(
+///     "Player",
+///     Container::Struct(vec![
+///         Field::new("score", Format::U32),
+///         Field::new("name", Format::Str),
+///     ]),
+/// );
+///
+/// // Reordering is only compatible when fields are tagged by name.
+/// let options = Options::new().with_struct_encoding(StructEncoding::Tagged(FieldTag::Hash));
+/// let report = schema::check_compat_with(&old, &new, options);
+/// assert_eq!(report.compatibility(), Compatibility::Compatible);
+/// let report = schema::check_compat(&old, &new);
+/// assert_eq!(report.compatibility(), Compatibility::Breaking);
+/// ```
+pub fn check_compat_with(old: &Schema, new: &Schema, options: Options) -> Report {
+    let mut checker = Checker::new(old, new, options);
+    checker.compare_formats(String::new(), old.root(), new.root());
+    Report {
+        changes: checker.changes,
+    }
+}
+
+/// Compatibility of a schema change.
+///
+/// Variants are ordered from the least to the most severe.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Compatibility {
+    /// Data is interchangeable between the old and the new schema.
+    Compatible,
+    /// Data written with the old schema can be read with the new schema, but
+    /// data written with the new schema cannot be read with the old one.
+    ForwardOnly,
+    /// Data written with the old schema cannot be read with the new schema.
+    Breaking,
+}
+
+impl Display for Compatibility {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Compatibility::Compatible => fmt.write_str("compatible"),
+            Compatibility::ForwardOnly => fmt.write_str("forward-only"),
+            Compatibility::Breaking => fmt.write_str("breaking"),
+        }
+    }
+}
+
+/// Kind of schema change.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ChangeKind {
+    ContainerRenamed {
+        old: String,
+        new: String,
+    },
+    MissingDefinition {
+        name: String,
+    },
+    FieldRenamed {
+        old: String,
+        new: String,
+    },
+    FieldMoved {
+        name: String,
+        old_index: usize,
+        new_index: usize,
+    },
+    FieldInserted {
+        name: String,
+    },
+    FieldAppended {
+        name: String,
+    },
+    FieldRemoved {
+        name: String,
+    },
+    VariantRenamed {
+        old: String,
+        new: String,
+    },
+    VariantMoved {
+        name: String,
+        old_index: usize,
+        new_index: usize,
+    },
+    VariantInserted {
+        name: String,
+    },
+    VariantAppended {
+        name: String,
+    },
+    VariantRemoved {
+        name: String,
+    },
+    IntegerWidthChanged {
+        old: Format,
+        new: Format,
+    },
+    FormatChanged {
+        old: String,
+        new: String,
+    },
+}
+
+impl Display for ChangeKind {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChangeKind::ContainerRenamed { old, new } => {
+                write!(fmt, "container `{old}` renamed to `{new}`")
+            }
+            ChangeKind::MissingDefinition { name } => {
+                write!(fmt, "container `{name}` is not defined")
+            }
+            ChangeKind::FieldRenamed { old, new } => {
+                write!(fmt, "field `{old}` renamed to `{new}`")
+            }
+            ChangeKind::FieldMoved {
+                name,
+                old_index,
+                new_index,
+            } => write!(
+                fmt,
+                "field `{name}` moved from position {old_index} to {new_index}"
+            ),
+            ChangeKind::FieldInserted { name } => write!(fmt, "field `{name}` inserted"),
+            ChangeKind::FieldAppended { name } => write!(fmt, "field `{name}` appended"),
+            ChangeKind::FieldRemoved { name } => write!(fmt, "field `{name}` removed"),
+            ChangeKind::VariantRenamed { old, new } => {
+                write!(fmt, "variant `{old}` renamed to `{new}`")
+            }
+            ChangeKind::VariantMoved {
+                name,
+                old_index,
+                new_index,
+            } => write!(
+                fmt,
+                "variant `{name}` moved from index {old_index} to {new_index}"
+            ),
+            ChangeKind::VariantInserted { name } => write!(fmt, "variant `{name}` inserted"),
+            ChangeKind::VariantAppended { name } => write!(fmt, "variant `{name}` appended"),
+            ChangeKind::VariantRemoved { name } => write!(fmt, "variant `{name}` removed"),
+            ChangeKind::IntegerWidthChanged { old, new } => {
+                write!(fmt, "integer width changed from {old} to {new}")
+            }
+            ChangeKind::FormatChanged { old, new } => {
+                write!(fmt, "format changed from {old} to {new}")
+            }
+        }
+    }
+}
+
+/// Schema change found by [`check_compat`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Change {
+    path: String,
+    kind: ChangeKind,
+    compatibility: Compatibility,
+}
+
+impl Change {
+    /// Returns the path of the changed value (e.g. `.orders[].sku`).
+    pub fn path(&self) -> &str {
+        if self.path.is_empty() {
+            "."
+        } else {
+            &self.path
+        }
+    }
+
+    /// Returns the kind of change.
+    pub fn kind(&self) -> &ChangeKind {
+        &self.kind
+    }
+
+    /// Returns the compatibility of the change.
+    pub fn compatibility(&self) -> Compatibility {
+        self.compatibility
+    }
+}
+
+impl Display for Change {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            fmt,
+            "{}: {} ({})",
+            self.path(),
+            self.kind,
+            self.compatibility
+        )
+    }
+}
+
+/// Result of [`check_compat`].
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Report {
+    changes: Vec<Change>,
+}
+
+impl Report {
+    /// Returns the changes found between the schemas.
+    pub fn changes(&self) -> &[Change] {
+        &self.changes
+    }
+
+    /// Returns the overall compatibility, that is the most severe change.
+    pub fn compatibility(&self) -> Compatibility {
+        self.changes
+            .iter()
+            .map(Change::compatibility)
+            .max()
+            .unwrap_or(Compatibility::Compatible)
+    }
+
+    /// Returns whether data written with the old schema can be read with the
+    /// new schema.
+    pub fn is_compatible(&self) -> bool {
+        self.compatibility() != Compatibility::Breaking
+    }
+}
+
+impl Display for Report {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for change in &self.changes {
+            writeln!(fmt, "{change}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Wire layout of a value with named containers resolved.
+#[derive(Clone, Copy)]
+enum View<'s> {
+    Format(&'s Format),
+    Tuple(&'s [Format]),
+    Struct(&'s [Field]),
+    Enum(&'s [Variant]),
+}
+
+impl Display for View<'_> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            View::Format(format) => write!(fmt, "{format}"),
+            View::Tuple(formats) => write!(fmt, "{}", Format::Tuple(formats.to_vec())),
+            View::Struct(fields) => {
+                fmt.write_str("{")?;
+                for (index, field) in fields.iter().enumerate() {
+                    if index > 0 {
+                        fmt.write_str(",")?;
+                    }
+                    write!(fmt, " {}: {}", field.name, field.format)?;
+                }
+                fmt.write_str(" }")
+            }
+            View::Enum(variants) => {
+                fmt.write_str("enum {")?;
+                for (index, variant) in variants.iter().enumerate() {
+                    if index > 0 {
+                        fmt.write_str(",")?;
+                    }
+                    write!(fmt, " {}", variant.name)?;
+                }
+                fmt.write_str(" }")
+            }
+        }
+    }
+}
+
+/// Matching of named items between two schemas.
+struct Alignment {
+    /// Items with the same name.
+    kept: Vec<(usize, usize)>,
+    /// Items at the same position whose names exist in one schema only.
+    renamed: Vec<usize>,
+    added: Vec<usize>,
+    removed: Vec<usize>,
+}
+
+impl Alignment {
+    fn new(old: &[&str], new: &[&str]) -> Self {
+        let mut kept = Vec::new();
+        let mut renamed = Vec::new();
+        for (index, name) in old.iter().enumerate() {
+            match new.iter().position(|other| other == name) {
+                Some(other) => kept.push((index, other)),
+                None => {
+                    if let Some(other) = new.get(index) {
+                        if !old.contains(other) {
+                            renamed.push(index);
+                        }
+                    }
+                }
+            }
+        }
+        let added = (0..new.len())
+            .filter(|index| !old.contains(&new[*index]) && !renamed.contains(index))
+            .collect();
+        let removed = (0..old.len())
+            .filter(|index| !new.contains(&old[*index]) && !renamed.contains(index))
+            .collect();
+        Self {
+            kept,
+            renamed,
+            added,
+            removed,
+        }
+    }
+}
+
+struct Checker<'s> {
+    old: &'s Schema,
+    new: &'s Schema,
+    options: Options,
+    /// Whether the compared value ends its enclosing region of the input.
+    at_end: bool,
+    visited: HashSet<(&'s str, &'s str, bool)>,
+    changes: Vec<Change>,
+}
+
+impl<'s> Checker<'s> {
+    fn new(old: &'s Schema, new: &'s Schema, options: Options) -> Self {
+        Self {
+            old,
+            new,
+            options,
+            at_end: true,
+            visited: HashSet::new(),
+            changes: Vec::new(),
+        }
+    }
+
+    fn push(&mut self, path: &str, kind: ChangeKind, compatibility: Compatibility) {
+        self.changes.push(Change {
+            path: path.to_owned(),
+            kind,
+            compatibility,
+        });
+    }
+
+    /// Resolve named containers, returning `None` for undefined containers.
+    fn view(&mut self, schema: &'s Schema, path: &str, format: &'s Format) -> Option<View<'s>> {
+        let Format::Named(name) = format else {
+            return Some(match format {
+                Format::Tuple(formats) => View::Tuple(formats),
+                format => View::Format(format),
+            });
+        };
+        let Some(container) = schema.container(name) else {
+            let name = name.clone();
+            self.push(
+                path,
+                ChangeKind::MissingDefinition { name },
+                Compatibility::Breaking,
+            );
+            return None;
+        };
+        match container {
+            Container::UnitStruct => Some(View::Tuple(&[])),
+            Container::NewtypeStruct(format) => self.view(schema, path, format),
+            Container::TupleStruct(formats) => Some(View::Tuple(formats)),
+            Container::Struct(fields) => Some(View::Struct(fields)),
+            Container::Enum(variants) => Some(View::Enum(variants)),
+        }
+    }
+
+    fn compare_formats(&mut self, path: String, old: &'s Format, new: &'s Format) {
+        if let (Format::Named(old_name), Format::Named(new_name)) = (old, new) {
+            // Position only matters to decoding when trailing defaults are enabled.
+            let at_end = self.at_end && self.options.trailing_defaults();
+            if !self.visited.insert((old_name, new_name, at_end)) {
+                return;
+            }
+            if old_name != new_name {
+                self.push(
+                    &path,
+                    ChangeKind::ContainerRenamed {
+                        old: old_name.clone(),
+                        new: new_name.clone(),
+                    },
+                    Compatibility::Compatible,
+                );
+            }
+        }
+        let Some(old) = self.view(self.old, &path, old) else {
+            return;
+        };
+        let Some(new) = self.view(self.new, &path, new) else {
+            return;
+        };
+        self.compare_views(path, old, new);
+    }
+
+    fn compare_within(&mut self, at_end: bool, path: String, old: &'s Format, new: &'s Format) {
+        let outer = std::mem::replace(&mut self.at_end, at_end);
+        self.compare_formats(path, old, new);
+        self.at_end = outer;
+    }
+
+    fn compare_views(&mut self, path: String, old: View<'s>, new: View<'s>) {
+        match (old, new) {
+            (View::Format(old), View::Format(new)) => self.compare_plain(path, old, new),
+            (View::Format(Format::Unit), View::Tuple([]))
+            | (View::Tuple([]), View::Format(Format::Unit)) => {}
+            (View::Tuple(old), View::Tuple(new)) if old.len() == new.len() => {
+                for (index, (old, format)) in old.iter().zip(new).enumerate() {
+                    let at_end = self.at_end && index + 1 == new.len();
+                    self.compare_within(at_end, format!("{path}.{index}"), old, format);
+                }
+            }
+            (View::Struct(old), View::Struct(new)) => self.compare_fields(path, old, new),
+            (View::Tuple(old), View::Struct(new)) if old.len() == new.len() => {
+                for (index, (old, field)) in old.iter().zip(new).enumerate() {
+                    let at_end = self.at_end && index + 1 == new.len();
+                    let path = format!("{path}.{}", field.name);
+                    self.compare_within(at_end, path, old, &field.format);
+                }
+            }
+            (View::Struct(old), View::Tuple(new)) if old.len() == new.len() => {
+                for (index, (field, new)) in old.iter().zip(new).enumerate() {
+                    let at_end = self.at_end && index + 1 == old.len();
+                    let path = format!("{path}.{index}");
+                    self.compare_within(at_end, path, &field.format, new);
+                }
+            }
+            (View::Enum(old), View::Enum(new)) => self.compare_variants(path, old, new),
+            (old, new) => self.push(
+                &path,
+                ChangeKind::FormatChanged {
+                    old: old.to_string(),
+                    new: new.to_string(),
+                },
+                Compatibility::Breaking,
+            ),
+        }
+    }
+
+    fn compare_plain(&mut self, path: String, old: &'s Format, new: &'s Format) {
+        match (old, new) {
+            (Format::Option(old), Format::Option(new)) => {
+                self.compare_formats(format!("{path}?"), old, new)
+            }
+            (Format::Seq(old), Format::Seq(new)) => {
+                self.compare_within(false, format!("{path}[]"), old, new)
+            }
+            (Format::Map(old_key, old_value), Format::Map(new_key, new_value)) => {
+                self.compare_within(false, format!("{path}{{key}}"), old_key, new_key);
+                self.compare_within(false, format!("{path}{{value}}"), old_value, new_value);
+            }
+            (Format::Adapted(old_adapter, old), Format::Adapted(new_adapter, new))
+                if old_adapter == new_adapter =>
+            {
+                self.compare_formats(path, old, new)
+            }
+            (old, new) if old == new => {}
+            (old, new) => match (old.integer_width(), new.integer_width()) {
+                (Some(old_width), Some(new_width)) if old_width != new_width => self.push(
+                    &path,
+                    ChangeKind::IntegerWidthChanged {
+                        old: old.clone(),
+                        new: new.clone(),
+                    },
+                    Compatibility::Breaking,
+                ),
+                _ => self.push(
+                    &path,
+                    ChangeKind::FormatChanged {
+                        old: old.to_string(),
+                        new: new.to_string(),
+                    },
+                    Compatibility::Breaking,
+                ),
+            },
+        }
+    }
+
+    fn compare_fields(&mut self, path: String, old: &'s [Field], new: &'s [Field]) {
+        use Compatibility::{Breaking, Compatible, ForwardOnly};
+        // Compatibility of moved, renamed, appended, inserted, removed trailing
+        // and removed inner fields.
+        let (moved, renamed, appended, inserted, trailing, removed) =
+            match self.options.struct_encoding() {
+                StructEncoding::Positional if self.options.trailing_defaults() && self.at_end => (
+                    Breaking,
+                    Compatible,
+                    ForwardOnly,
+                    Breaking,
+                    Breaking,
+                    Breaking,
+                ),
+                StructEncoding::Positional => {
+                    (Breaking, Compatible, Breaking, Breaking, Breaking, Breaking)
+                }
+                StructEncoding::Tagged(FieldTag::Hash) => (
+                    Compatible,
+                    Breaking,
+                    ForwardOnly,
+                    ForwardOnly,
+                    ForwardOnly,
+                    ForwardOnly,
+                ),
+                StructEncoding::Tagged(FieldTag::Index) => (
+                    Breaking,
+                    Compatible,
+                    ForwardOnly,
+                    Breaking,
+                    ForwardOnly,
+                    Breaking,
+                ),
+            };
+        let old_names: Vec<&str> = old.iter().map(|field| field.name.as_str()).collect();
+        let new_names: Vec<&str> = new.iter().map(|field| field.name.as_str()).collect();
+        let alignment = Alignment::new(&old_names, &new_names);
+        // Tagged fields are length-delimited regions of their own.
+        let tagged = self.options.struct_encoding() != StructEncoding::Positional;
+        let outer_end = self.at_end;
+        let at_end = |index: usize| tagged || outer_end && index + 1 == new.len();
+        for (old_index, new_index) in alignment.kept {
+            let name = &new[new_index].name;
+            if old_index != new_index {
+                self.push(
+                    &path,
+                    ChangeKind::FieldMoved {
+                        name: name.clone(),
+                        old_index,
+                        new_index,
+                    },
+                    moved,
+                );
+            }
+            self.compare_within(
+                at_end(new_index),
+                format!("{path}.{name}"),
+                &old[old_index].format,
+                &new[new_index].format,
+            );
+        }
+        for index in alignment.renamed {
+            self.push(
+                &path,
+                ChangeKind::FieldRenamed {
+                    old: old[index].name.clone(),
+                    new: new[index].name.clone(),
+                },
+                renamed,
+            );
+            self.compare_within(
+                at_end(index),
+                format!("{path}.{}", new[index].name),
+                &old[index].format,
+                &new[index].format,
+            );
+        }
+        for index in alignment.added {
+            let field = &new[index];
+            // Old data lacks the field, which must be allowed to be missing.
+            let missing = if tagged {
+                field.optional || field.default
+            } else {
+                field.default
+            };
+            let required = |compatibility| match compatibility {
+                ForwardOnly if !missing => Breaking,
+                compatibility => compatibility,
+            };
+            let name = field.name.clone();
+            if index >= old.len() {
+                self.push(
+                    &path,
+                    ChangeKind::FieldAppended { name },
+                    required(appended),
+                );
+            } else {
+                self.push(
+                    &path,
+                    ChangeKind::FieldInserted { name },
+                    required(inserted),
+                );
+            }
+        }
+        for index in alignment.removed {
+            let name = old[index].name.clone();
+            let compatibility = if index >= new.len() {
+                trailing
+            } else {
+                removed
+            };
+            self.push(&path, ChangeKind::FieldRemoved { name }, compatibility);
+        }
+    }
+
+    fn compare_variants(&mut self, path: String, old: &'s [Variant], new: &'s [Variant]) {
+        use Compatibility::{Breaking, Compatible, ForwardOnly};
+        // Compatibility of moved, renamed, appended and inserted variants.
+        let (moved, renamed, appended, inserted) = match self.options.variant_encoding() {
+            VariantEncoding::Index => (Breaking, Compatible, ForwardOnly, Breaking),
+            VariantEncoding::Name | VariantEncoding::Hash => {
+                (Compatible, Breaking, ForwardOnly, ForwardOnly)
+            }
+        };
+        let old_names: Vec<&str> = old.iter().map(|variant| variant.name.as_str()).collect();
+        let new_names: Vec<&str> = new.iter().map(|variant| variant.name.as_str()).collect();
+        let alignment = Alignment::new(&old_names, &new_names);
+        for (old_index, new_index) in alignment.kept {
+            let name = &new[new_index].name;
+            if old_index != new_index {
+                self.push(
+                    &path,
+                    ChangeKind::VariantMoved {
+                        name: name.clone(),
+                        old_index,
+                        new_index,
+                    },
+                    moved,
+                );
+            }
+            self.compare_payloads(
+                format!("{path}::{name}"),
+                &old[old_index].payload,
+                &new[new_index].payload,
+            );
+        }
+        for index in alignment.renamed {
+            self.push(
+                &path,
+                ChangeKind::VariantRenamed {
+                    old: old[index].name.clone(),
+                    new: new[index].name.clone(),
+                },
+                renamed,
+            );
+            self.compare_payloads(
+                format!("{path}::{}", new[index].name),
+                &old[index].payload,
+                &new[index].payload,
+            );
+        }
+        for index in alignment.added {
+            let name = new[index].name.clone();
+            if index >= old.len() {
+                self.push(&path, ChangeKind::VariantAppended { name }, appended);
+            } else {
+                self.push(&path, ChangeKind::VariantInserted { name }, inserted);
+            }
+        }
+        for index in alignment.removed {
+            let name = old[index].name.clone();
+            self.push(&path, ChangeKind::VariantRemoved { name }, Breaking);
+        }
+    }
+
+    fn compare_payloads(&mut self, path: String, old: &'s Payload, new: &'s Payload) {
+        match (old, new) {
+            (Payload::Newtype(old), Payload::Newtype(new)) => self.compare_formats(path, old, new),
+            (old, new) => {
+                let Some(old) = self.payload_view(self.old, &path, old) else {
+                    return;
+                };
+                let Some(new) = self.payload_view(self.new, &path, new) else {
+                    return;
+                };
+                self.compare_views(path, old, new);
+            }
+        }
+    }
+
+    fn payload_view(
+        &mut self,
+        schema: &'s Schema,
+        path: &str,
+        payload: &'s Payload,
+    ) -> Option<View<'s>> {
+        match payload {
+            Payload::Unit => Some(View::Tuple(&[])),
+            Payload::Newtype(format) => self.view(schema, path, format),
+            Payload::Tuple(formats) => Some(View::Tuple(formats)),
+            Payload::Struct(fields) => Some(View::Struct(fields)),
+        }
+    }
+}
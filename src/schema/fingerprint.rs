@@ -0,0 +1,164 @@
+use std::collections::HashMap;
+
+use crate::schema::{Container, Field, Format, Payload, Schema, Variant};
+
+/// Compute the structural fingerprint of a schema.
+///
+/// Containers are hashed by definition where they are referenced rather than
+/// by name, and references back to a container being hashed are replaced by
+/// the order in which it was first reached.
+pub(super) fn fingerprint(schema: &Schema) -> u64 {
+    let mut hasher = Hasher::new(schema);
+    hasher.format(schema.root());
+    hasher.hash
+}
+
+struct Hasher<'s> {
+    schema: &'s Schema,
+    visited: HashMap<&'s str, u64>,
+    hash: u64,
+}
+
+impl<'s> Hasher<'s> {
+    fn new(schema: &'s Schema) -> Self {
+        Self {
+            schema,
+            visited: HashMap::new(),
+            // 64-bit FNV-1a offset basis.
+            hash: 0xcbf2_9ce4_8422_2325,
+        }
+    }
+
+    fn bytes(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.hash = (self.hash ^ *byte as u64).wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+
+    fn tag(&mut self, tag: u8) {
+        self.bytes(&[tag]);
+    }
+
+    fn len(&mut self, len: usize) {
+        self.bytes(&(len as u64).to_le_bytes());
+    }
+
+    fn name(&mut self, name: &str) {
+        self.len(name.len());
+        self.bytes(name.as_bytes());
+    }
+
+    fn format(&mut self, format: &'s Format) {
+        match format {
+            Format::Unit => self.tag(0),
+            Format::Bool => self.tag(1),
+            Format::I8 => self.tag(2),
+            Format::I16 => self.tag(3),
+            Format::I32 => self.tag(4),
+            Format::I64 => self.tag(5),
+            Format::U8 => self.tag(6),
+            Format::U16 => self.tag(7),
+            Format::U32 => self.tag(8),
+            Format::U64 => self.tag(9),
+            Format::F32 => self.tag(10),
+            Format::F64 => self.tag(11),
+            Format::Char => self.tag(12),
+            Format::Str => self.tag(13),
+            Format::Bytes => self.tag(14),
+            Format::Option(inner) => {
+                self.tag(15);
+                self.format(inner);
+            }
+            Format::Seq(inner) => {
+                self.tag(16);
+                self.format(inner);
+            }
+            Format::Map(key, value) => {
+                self.tag(17);
+                self.format(key);
+                self.format(value);
+            }
+            Format::Tuple(formats) => {
+                self.tag(18);
+                self.formats(formats);
+            }
+            Format::Named(name) => self.named(name),
+            Format::Adapted(adapter, inner) => {
+                self.tag(26);
+                self.name(adapter);
+                self.format(inner);
+            }
+        }
+    }
+
+    fn formats(&mut self, formats: &'s [Format]) {
+        self.len(formats.len());
+        for format in formats {
+            self.format(format);
+        }
+    }
+
+    fn fields(&mut self, fields: &'s [Field]) {
+        self.len(fields.len());
+        for field in fields {
+            self.name(&field.name);
+            self.format(&field.format);
+        }
+    }
+
+    fn variants(&mut self, variants: &'s [Variant]) {
+        self.len(variants.len());
+        for variant in variants {
+            self.name(&variant.name);
+            match &variant.payload {
+                Payload::Unit => self.tag(0),
+                Payload::Newtype(format) => {
+                    self.tag(1);
+                    self.format(format);
+                }
+                Payload::Tuple(formats) => {
+                    self.tag(2);
+                    self.formats(formats);
+                }
+                Payload::Struct(fields) => {
+                    self.tag(3);
+                    self.fields(fields);
+                }
+            }
+        }
+    }
+
+    fn named(&mut self, name: &'s str) {
+        if let Some(&index) = self.visited.get(name) {
+            self.tag(19);
+            self.bytes(&index.to_le_bytes());
+            return;
+        }
+        let Some(container) = self.schema.container(name) else {
+            self.tag(20);
+            self.name(name);
+            return;
+        };
+        let index = self.visited.len() as u64;
+        self.visited.insert(name, index);
+        match container {
+            Container::UnitStruct => self.tag(21),
+            Container::NewtypeStruct(format) => {
+                self.tag(22);
+                self.format(format);
+            }
+            Container::TupleStruct(formats) => {
+                self.tag(23);
+                self.formats(formats);
+            }
+            Container::Struct(fields) => {
+                self.tag(24);
+                self.fields(fields);
+            }
+            Container::Enum(variants) => {
+                self.tag(25);
+                self.variants(variants);
+            }
+        }
+    }
+}
@@ -0,0 +1,893 @@
+use std::collections::BTreeMap;
+
+use serde::de::{
+    self,
+    value::{BorrowedBytesDeserializer, BorrowedStrDeserializer, U32Deserializer},
+    IntoDeserializer,
+};
+
+use crate::{
+    error::{Error, Result},
+    lazy,
+    options::Options,
+    raw,
+    schema::{Container, Field, Format, Payload, Schema, Variant},
+    with,
+};
+
+/// Maximum nesting of containers while tracing.
+const MAX_DEPTH: usize = 256;
+
+pub fn trace<'de, T>() -> Result<Schema>
+where
+    T: de::Deserialize<'de>,
+{
+    let mut tracer = Tracer::default();
+    let root = loop {
+        let traced = tracer.traced_variants();
+        let mut root = Format::Unit;
+        T::deserialize(Deserializer::new(&mut tracer, &mut root))?;
+        match tracer.incomplete_variant() {
+            None => break root,
+            Some((name, variant)) if tracer.traced_variants() == traced => {
+                return Err(format!("schema: cannot trace variant `{variant}` of `{name}`").into());
+            }
+            Some(_) => {}
+        }
+    };
+    // Probe one field per pass, a failed pass meaning the field is required.
+    tracer.probing = true;
+    loop {
+        let mut format = Format::Unit;
+        let result = T::deserialize(Deserializer::new(&mut tracer, &mut format));
+        let Some(probe) = tracer.probe.take() else {
+            result?;
+            break;
+        };
+        tracer.frames.clear();
+        let slots = tracer.fields.get_mut(&probe.key).expect("unknown fields");
+        slots[probe.index][probe.omit as usize] = Some(result.is_ok());
+    }
+    tracer.into_schema(root)
+}
+
+/// Container being traced.
+struct Frame {
+    name: &'static str,
+    variant: Option<u32>,
+    tainted: bool,
+}
+
+/// Name and variant index of a struct container or variant.
+type StructKey = (&'static str, Option<u32>);
+
+/// Fields left out to probe whether a field can be missing.
+#[derive(Clone, Copy)]
+enum Omit {
+    /// The field alone, as from tagged input.
+    Field,
+    /// The field and the fields after it, as from positional input.
+    Rest,
+}
+
+/// Field probed during a pass.
+#[derive(Clone, Copy)]
+struct Probe {
+    key: StructKey,
+    index: usize,
+    omit: Omit,
+}
+
+/// Tracing state shared across passes.
+#[derive(Default)]
+struct Tracer {
+    frames: Vec<Frame>,
+    containers: BTreeMap<String, Container>,
+    enums: BTreeMap<&'static str, (&'static [&'static str], Vec<Option<Payload>>)>,
+    /// Whether struct fields can be missing, by container and variant and
+    /// indexed by [`Omit`], once probed.
+    fields: BTreeMap<StructKey, Vec<[Option<bool>; 2]>>,
+    /// Whether passes probe fields.
+    probing: bool,
+    probe: Option<Probe>,
+}
+
+impl Tracer {
+    fn enter(&mut self, name: &'static str, variant: Option<u32>) -> Result<()> {
+        if self.frames.len() >= MAX_DEPTH {
+            return Err(format!("schema: cannot trace `{name}`, nesting is too deep").into());
+        }
+        self.frames.push(Frame {
+            name,
+            variant,
+            tainted: false,
+        });
+        Ok(())
+    }
+
+    fn leave(&mut self) -> Frame {
+        self.frames.pop().expect("unbalanced schema frames")
+    }
+
+    /// Returns whether optional values should be left empty.
+    ///
+    /// Values are left empty inside a container that is already being traced
+    /// further up the stack, which bounds the recursion of recursive types.
+    /// The partial definition of such a container is not recorded: the outer
+    /// occurrence records the complete one.
+    fn escape(&mut self) -> bool {
+        let Some((frame, frames)) = self.frames.split_last_mut() else {
+            return false;
+        };
+        if frames.iter().any(|outer| outer.name == frame.name) {
+            frame.tainted = true;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn record(&mut self, name: &str, container: Container) -> Result<()> {
+        match self.containers.get(name) {
+            Some(existing) if *existing != container => {
+                Err(format!("schema: conflicting definitions of `{name}`").into())
+            }
+            Some(_) => Ok(()),
+            None => {
+                self.containers.insert(name.to_owned(), container);
+                Ok(())
+            }
+        }
+    }
+
+    fn record_variant(&mut self, name: &'static str, index: u32, payload: Payload) -> Result<()> {
+        let (_, payloads) = self.enums.get_mut(name).expect("unknown enum");
+        match &payloads[index as usize] {
+            Some(existing) if *existing != payload => {
+                Err(format!("schema: conflicting definitions of `{name}`").into())
+            }
+            Some(_) => Ok(()),
+            None => {
+                payloads[index as usize] = Some(payload);
+                Ok(())
+            }
+        }
+    }
+
+    /// Choose the variant to trace for an enum.
+    ///
+    /// Untraced variants are preferred, except when the enum is already being
+    /// traced further up the stack where traced unit variants are preferred to
+    /// end the recursion. Variants currently being traced are never chosen.
+    fn choose_variant(
+        &mut self,
+        name: &'static str,
+        variants: &'static [&'static str],
+    ) -> Result<u32> {
+        if variants.is_empty() {
+            return Err(format!("schema: cannot trace empty enum `{name}`").into());
+        }
+        let (_, payloads) = self
+            .enums
+            .entry(name)
+            .or_insert_with(|| (variants, vec![None; variants.len()]));
+        if payloads.len() != variants.len() {
+            return Err(format!("schema: conflicting definitions of `{name}`").into());
+        }
+        let frames = &self.frames;
+        let reentrant = frames.iter().any(|frame| frame.name == name);
+        let available = |index: usize| {
+            !frames
+                .iter()
+                .any(|frame| frame.name == name && frame.variant == Some(index as u32))
+        };
+        let untraced = payloads
+            .iter()
+            .enumerate()
+            .find(|(index, payload)| payload.is_none() && available(*index));
+        let unit = payloads
+            .iter()
+            .enumerate()
+            .find(|(index, payload)| matches!(payload, Some(Payload::Unit)) && available(*index));
+        let traced = payloads
+            .iter()
+            .enumerate()
+            .find(|(index, payload)| payload.is_some() && available(*index));
+        let fields = &self.fields;
+        let unprobed = payloads.iter().enumerate().find(|(index, _)| {
+            available(*index)
+                && fields
+                    .get(&(name, Some(*index as u32)))
+                    .is_some_and(|slots| slots.iter().flatten().any(Option::is_none))
+        });
+        let chosen = if reentrant {
+            unit.or(untraced).or(unprobed).or(traced)
+        } else {
+            untraced.or(unprobed).or(unit).or(traced)
+        };
+        match chosen {
+            Some((index, _)) => Ok(index as u32),
+            None => Err(format!("schema: cannot trace recursive enum `{name}`").into()),
+        }
+    }
+
+    /// Start probing a field of the struct being traced if none was probed
+    /// during the pass yet.
+    ///
+    /// The struct is not recorded during the probe, its fields being left
+    /// out.
+    fn start_probe(&mut self, len: usize) -> Option<Probe> {
+        let frame = self.frames.last_mut()?;
+        let key = (frame.name, frame.variant);
+        let slots = self
+            .fields
+            .entry(key)
+            .or_insert_with(|| vec![[None; 2]; len]);
+        if !self.probing || self.probe.is_some() {
+            return None;
+        }
+        let (index, omit) = slots.iter().enumerate().find_map(|(index, slot)| {
+            match slot {
+                [None, _] => Some(Omit::Field),
+                [_, None] => Some(Omit::Rest),
+                _ => None,
+            }
+            .map(|omit| (index, omit))
+        })?;
+        frame.tainted = true;
+        let probe = Probe { key, index, omit };
+        self.probe = Some(probe);
+        Some(probe)
+    }
+
+    fn traced_variants(&self) -> usize {
+        self.enums
+            .values()
+            .flat_map(|(_, payloads)| payloads)
+            .filter(|payload| payload.is_some())
+            .count()
+    }
+
+    fn incomplete_variant(&self) -> Option<(&'static str, &'static str)> {
+        self.enums.iter().find_map(|(name, (variants, payloads))| {
+            payloads
+                .iter()
+                .position(Option::is_none)
+                .map(|index| (*name, variants[index]))
+        })
+    }
+
+    fn into_schema(mut self, root: Format) -> Result<Schema> {
+        for ((name, variant), slots) in &self.fields {
+            let fields = match variant {
+                None => match self.containers.get_mut(*name) {
+                    Some(Container::Struct(fields)) => fields,
+                    _ => continue,
+                },
+                Some(index) => match self.enums.get_mut(name) {
+                    Some((_, payloads)) => match payloads.get_mut(*index as usize) {
+                        Some(Some(Payload::Struct(fields))) => fields,
+                        _ => continue,
+                    },
+                    None => continue,
+                },
+            };
+            for (field, [optional, default]) in fields.iter_mut().zip(slots) {
+                field.optional = optional.unwrap_or(false);
+                field.default = default.unwrap_or(false);
+            }
+        }
+        let mut schema = Schema::new(root);
+        schema.containers = self.containers;
+        for (name, (variants, payloads)) in self.enums {
+            let variants = variants
+                .iter()
+                .zip(payloads)
+                .map(|(variant, payload)| Variant::new(*variant, payload.expect("untraced")))
+                .collect();
+            if schema.insert(name, Container::Enum(variants)).is_some() {
+                return Err(format!("schema: conflicting definitions of `{name}`").into());
+            }
+        }
+        Ok(schema)
+    }
+}
+
+fn traced(formats: Vec<Option<Format>>) -> Result<Vec<Format>> {
+    formats
+        .into_iter()
+        .map(|format| format.ok_or_else(|| "schema: value was not traced".into()))
+        .collect()
+}
+
+fn traced_fields(names: &[&str], formats: Vec<Option<Format>>) -> Result<Vec<Field>> {
+    let formats = traced(formats)?;
+    Ok(names
+        .iter()
+        .zip(formats)
+        .map(|(name, format)| Field::new(*name, format))
+        .collect())
+}
+
+/// Visit the fields of a struct, or probe whether one of them can be missing.
+fn visit_fields<'de, V>(
+    tracer: &mut Tracer,
+    fields: &'static [&'static str],
+    formats: &mut [Option<Format>],
+    visitor: V,
+) -> Result<V::Value>
+where
+    V: de::Visitor<'de>,
+{
+    match tracer.start_probe(fields.len()) {
+        Some(Probe {
+            index,
+            omit: Omit::Field,
+            ..
+        }) => visitor.visit_map(FieldAccess::new(tracer, fields, index)),
+        Some(Probe {
+            index,
+            omit: Omit::Rest,
+            ..
+        }) => visitor.visit_seq(SeqAccess::new(tracer, &mut formats[..index])),
+        None => visitor.visit_seq(SeqAccess::new(tracer, formats)),
+    }
+}
+
+struct Deserializer<'t> {
+    tracer: &'t mut Tracer,
+    format: &'t mut Format,
+}
+
+impl<'t> Deserializer<'t> {
+    fn new(tracer: &'t mut Tracer, format: &'t mut Format) -> Self {
+        Self { tracer, format }
+    }
+}
+
+impl<'de, 't> de::Deserializer<'de> for Deserializer<'t> {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, _: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        Err("schema: self-describing values cannot be traced".into())
+    }
+
+    fn deserialize_bool<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        *self.format = Format::Bool;
+        visitor.visit_bool(false)
+    }
+
+    fn deserialize_i8<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        *self.format = Format::I8;
+        visitor.visit_i8(0)
+    }
+
+    fn deserialize_i16<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        *self.format = Format::I16;
+        visitor.visit_i16(0)
+    }
+
+    fn deserialize_i32<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        *self.format = Format::I32;
+        visitor.visit_i32(0)
+    }
+
+    fn deserialize_i64<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        *self.format = Format::I64;
+        visitor.visit_i64(0)
+    }
+
+    fn deserialize_u8<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        *self.format = Format::U8;
+        visitor.visit_u8(0)
+    }
+
+    fn deserialize_u16<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        *self.format = Format::U16;
+        visitor.visit_u16(0)
+    }
+
+    fn deserialize_u32<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        *self.format = Format::U32;
+        visitor.visit_u32(0)
+    }
+
+    fn deserialize_u64<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        *self.format = Format::U64;
+        visitor.visit_u64(0)
+    }
+
+    fn deserialize_f32<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        *self.format = Format::F32;
+        visitor.visit_f32(0.0)
+    }
+
+    fn deserialize_f64<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        *self.format = Format::F64;
+        visitor.visit_f64(0.0)
+    }
+
+    fn deserialize_char<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        *self.format = Format::Char;
+        visitor.visit_char('\0')
+    }
+
+    fn deserialize_str<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        *self.format = Format::Str;
+        visitor.visit_borrowed_str("")
+    }
+
+    fn deserialize_string<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_bytes<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        *self.format = Format::Bytes;
+        visitor.visit_borrowed_bytes(&[])
+    }
+
+    fn deserialize_byte_buf<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.deserialize_bytes(visitor)
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        let mut inner = Format::Unit;
+        let value = if self.tracer.escape() {
+            visitor.visit_none::<Error>()?
+        } else {
+            visitor.visit_some(Deserializer::new(self.tracer, &mut inner))?
+        };
+        *self.format = Format::Option(Box::new(inner));
+        Ok(value)
+    }
+
+    fn deserialize_unit<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        *self.format = Format::Unit;
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V>(self, name: &'static str, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        *self.format = Format::Named(name.to_owned());
+        self.tracer.record(name, Container::UnitStruct)?;
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V>(self, name: &'static str, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        // Reserved names are not containers: adapters are traced as their
+        // inner value marked with the adapter, lazy values as bytes and raw
+        // values as the value they hold.
+        if let Some(adapter) = with::adapter(name) {
+            let mut inner = Format::Unit;
+            let value = visitor.visit_newtype_struct(Deserializer::new(self.tracer, &mut inner))?;
+            *self.format = Format::Adapted(adapter.to_owned(), Box::new(inner));
+            return Ok(value);
+        }
+        if name == lazy::NAME {
+            return visitor.visit_newtype_struct(self);
+        }
+        if name == raw::NAME {
+            return visitor.visit_seq(RawAccess {
+                tracer: self.tracer,
+                format: Some(self.format),
+                index: 0,
+            });
+        }
+        *self.format = Format::Named(name.to_owned());
+        self.tracer.enter(name, None)?;
+        let mut inner = Format::Unit;
+        let value = visitor.visit_newtype_struct(Deserializer::new(self.tracer, &mut inner))?;
+        if !self.tracer.leave().tainted {
+            self.tracer.record(name, Container::NewtypeStruct(inner))?;
+        }
+        Ok(value)
+    }
+
+    fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        let mut formats = if self.tracer.escape() {
+            vec![]
+        } else {
+            vec![None]
+        };
+        let value = visitor.visit_seq(SeqAccess::new(self.tracer, &mut formats))?;
+        let inner = formats.pop().flatten().unwrap_or(Format::Unit);
+        *self.format = Format::Seq(Box::new(inner));
+        Ok(value)
+    }
+
+    fn deserialize_tuple<V>(self, len: usize, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        let mut formats = vec![None; len];
+        let value = visitor.visit_seq(SeqAccess::new(self.tracer, &mut formats))?;
+        *self.format = Format::Tuple(traced(formats)?);
+        Ok(value)
+    }
+
+    fn deserialize_tuple_struct<V>(
+        self,
+        name: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        *self.format = Format::Named(name.to_owned());
+        self.tracer.enter(name, None)?;
+        let mut formats = vec![None; len];
+        let value = visitor.visit_seq(SeqAccess::new(self.tracer, &mut formats))?;
+        if !self.tracer.leave().tainted {
+            self.tracer
+                .record(name, Container::TupleStruct(traced(formats)?))?;
+        }
+        Ok(value)
+    }
+
+    fn deserialize_map<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        let mut key = Format::Unit;
+        let mut value = Format::Unit;
+        let result = if self.tracer.escape() {
+            visitor.visit_map(MapAccess::new(self.tracer, None))?
+        } else {
+            visitor.visit_map(MapAccess::new(self.tracer, Some((&mut key, &mut value))))?
+        };
+        *self.format = Format::Map(Box::new(key), Box::new(value));
+        Ok(result)
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        *self.format = Format::Named(name.to_owned());
+        self.tracer.enter(name, None)?;
+        let mut formats = vec![None; fields.len()];
+        let value = visit_fields(self.tracer, fields, &mut formats, visitor)?;
+        if !self.tracer.leave().tainted {
+            self.tracer
+                .record(name, Container::Struct(traced_fields(fields, formats)?))?;
+        }
+        Ok(value)
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        *self.format = Format::Named(name.to_owned());
+        let index = self.tracer.choose_variant(name, variants)?;
+        self.tracer.enter(name, Some(index))?;
+        let mut payload = None;
+        let value = visitor.visit_enum(EnumAccess::new(self.tracer, index, &mut payload))?;
+        if !self.tracer.leave().tainted {
+            let payload = payload.ok_or("schema: variant was not traced")?;
+            self.tracer.record_variant(name, index, payload)?;
+        }
+        Ok(value)
+    }
+
+    fn deserialize_identifier<V>(self, _: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        Err("schema: identifiers cannot be traced".into())
+    }
+
+    fn deserialize_ignored_any<V>(self, _: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        Err("schema: ignored values cannot be traced".into())
+    }
+
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+}
+
+struct SeqAccess<'t> {
+    tracer: &'t mut Tracer,
+    formats: std::slice::IterMut<'t, Option<Format>>,
+}
+
+impl<'t> SeqAccess<'t> {
+    fn new(tracer: &'t mut Tracer, formats: &'t mut [Option<Format>]) -> Self {
+        Self {
+            tracer,
+            formats: formats.iter_mut(),
+        }
+    }
+}
+
+impl<'de, 't> de::SeqAccess<'de> for SeqAccess<'t> {
+    type Error = Error;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>>
+    where
+        T: de::DeserializeSeed<'de>,
+    {
+        match self.formats.next() {
+            Some(slot) => {
+                let mut format = Format::Unit;
+                let value = seed.deserialize(Deserializer::new(self.tracer, &mut format))?;
+                *slot = Some(format);
+                Ok(Some(value))
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.formats.len())
+    }
+}
+
+/// Access to the value, options and bytes of a raw value.
+struct RawAccess<'t> {
+    tracer: &'t mut Tracer,
+    format: Option<&'t mut Format>,
+    index: usize,
+}
+
+impl<'de, 't> de::SeqAccess<'de> for RawAccess<'t> {
+    type Error = Error;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>>
+    where
+        T: de::DeserializeSeed<'de>,
+    {
+        self.index += 1;
+        match self.index {
+            1 => {
+                let format = self.format.take().expect("raw value traced twice");
+                seed.deserialize(Deserializer::new(self.tracer, format))
+                    .map(Some)
+            }
+            2 => {
+                let bits: U32Deserializer<Error> = Options::default().to_bits().into_deserializer();
+                seed.deserialize(bits).map(Some)
+            }
+            3 => seed
+                .deserialize(BorrowedBytesDeserializer::<Error>::new(&[]))
+                .map(Some),
+            _ => Ok(None),
+        }
+    }
+}
+
+/// Access to the fields of a struct but one, by name.
+struct FieldAccess<'t> {
+    tracer: &'t mut Tracer,
+    fields: std::iter::Enumerate<std::slice::Iter<'static, &'static str>>,
+    omitted: usize,
+}
+
+impl<'t> FieldAccess<'t> {
+    fn new(tracer: &'t mut Tracer, fields: &'static [&'static str], omitted: usize) -> Self {
+        Self {
+            tracer,
+            fields: fields.iter().enumerate(),
+            omitted,
+        }
+    }
+}
+
+impl<'de, 't> de::MapAccess<'de> for FieldAccess<'t> {
+    type Error = Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>>
+    where
+        K: de::DeserializeSeed<'de>,
+    {
+        let omitted = self.omitted;
+        match self.fields.find(|(index, _)| *index != omitted) {
+            Some((_, field)) => Ok(Some(
+                seed.deserialize(BorrowedStrDeserializer::<Error>::new(field))?,
+            )),
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value>
+    where
+        V: de::DeserializeSeed<'de>,
+    {
+        let mut format = Format::Unit;
+        seed.deserialize(Deserializer::new(self.tracer, &mut format))
+    }
+}
+
+struct MapAccess<'t> {
+    tracer: &'t mut Tracer,
+    key: Option<&'t mut Format>,
+    value: Option<&'t mut Format>,
+}
+
+impl<'t> MapAccess<'t> {
+    fn new(tracer: &'t mut Tracer, entry: Option<(&'t mut Format, &'t mut Format)>) -> Self {
+        let (key, value) = entry.unzip();
+        Self { tracer, key, value }
+    }
+}
+
+impl<'de, 't> de::MapAccess<'de> for MapAccess<'t> {
+    type Error = Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>>
+    where
+        K: de::DeserializeSeed<'de>,
+    {
+        match self.key.take() {
+            Some(format) => Ok(Some(
+                seed.deserialize(Deserializer::new(self.tracer, format))?,
+            )),
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value>
+    where
+        V: de::DeserializeSeed<'de>,
+    {
+        match self.value.take() {
+            Some(format) => seed.deserialize(Deserializer::new(self.tracer, format)),
+            None => Err("schema: map value without key".into()),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.key.iter().count())
+    }
+}
+
+struct EnumAccess<'t> {
+    tracer: &'t mut Tracer,
+    index: u32,
+    payload: &'t mut Option<Payload>,
+}
+
+impl<'t> EnumAccess<'t> {
+    fn new(tracer: &'t mut Tracer, index: u32, payload: &'t mut Option<Payload>) -> Self {
+        Self {
+            tracer,
+            index,
+            payload,
+        }
+    }
+}
+
+impl<'de, 't> de::EnumAccess<'de> for EnumAccess<'t> {
+    type Error = Error;
+
+    type Variant = Self;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self::Variant)>
+    where
+        V: de::DeserializeSeed<'de>,
+    {
+        let value = seed.deserialize::<U32Deserializer<Error>>(self.index.into_deserializer())?;
+        Ok((value, self))
+    }
+}
+
+impl<'de, 't> de::VariantAccess<'de> for EnumAccess<'t> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<()> {
+        *self.payload = Some(Payload::Unit);
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value>
+    where
+        T: de::DeserializeSeed<'de>,
+    {
+        let mut format = Format::Unit;
+        let value = seed.deserialize(Deserializer::new(self.tracer, &mut format))?;
+        *self.payload = Some(Payload::Newtype(format));
+        Ok(value)
+    }
+
+    fn tuple_variant<V>(self, len: usize, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        let mut formats = vec![None; len];
+        let value = visitor.visit_seq(SeqAccess::new(self.tracer, &mut formats))?;
+        *self.payload = Some(Payload::Tuple(traced(formats)?));
+        Ok(value)
+    }
+
+    fn struct_variant<V>(self, fields: &'static [&'static str], visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        let mut formats = vec![None; fields.len()];
+        let probed = self.tracer.probe.is_some();
+        let value = visit_fields(self.tracer, fields, &mut formats, visitor)?;
+        if probed || self.tracer.probe.is_none() {
+            *self.payload = Some(Payload::Struct(traced_fields(fields, formats)?));
+        }
+        Ok(value)
+    }
+}
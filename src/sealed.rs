@@ -0,0 +1,170 @@
+//! Authenticated encryption of serialized values.
+//!
+//! Available with the `crypto` feature.
+//!
+//! Values are serialized then encrypted with ChaCha20-Poly1305 under a random
+//! 96-bit nonce. Sealed bytes are made of the nonce followed by the ciphertext
+//! and the 128-bit authentication tag. Associated data is authenticated but not
+//! encrypted: it must be given again to open the sealed bytes.
+//!
+//! Sealed bytes that were tampered with, or opened with the wrong key or the
+//! wrong associated data, are rejected with [`ErrorKind::AuthenticationFailed`]
+//! before any decoding happens.
+//!
+//! Keys, and the plaintext bytes buffered while sealing or opening, are zeroed
+//! when dropped.
+//!
+//! # Examples
+//!
+//! ```
+//! // Bring serde Serialize/Deserialize derivable traits into scope.
+//! use serde::{Deserialize, Serialize};
+//! // Bring sealed items into scope.
+//! use shaft::sealed::{self, Key};
+//!
+//! // Define custom struct.
+//! #[derive(Deserialize, Serialize)]
+//! struct Token {
+//!     user: String,
+//!     secret: u64,
+//! }
+//!
+//! // Create value to seal.
+//! let token = Token {
+//!     user: "Ferris".to_owned(),
+//!     secret: 42,
+//! };
+//!
+//! // Seal value with a random key.
+//! let key = Key::generate();
+//! let bytes = sealed::to_bytes(&token, &key).expect("serialization error");
+//!
+//! // Open sealed bytes.
+//! let token: Token = sealed::from_bytes(&bytes, &key).expect("deserialization error");
+//! assert_eq!(token.secret, 42);
+//!
+//! // Sealed bytes cannot be opened with another key.
+//! let result: Result<Token, _> = sealed::from_bytes(&bytes, &Key::generate());
+//! assert!(result.is_err());
+//! ```
+
+use std::fmt::{self, Debug};
+
+use chacha20poly1305::{
+    aead::{Aead, AeadCore, KeyInit, OsRng, Payload},
+    ChaCha20Poly1305, Nonce,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use zeroize::{Zeroize, Zeroizing};
+
+use crate::{
+    error::{Error, ErrorKind, Result},
+    options::Options,
+};
+
+const NONCE_LEN: usize = 12;
+
+/// 256-bit secret key.
+///
+/// The key bytes are zeroed when the key is dropped.
+#[derive(Clone)]
+pub struct Key([u8; 32]);
+
+impl Key {
+    /// Create a key from bytes.
+    pub fn from_bytes(bytes: [u8; 32]) -> Self {
+        Self(bytes)
+    }
+
+    /// Generate a random key from the operating system random source.
+    pub fn generate() -> Self {
+        Self(ChaCha20Poly1305::generate_key(&mut OsRng).into())
+    }
+
+    /// Returns the bytes of the key.
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+}
+
+impl Drop for Key {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl Debug for Key {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.write_str("Key(..)")
+    }
+}
+
+/// Serialize and seal a value.
+pub fn to_bytes<T>(value: &T, key: &Key) -> Result<Vec<u8>>
+where
+    T: Serialize,
+{
+    to_bytes_with(value, key, &[], Options::default())
+}
+
+/// Serialize and seal a value with associated data and the given options.
+pub fn to_bytes_with<T>(
+    value: &T,
+    key: &Key,
+    associated_data: &[u8],
+    options: Options,
+) -> Result<Vec<u8>>
+where
+    T: Serialize,
+{
+    let plaintext = Zeroizing::new(crate::to_bytes_with(value, options)?);
+    let cipher = ChaCha20Poly1305::new(chacha20poly1305::Key::from_slice(&key.0));
+    let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let payload = Payload {
+        msg: &plaintext,
+        aad: associated_data,
+    };
+    let ciphertext = match cipher.encrypt(&nonce, payload) {
+        Ok(ciphertext) => ciphertext,
+        Err(_) => return Err("sealed: encryption failed".into()),
+    };
+    let mut bytes = Vec::with_capacity(NONCE_LEN + ciphertext.len());
+    bytes.extend(nonce);
+    bytes.extend(ciphertext);
+    Ok(bytes)
+}
+
+/// Open sealed bytes and deserialize a value.
+pub fn from_bytes<T>(bytes: &[u8], key: &Key) -> Result<T>
+where
+    T: DeserializeOwned,
+{
+    from_bytes_with(bytes, key, &[], Options::default())
+}
+
+/// Open sealed bytes with associated data and deserialize a value with the
+/// given options.
+pub fn from_bytes_with<T>(
+    bytes: &[u8],
+    key: &Key,
+    associated_data: &[u8],
+    options: Options,
+) -> Result<T>
+where
+    T: DeserializeOwned,
+{
+    if bytes.len() < NONCE_LEN {
+        return Err(Error::new(ErrorKind::AuthenticationFailed));
+    }
+    let (nonce, ciphertext) = bytes.split_at(NONCE_LEN);
+    let cipher = ChaCha20Poly1305::new(chacha20poly1305::Key::from_slice(&key.0));
+    let payload = Payload {
+        msg: ciphertext,
+        aad: associated_data,
+    };
+    let plaintext = match cipher.decrypt(Nonce::from_slice(nonce), payload) {
+        Ok(plaintext) => Zeroizing::new(plaintext),
+        Err(_) => return Err(Error::new(ErrorKind::AuthenticationFailed)),
+    };
+    crate::from_bytes_with(&plaintext, options)
+}
@@ -0,0 +1,375 @@
+//! Element-by-element decoding and encoding of top-level sequences.
+
+use std::{
+    io::{Read, Seek, SeekFrom, Write},
+    marker::PhantomData,
+};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::{
+    checksum::{self, ChecksumWriter, Hasher},
+    de::Deserializer,
+    error::Result,
+    options::{IntEncoding, LenWidth, Options},
+    read::SliceReader,
+    ser::Serializer,
+    write::IoWriter,
+};
+
+/// Size of the first read of [`ReaderSeqIter`], doubled while an element does
+/// not fit.
+const CHUNK_LEN: usize = 8 * 1024;
+
+/// Iterate over the elements of a sequence serialized into bytes.
+///
+/// The length prefix is read immediately, and each element is deserialized
+/// when the iterator reaches it, so the sequence is never materialized.
+///
+/// # Examples
+///
+/// ```
+/// // Bring serde Serialize/Deserialize derivable traits into scope.
+/// use serde::{Deserialize, Serialize};
+///
+/// // Define custom struct.
+/// #[derive(Deserialize, Serialize)]
+/// struct Event<'a> {
+///     id: u32,
+///     name: &'a str,
+/// }
+///
+/// // Serialize a sequence of events.
+/// let events = vec![Event { id: 1, name: "start" }, Event { id: 2, name: "stop" }];
+/// let bytes = shaft::to_bytes(&events).expect("serialization error");
+///
+/// // Deserialize events one by one.
+/// let mut iter = shaft::iter_seq::<Event>(&bytes).expect("deserialization error");
+/// let event = iter.next().unwrap().expect("deserialization error");
+/// assert_eq!(event.name, "start");
+/// assert_eq!(iter.count(), 1);
+/// ```
+pub fn iter_seq<'a, T>(bytes: &'a [u8]) -> Result<SeqIter<'a, T>>
+where
+    T: Deserialize<'a>,
+{
+    iter_seq_with(bytes, Options::default())
+}
+
+/// Iterate over the elements of a sequence serialized into bytes with the
+/// given options.
+///
+/// The checksum trailer is verified before the first element is read.
+pub fn iter_seq_with<'a, T>(bytes: &'a [u8], options: Options) -> Result<SeqIter<'a, T>>
+where
+    T: Deserialize<'a>,
+{
+    let bytes = checksum::verify(bytes, options.checksum())?;
+    let mut de = Deserializer::with_options(SliceReader::new(bytes), options);
+    let len = de.read_len().map_err(|error| de.locate(error))?;
+    Ok(SeqIter {
+        de,
+        len,
+        index: 0,
+        phantom: PhantomData,
+    })
+}
+
+/// Iterator over the elements of a sequence serialized into bytes.
+///
+/// Iteration ends after the first error. The length prefix is not trusted, so
+/// the size hint has no lower bound.
+pub struct SeqIter<'a, T> {
+    de: Deserializer<'a, SliceReader<'a>>,
+    len: usize,
+    index: usize,
+    phantom: PhantomData<fn() -> T>,
+}
+
+impl<'a, T: Deserialize<'a>> Iterator for SeqIter<'a, T> {
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.index >= self.len {
+            return None;
+        }
+        let result = self.de.element(self.index);
+        self.index = if result.is_ok() {
+            self.index + 1
+        } else {
+            self.len
+        };
+        Some(result)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.len - self.index))
+    }
+}
+
+/// Iterate over the elements of a sequence read from a reader.
+pub fn iter_seq_from_reader<R, T>(reader: R) -> Result<ReaderSeqIter<R, T>>
+where
+    R: Read,
+    T: DeserializeOwned,
+{
+    iter_seq_from_reader_with(reader, Options::default())
+}
+
+/// Iterate over the elements of a sequence read from a reader with the given
+/// options.
+///
+/// Bytes are read in chunks as elements are deserialized. The checksum
+/// trailer is verified after the last element, so elements are yielded before
+/// the whole sequence is known to be intact.
+pub fn iter_seq_from_reader_with<R, T>(reader: R, options: Options) -> Result<ReaderSeqIter<R, T>>
+where
+    R: Read,
+    T: DeserializeOwned,
+{
+    let mut iter = ReaderSeqIter {
+        reader,
+        options,
+        buf: Vec::new(),
+        pos: 0,
+        offset: 0,
+        hasher: Hasher::new(options.checksum()),
+        len: 0,
+        index: 0,
+        phantom: PhantomData,
+    };
+    iter.len = iter.decode(|de| de.read_len().map_err(|error| de.locate(error)))?;
+    if iter.len == 0 {
+        iter.verify_trailer()?;
+    }
+    Ok(iter)
+}
+
+/// Iterator over the elements of a sequence read from a reader.
+///
+/// Iteration ends after the first error. The length prefix is not trusted, so
+/// the size hint has no lower bound.
+pub struct ReaderSeqIter<R, T> {
+    reader: R,
+    options: Options,
+    /// Bytes read but not consumed yet start at `pos`.
+    buf: Vec<u8>,
+    pos: usize,
+    /// Offset of `pos` in the input.
+    offset: usize,
+    hasher: Hasher,
+    len: usize,
+    index: usize,
+    phantom: PhantomData<fn() -> T>,
+}
+
+impl<R: Read, T> ReaderSeqIter<R, T> {
+    /// Read more bytes into the buffer, returning the number of bytes read.
+    fn fill(&mut self) -> Result<usize> {
+        if self.pos > self.buf.len() / 2 {
+            self.buf.drain(..self.pos);
+            self.pos = 0;
+        }
+        let chunk = CHUNK_LEN.max(self.buf.len() - self.pos);
+        let read = (&mut self.reader)
+            .take(chunk as u64)
+            .read_to_end(&mut self.buf)?;
+        Ok(read)
+    }
+
+    /// Decode from the buffered bytes, reading more bytes and decoding again
+    /// while decoding reaches the end of the buffer.
+    ///
+    /// Decoding also reaches the end when it takes trailing defaults, which
+    /// only hold if the reader has no more bytes.
+    fn decode<V, F>(&mut self, mut decode: F) -> Result<V>
+    where
+        F: for<'b> FnMut(&mut Deserializer<'b, SliceReader<'b>>) -> Result<V>,
+    {
+        let mut exhausted = false;
+        loop {
+            let bytes = &self.buf[self.pos..];
+            let mut de = Deserializer::with_options(SliceReader::new(bytes), self.options);
+            de.set_base(self.offset);
+            let result = decode(&mut de);
+            match result {
+                Ok(value) if exhausted || !de.reached_end() => {
+                    let len = de.position() - self.offset;
+                    self.hasher.update(&bytes[..len]);
+                    self.pos += len;
+                    self.offset += len;
+                    return Ok(value);
+                }
+                Err(error) if exhausted || !de.reached_end() => return Err(error),
+                _ => {}
+            }
+            exhausted = self.fill()? == 0;
+        }
+    }
+
+    /// Read the checksum trailer and verify it.
+    fn verify_trailer(&mut self) -> Result<()> {
+        let len = checksum::trailer_len(self.options.checksum());
+        while self.buf.len() - self.pos < len {
+            if self.fill()? == 0 {
+                return Err("checksum: missing trailer".into());
+            }
+        }
+        checksum::verify_trailer(&self.hasher, &self.buf[self.pos..self.pos + len])
+    }
+
+    /// Returns the inner reader.
+    ///
+    /// Bytes read ahead of the current element are lost.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R: Read, T: DeserializeOwned> Iterator for ReaderSeqIter<R, T> {
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.index >= self.len {
+            return None;
+        }
+        let index = self.index;
+        let result = self.decode(|de| de.element(index));
+        let result = match result {
+            Ok(value) if index + 1 == self.len => self.verify_trailer().map(|()| value),
+            result => result,
+        };
+        self.index = if result.is_ok() { index + 1 } else { self.len };
+        Some(result)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.len - self.index))
+    }
+}
+
+/// Writer serializing the elements of a sequence one by one.
+///
+/// The bytes written are the same as serializing a `Vec` of the elements.
+///
+/// # Examples
+///
+/// ```
+/// // Bring sequence writer into scope.
+/// use shaft::SeqWriter;
+///
+/// // Serialize elements one by one.
+/// let mut writer = SeqWriter::new(Vec::new(), 3).expect("serialization error");
+/// for value in 1..=3u16 {
+///     writer.push(&value).expect("serialization error");
+/// }
+/// let bytes = writer.finish().expect("serialization error");
+/// assert_eq!(bytes, shaft::to_bytes(&vec![1u16, 2, 3]).expect("serialization error"));
+/// ```
+pub struct SeqWriter<W: Write> {
+    writer: ChecksumWriter<IoWriter<W>>,
+    options: Options,
+    len: Option<usize>,
+    /// Position of the length prefix, when back-patched.
+    start: u64,
+    count: usize,
+}
+
+impl<W: Write> SeqWriter<W> {
+    /// Create a writer of `len` elements with default options.
+    pub fn new(writer: W, len: usize) -> Result<Self> {
+        Self::with_options(writer, len, Options::default())
+    }
+
+    /// Create a writer of `len` elements with the given options, writing the
+    /// length prefix.
+    pub fn with_options(writer: W, len: usize, options: Options) -> Result<Self> {
+        let mut writer = ChecksumWriter::new(IoWriter::new(writer), options.checksum());
+        Serializer::with_options(&mut writer, options).write_len(len)?;
+        Ok(Self {
+            writer,
+            options,
+            len: Some(len),
+            start: 0,
+            count: 0,
+        })
+    }
+
+    /// Serialize the next element.
+    pub fn push<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        if self.len.is_some_and(|len| self.count >= len) {
+            return Err("seq: too many elements".into());
+        }
+        value.serialize(&mut Serializer::with_options(
+            &mut self.writer,
+            self.options,
+        ))?;
+        self.count += 1;
+        Ok(())
+    }
+
+    /// Returns the number of elements written.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Write the checksum trailer and return the inner writer.
+    ///
+    /// Fails if fewer elements than announced were written, or if the length
+    /// prefix must be back-patched.
+    pub fn finish(self) -> Result<W> {
+        match self.len {
+            Some(len) if self.count != len => {
+                Err(format!("seq: expected {len} elements, found {}", self.count).into())
+            }
+            Some(_) => Ok(self.writer.finish()?.into_inner()),
+            None => Err("seq: length must be back-patched".into()),
+        }
+    }
+}
+
+impl<W: Write + Seek> SeqWriter<W> {
+    /// Create a writer of an unknown number of elements, whose length prefix
+    /// is written by [`SeqWriter::finish_backpatched`].
+    ///
+    /// Back-patching rewrites the length prefix in place: it requires a
+    /// fixed-width length, so either fixed-width integers or
+    /// [`LenWidth::U8`], and no checksum.
+    pub fn backpatched(writer: W, options: Options) -> Result<Self> {
+        if options.int_encoding() == IntEncoding::Varint && options.len_width() != LenWidth::U8 {
+            return Err("seq: back-patched length requires fixed-width lengths".into());
+        }
+        if checksum::trailer_len(options.checksum()) != 0 {
+            return Err("seq: back-patched length requires no checksum".into());
+        }
+        let mut writer = ChecksumWriter::new(IoWriter::new(writer), options.checksum());
+        let start = writer.get_mut().get_mut().stream_position()?;
+        Serializer::with_options(&mut writer, options).write_len(0)?;
+        Ok(Self {
+            writer,
+            options,
+            len: None,
+            start,
+            count: 0,
+        })
+    }
+
+    /// Rewrite the length prefix with the number of elements written and
+    /// return the inner writer.
+    pub fn finish_backpatched(self) -> Result<W> {
+        if self.len.is_some() {
+            return self.finish();
+        }
+        let mut writer = self.writer.finish()?;
+        let inner = writer.get_mut();
+        let end = inner.stream_position()?;
+        inner.seek(SeekFrom::Start(self.start))?;
+        Serializer::with_options(&mut writer, self.options).write_len(self.count)?;
+        let inner = writer.get_mut();
+        inner.seek(SeekFrom::Start(end))?;
+        Ok(writer.into_inner())
+    }
+}
@@ -1,8 +1,16 @@
+use std::io;
+
 use serde::{ser, Serialize};
 
 use crate::{
+    checksum::ChecksumWriter,
     error::{Error, Result},
-    write::{VecWriter, Writer},
+    options::{
+        name_hash, Endian, FieldTag, IntEncoding, LenWidth, Options, StructEncoding,
+        VariantEncoding,
+    },
+    raw, with,
+    write::{IoWriter, VecWriter, Writer},
 };
 
 /// Serialize a value into bytes.
@@ -38,6 +46,84 @@ where
     Ok(writer.into_vec())
 }
 
+/// Serialize a value into bytes with the given options.
+///
+/// # Examples
+///
+/// ```
+/// // Bring serde Serialize/Deserialize derivable traits into scope.
+/// use serde::Serialize;
+/// // Bring options into scope.
+/// use shaft::{FieldTag, Options, StructEncoding};
+///
+/// // Define custom struct.
+/// #[derive(Serialize)]
+/// struct MyStruct {
+///     score: u8,
+/// }
+///
+/// // Create value to serialize.
+/// let value = MyStruct { score: 42 };
+///
+/// // Serialize value into bytes with tagged fields.
+/// let options = Options::new().with_struct_encoding(StructEncoding::Tagged(FieldTag::Index));
+/// let bytes = shaft::to_bytes_with(&value, options).expect("serialization error");
+///
+/// // Check field count, field tag, field length and field value.
+/// assert_eq!(bytes, [1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 42]);
+/// ```
+pub fn to_bytes_with<T>(value: &T, options: Options) -> Result<Vec<u8>>
+where
+    T: Serialize,
+{
+    Ok(to_writer_with(VecWriter::new(), value, options)?.into_vec())
+}
+
+/// Serialize a value into a writer with the given options, appending the
+/// checksum trailer, and return the writer.
+pub(crate) fn to_writer_with<W, T>(writer: W, value: &T, options: Options) -> Result<W>
+where
+    W: Writer,
+    T: ?Sized + Serialize,
+{
+    let writer = ChecksumWriter::new(writer, options.checksum());
+    let mut serializer = Serializer::with_options(writer, options);
+    value.serialize(&mut serializer)?;
+    serializer.writer.finish()
+}
+
+/// Serialize a value into an [`io::Write`] and return it.
+///
+/// Bytes are written as they are produced, without buffering the whole
+/// value, so writers receiving small writes poorly should be buffered.
+///
+/// # Examples
+///
+/// ```
+/// // Serialize value into a writer.
+/// let bytes = shaft::to_io_writer(Vec::new(), &(1u8, "Ferris")).expect("serialization error");
+/// assert_eq!(bytes, shaft::to_bytes(&(1u8, "Ferris")).expect("serialization error"));
+/// ```
+pub fn to_io_writer<W, T>(writer: W, value: &T) -> Result<W>
+where
+    W: io::Write,
+    T: ?Sized + Serialize,
+{
+    to_io_writer_with(writer, value, Options::default())
+}
+
+/// Serialize a value into an [`io::Write`] with the given options and return
+/// it.
+///
+/// See [`to_io_writer`].
+pub fn to_io_writer_with<W, T>(writer: W, value: &T, options: Options) -> Result<W>
+where
+    W: io::Write,
+    T: ?Sized + Serialize,
+{
+    Ok(to_writer_with(IoWriter::new(writer), value, options)?.into_inner())
+}
+
 pub fn to_writer<W, T>(writer: &mut W, value: &T) -> Result<()>
 where
     W: Writer,
@@ -49,11 +135,77 @@ where
 
 pub struct Serializer<W: Writer> {
     writer: W,
+    options: Options,
+    // Set while serializing a raw value, whose bytes are written verbatim.
+    raw: bool,
 }
 
 impl<W: Writer> Serializer<W> {
     pub fn new(writer: W) -> Self {
-        Self { writer }
+        Self::with_options(writer, Options::default())
+    }
+
+    pub fn with_options(writer: W, options: Options) -> Self {
+        Self {
+            writer,
+            options,
+            raw: false,
+        }
+    }
+}
+
+impl<W: Writer> Serializer<W> {
+    fn is_varint(&self) -> bool {
+        self.options.int_encoding() == IntEncoding::Varint
+    }
+
+    fn is_big_endian(&self) -> bool {
+        self.options.endian() == Endian::Big
+    }
+
+    fn write_varint(&mut self, mut value: u64) -> Result<()> {
+        while value >= 0x80 {
+            self.writer.write_u8(value as u8 | 0x80)?;
+            value >>= 7;
+        }
+        self.writer.write_u8(value as u8)
+    }
+
+    pub(crate) fn write_len(&mut self, len: usize) -> Result<()> {
+        let out_of_range = || Error::from("serialize: length out of range");
+        match self.options.len_width() {
+            LenWidth::U8 => {
+                let len = u8::try_from(len).map_err(|_| out_of_range())?;
+                ser::Serializer::serialize_u8(self, len)
+            }
+            LenWidth::U16 => {
+                let len = u16::try_from(len).map_err(|_| out_of_range())?;
+                ser::Serializer::serialize_u16(self, len)
+            }
+            LenWidth::U32 => {
+                let len = u32::try_from(len).map_err(|_| out_of_range())?;
+                ser::Serializer::serialize_u32(self, len)
+            }
+            LenWidth::U64 => ser::Serializer::serialize_u64(self, len as u64),
+        }
+    }
+
+    /// Serialize a value into a buffer with the same options.
+    fn encode<T>(&self, value: &T) -> Result<Vec<u8>>
+    where
+        T: ?Sized + Serialize,
+    {
+        let mut buffer = Serializer::with_options(VecWriter::new(), self.options);
+        value.serialize(&mut buffer)?;
+        Ok(buffer.writer.into_vec())
+    }
+
+    fn write_variant(&mut self, index: u32, variant: &'static str) -> Result<()> {
+        match self.options.variant_encoding() {
+            VariantEncoding::Index => ser::Serializer::serialize_u32(self, index),
+            VariantEncoding::Name => ser::Serializer::serialize_str(self, variant),
+            VariantEncoding::Hash => ser::Serializer::serialize_u32(self, name_hash(variant)),
+        }
     }
 }
 
@@ -67,9 +219,9 @@ where
     type SerializeTuple = Self;
     type SerializeTupleStruct = Self;
     type SerializeTupleVariant = Self;
-    type SerializeMap = Self;
-    type SerializeStruct = Self;
-    type SerializeStructVariant = Self;
+    type SerializeMap = MapSerializer<'a, W>;
+    type SerializeStruct = StructSerializer<'a, W>;
+    type SerializeStructVariant = StructSerializer<'a, W>;
 
     fn serialize_bool(self, value: bool) -> Result<()> {
         self.writer.write_bool(value)
@@ -80,15 +232,33 @@ where
     }
 
     fn serialize_i16(self, value: i16) -> Result<()> {
-        self.writer.write_i16(value)
+        if self.is_varint() {
+            self.write_varint(zigzag(value as i64))
+        } else if self.is_big_endian() {
+            self.writer.write_bytes(&value.to_be_bytes())
+        } else {
+            self.writer.write_i16(value)
+        }
     }
 
     fn serialize_i32(self, value: i32) -> Result<()> {
-        self.writer.write_i32(value)
+        if self.is_varint() {
+            self.write_varint(zigzag(value as i64))
+        } else if self.is_big_endian() {
+            self.writer.write_bytes(&value.to_be_bytes())
+        } else {
+            self.writer.write_i32(value)
+        }
     }
 
     fn serialize_i64(self, value: i64) -> Result<()> {
-        self.writer.write_i64(value)
+        if self.is_varint() {
+            self.write_varint(zigzag(value))
+        } else if self.is_big_endian() {
+            self.writer.write_bytes(&value.to_be_bytes())
+        } else {
+            self.writer.write_i64(value)
+        }
     }
 
     fn serialize_u8(self, value: u8) -> Result<()> {
@@ -96,24 +266,55 @@ where
     }
 
     fn serialize_u16(self, value: u16) -> Result<()> {
-        self.writer.write_u16(value)
+        if self.is_varint() {
+            self.write_varint(value as u64)
+        } else if self.is_big_endian() {
+            self.writer.write_bytes(&value.to_be_bytes())
+        } else {
+            self.writer.write_u16(value)
+        }
     }
 
     fn serialize_u32(self, value: u32) -> Result<()> {
-        self.writer.write_u32(value)?;
-        Ok(())
+        if self.is_varint() {
+            self.write_varint(value as u64)
+        } else if self.is_big_endian() {
+            self.writer.write_bytes(&value.to_be_bytes())
+        } else {
+            self.writer.write_u32(value)
+        }
     }
 
     fn serialize_u64(self, value: u64) -> Result<()> {
-        self.writer.write_u64(value)
+        if self.is_varint() {
+            self.write_varint(value)
+        } else if self.is_big_endian() {
+            self.writer.write_bytes(&value.to_be_bytes())
+        } else {
+            self.writer.write_u64(value)
+        }
     }
 
-    fn serialize_f32(self, value: f32) -> Result<()> {
-        self.writer.write_f32(value)
+    fn serialize_f32(self, mut value: f32) -> Result<()> {
+        if self.options.canonical() {
+            value = canonical_f32(value);
+        }
+        if self.is_big_endian() {
+            self.writer.write_bytes(&value.to_be_bytes())
+        } else {
+            self.writer.write_f32(value)
+        }
     }
 
-    fn serialize_f64(self, value: f64) -> Result<()> {
-        self.writer.write_f64(value)
+    fn serialize_f64(self, mut value: f64) -> Result<()> {
+        if self.options.canonical() {
+            value = canonical_f64(value);
+        }
+        if self.is_big_endian() {
+            self.writer.write_bytes(&value.to_be_bytes())
+        } else {
+            self.writer.write_f64(value)
+        }
     }
 
     fn serialize_char(self, value: char) -> Result<()> {
@@ -121,14 +322,15 @@ where
     }
 
     fn serialize_str(self, value: &str) -> Result<()> {
-        let len = value.len();
-        self.serialize_u64(len as u64)?;
+        self.write_len(value.len())?;
         self.writer.write_bytes(value.as_bytes())
     }
 
     fn serialize_bytes(self, value: &[u8]) -> Result<()> {
-        let len = value.len();
-        self.serialize_u64(len as u64)?;
+        if std::mem::take(&mut self.raw) {
+            return self.writer.write_bytes(value);
+        }
+        self.write_len(value.len())?;
         self.writer.write_bytes(value)
     }
 
@@ -152,35 +354,52 @@ where
         Ok(())
     }
 
-    fn serialize_unit_variant(self, _: &'static str, index: u32, _: &'static str) -> Result<()> {
-        self.serialize_u32(index)
+    fn serialize_unit_variant(
+        self,
+        _: &'static str,
+        index: u32,
+        variant: &'static str,
+    ) -> Result<()> {
+        self.write_variant(index, variant)
     }
 
-    fn serialize_newtype_struct<T>(self, _name: &'static str, value: &T) -> Result<()>
+    fn serialize_newtype_struct<T>(self, name: &'static str, value: &T) -> Result<()>
     where
         T: ?Sized + Serialize,
     {
-        value.serialize(self)
+        if name == raw::NAME {
+            self.raw = true;
+            let result = value.serialize(&mut *self);
+            self.raw = false;
+            return result;
+        }
+        let Some(options) = with::adapt(name, self.options) else {
+            return value.serialize(self);
+        };
+        let outer = std::mem::replace(&mut self.options, options);
+        let result = value.serialize(&mut *self);
+        self.options = outer;
+        result
     }
 
     fn serialize_newtype_variant<T>(
         self,
         _: &'static str,
         index: u32,
-        _: &'static str,
+        variant: &'static str,
         value: &T,
     ) -> Result<()>
     where
         T: ?Sized + Serialize,
     {
-        self.serialize_u32(index)?;
+        self.write_variant(index, variant)?;
         value.serialize(self)
     }
 
     fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq> {
         match len {
             Some(len) => {
-                self.serialize_u64(len as u64)?;
+                self.write_len(len)?;
             }
             None => {
                 return Err("serialize: unknown sequence length".into());
@@ -205,41 +424,46 @@ where
         self,
         _: &'static str,
         index: u32,
-        _: &'static str,
+        variant: &'static str,
         _: usize,
     ) -> Result<Self::SerializeTupleVariant> {
-        self.serialize_u32(index)?;
+        self.write_variant(index, variant)?;
         Ok(self)
     }
 
     fn serialize_map(self, len: Option<usize>) -> Result<Self::SerializeMap> {
         match len {
             Some(len) => {
-                self.serialize_u64(len as u64)?;
+                self.write_len(len)?;
             }
             None => {
                 return Err("serialize: unknown sequence length".into());
             }
         }
-        Ok(self)
+        Ok(MapSerializer::new(self))
     }
 
-    fn serialize_struct(self, _: &'static str, _: usize) -> Result<Self::SerializeStruct> {
-        Ok(self)
+    fn serialize_struct(self, _: &'static str, len: usize) -> Result<Self::SerializeStruct> {
+        StructSerializer::new(self, len)
     }
 
     fn serialize_struct_variant(
         self,
         _: &'static str,
         index: u32,
-        _: &'static str,
-        _: usize,
+        variant: &'static str,
+        len: usize,
     ) -> Result<Self::SerializeStructVariant> {
-        self.serialize_u32(index)?;
-        Ok(self)
+        self.write_variant(index, variant)?;
+        StructSerializer::new(self, len)
+    }
+
+    fn is_human_readable(&self) -> bool {
+        self.options.human_readable()
     }
 }
 
+#[allow(clippy::needless_lifetimes)]
 impl<'ser, W: Writer> ser::SerializeSeq for &'ser mut Serializer<W> {
     type Ok = ();
 
@@ -257,6 +481,7 @@ impl<'ser, W: Writer> ser::SerializeSeq for &'ser mut Serializer<W> {
     }
 }
 
+#[allow(clippy::needless_lifetimes)]
 impl<'ser, W: Writer> ser::SerializeTuple for &'ser mut Serializer<W> {
     type Ok = ();
 
@@ -274,6 +499,7 @@ impl<'ser, W: Writer> ser::SerializeTuple for &'ser mut Serializer<W> {
     }
 }
 
+#[allow(clippy::needless_lifetimes)]
 impl<'ser, W: Writer> ser::SerializeTupleStruct for &'ser mut Serializer<W> {
     type Ok = ();
 
@@ -291,6 +517,7 @@ impl<'ser, W: Writer> ser::SerializeTupleStruct for &'ser mut Serializer<W> {
     }
 }
 
+#[allow(clippy::needless_lifetimes)]
 impl<'ser, W: Writer> ser::SerializeTupleVariant for &'ser mut Serializer<W> {
     type Ok = ();
 
@@ -308,7 +535,27 @@ impl<'ser, W: Writer> ser::SerializeTupleVariant for &'ser mut Serializer<W> {
     }
 }
 
-impl<'ser, W: Writer> ser::SerializeMap for &'ser mut Serializer<W> {
+/// Serializer of map entries.
+///
+/// In canonical mode, entries are buffered and written sorted by the bytes of
+/// their encoded key.
+pub struct MapSerializer<'a, W: Writer> {
+    ser: &'a mut Serializer<W>,
+    entries: Vec<(Vec<u8>, Vec<u8>)>,
+    key: Option<Vec<u8>>,
+}
+
+impl<'a, W: Writer> MapSerializer<'a, W> {
+    fn new(ser: &'a mut Serializer<W>) -> Self {
+        Self {
+            ser,
+            entries: Vec::new(),
+            key: None,
+        }
+    }
+}
+
+impl<W: Writer> ser::SerializeMap for MapSerializer<'_, W> {
     type Ok = ();
 
     type Error = Error;
@@ -317,31 +564,88 @@ impl<'ser, W: Writer> ser::SerializeMap for &'ser mut Serializer<W> {
     where
         T: ?Sized + Serialize,
     {
-        key.serialize(&mut **self)
+        if !self.ser.options.canonical() {
+            return key.serialize(&mut *self.ser);
+        }
+        self.key = Some(self.ser.encode(key)?);
+        Ok(())
     }
 
     fn serialize_value<T>(&mut self, value: &T) -> Result<()>
     where
         T: ?Sized + Serialize,
     {
-        value.serialize(&mut **self)
+        if !self.ser.options.canonical() {
+            return value.serialize(&mut *self.ser);
+        }
+        let key = match self.key.take() {
+            Some(key) => key,
+            None => return Err("serialize: missing map key".into()),
+        };
+        let value = self.ser.encode(value)?;
+        self.entries.push((key, value));
+        Ok(())
     }
 
-    fn end(self) -> Result<()> {
+    fn end(mut self) -> Result<()> {
+        self.entries.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+        if self.entries.windows(2).any(|pair| pair[0].0 == pair[1].0) {
+            return Err("serialize: duplicate map key".into());
+        }
+        for (key, value) in &self.entries {
+            self.ser.writer.write_bytes(key)?;
+            self.ser.writer.write_bytes(value)?;
+        }
         Ok(())
     }
 }
 
-impl<'ser, W: Writer> ser::SerializeStruct for &'ser mut Serializer<W> {
+/// Serializer of struct fields.
+pub struct StructSerializer<'a, W: Writer> {
+    ser: &'a mut Serializer<W>,
+    index: u32,
+}
+
+impl<'a, W: Writer> StructSerializer<'a, W> {
+    fn new(ser: &'a mut Serializer<W>, len: usize) -> Result<Self> {
+        if let StructEncoding::Tagged(_) = ser.options.struct_encoding() {
+            ser.write_len(len)?;
+        }
+        Ok(Self { ser, index: 0 })
+    }
+
+    fn write_field<T>(&mut self, key: &'static str, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        let index = self.index;
+        self.index += 1;
+        let tag = match self.ser.options.struct_encoding() {
+            StructEncoding::Positional => return value.serialize(&mut *self.ser),
+            StructEncoding::Tagged(FieldTag::Hash) => name_hash(key),
+            StructEncoding::Tagged(FieldTag::Index) => index,
+        };
+        let bytes = self.ser.encode(value)?;
+        ser::Serializer::serialize_u32(&mut *self.ser, tag)?;
+        ser::Serializer::serialize_bytes(&mut *self.ser, &bytes)
+    }
+}
+
+impl<W: Writer> ser::SerializeStruct for StructSerializer<'_, W> {
     type Ok = ();
 
     type Error = Error;
 
-    fn serialize_field<T>(&mut self, _: &'static str, value: &T) -> Result<()>
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<()>
     where
         T: ?Sized + Serialize,
     {
-        value.serialize(&mut **self)
+        self.write_field(key, value)
+    }
+
+    fn skip_field(&mut self, _: &'static str) -> Result<()> {
+        self.index += 1;
+        Ok(())
     }
 
     fn end(self) -> Result<()> {
@@ -349,19 +653,51 @@ impl<'ser, W: Writer> ser::SerializeStruct for &'ser mut Serializer<W> {
     }
 }
 
-impl<'ser, W: Writer> ser::SerializeStructVariant for &'ser mut Serializer<W> {
+impl<W: Writer> ser::SerializeStructVariant for StructSerializer<'_, W> {
     type Ok = ();
 
     type Error = Error;
 
-    fn serialize_field<T>(&mut self, _: &'static str, value: &T) -> Result<()>
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<()>
     where
         T: ?Sized + Serialize,
     {
-        value.serialize(&mut **self)
+        self.write_field(key, value)
+    }
+
+    fn skip_field(&mut self, _: &'static str) -> Result<()> {
+        self.index += 1;
+        Ok(())
     }
 
     fn end(self) -> Result<()> {
         Ok(())
     }
 }
+
+/// Map signed integers to unsigned integers so that small magnitudes stay small.
+fn zigzag(value: i64) -> u64 {
+    ((value << 1) ^ (value >> 63)) as u64
+}
+
+/// Returns the canonical quiet NaN for NaN values and `0.0` for `-0.0`.
+pub(crate) fn canonical_f32(value: f32) -> f32 {
+    if value.is_nan() {
+        f32::from_bits(0x7fc0_0000)
+    } else if value == 0.0 {
+        0.0
+    } else {
+        value
+    }
+}
+
+/// Returns the canonical quiet NaN for NaN values and `0.0` for `-0.0`.
+pub(crate) fn canonical_f64(value: f64) -> f64 {
+    if value.is_nan() {
+        f64::from_bits(0x7ff8_0000_0000_0000)
+    } else if value == 0.0 {
+        0.0
+    } else {
+        value
+    }
+}
@@ -0,0 +1,120 @@
+//! Serialized values prefixed with a fingerprint of their type.
+//!
+//! The wire format is positional, so decoding bytes into the wrong type can
+//! succeed with garbage values. Typed payloads start with the `u64`
+//! fingerprint of the traced [`Schema`] of the serialized type, and decoding
+//! fails with [`ErrorKind::TypeMismatch`] before reading the body when the
+//! fingerprint of the target type differs.
+//!
+//! Fingerprints are computed by tracing the type on first use and cached per
+//! type, see [`Schema::trace`] for the types that can be traced and
+//! [`Schema::fingerprint`] for what the fingerprint covers.
+//!
+//! # Examples
+//!
+//! ```
+//! // Bring serde Serialize/Deserialize derivable traits into scope.
+//! use serde::{Deserialize, Serialize};
+//! // Bring error kind into scope.
+//! use shaft::ErrorKind;
+//!
+//! // Define two structs with the same wire layout.
+//! #[derive(Deserialize, Serialize)]
+//! struct Player {
+//!     score: u32,
+//! }
+//! #[derive(Debug, Deserialize)]
+//! struct Monster {
+//!     health: u32,
+//! }
+//!
+//! // Serialize value with its fingerprint.
+//! let bytes = shaft::typed::to_bytes(&Player { score: 42 }).expect("serialization error");
+//!
+//! // Decoding into the same type succeeds.
+//! let value: Player = shaft::typed::from_bytes(&bytes).expect("deserialization error");
+//! assert_eq!(value.score, 42);
+//!
+//! // Decoding into another type fails.
+//! let error = shaft::typed::from_bytes::<Monster>(&bytes).unwrap_err();
+//! assert!(matches!(error.kind(), ErrorKind::TypeMismatch { .. }));
+//! ```
+
+use std::{
+    any::TypeId,
+    collections::HashMap,
+    sync::{Mutex, OnceLock},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::{Error, ErrorKind, Result},
+    options::Options,
+    schema::Schema,
+};
+
+const FINGERPRINT_LEN: usize = 8;
+
+static FINGERPRINTS: OnceLock<Mutex<HashMap<TypeId, u64>>> = OnceLock::new();
+
+/// Returns the fingerprint of a type.
+pub fn fingerprint<'de, T>() -> Result<u64>
+where
+    T: Deserialize<'de>,
+{
+    let fingerprints = FINGERPRINTS.get_or_init(Default::default);
+    let id = typeid::of::<T>();
+    if let Some(fingerprint) = fingerprints.lock().unwrap().get(&id) {
+        return Ok(*fingerprint);
+    }
+    let fingerprint = Schema::trace::<T>()?.fingerprint();
+    fingerprints.lock().unwrap().insert(id, fingerprint);
+    Ok(fingerprint)
+}
+
+/// Serialize a value prefixed with the fingerprint of its type.
+pub fn to_bytes<'de, T>(value: &T) -> Result<Vec<u8>>
+where
+    T: Serialize + Deserialize<'de>,
+{
+    to_bytes_with(value, Options::default())
+}
+
+/// Serialize a value prefixed with the fingerprint of its type with the given
+/// options.
+pub fn to_bytes_with<'de, T>(value: &T, options: Options) -> Result<Vec<u8>>
+where
+    T: Serialize + Deserialize<'de>,
+{
+    let mut bytes = Vec::new();
+    bytes.extend(fingerprint::<T>()?.to_le_bytes());
+    bytes.extend(crate::to_bytes_with(value, options)?);
+    Ok(bytes)
+}
+
+/// Deserialize a value after checking the fingerprint of its type.
+pub fn from_bytes<'a, T>(bytes: &'a [u8]) -> Result<T>
+where
+    T: Deserialize<'a>,
+{
+    from_bytes_with(bytes, Options::default())
+}
+
+/// Deserialize a value after checking the fingerprint of its type with the
+/// given options.
+pub fn from_bytes_with<'a, T>(bytes: &'a [u8], options: Options) -> Result<T>
+where
+    T: Deserialize<'a>,
+{
+    if bytes.len() < FINGERPRINT_LEN {
+        return Err("typed: missing fingerprint".into());
+    }
+    let (found, body) = bytes.split_at(FINGERPRINT_LEN);
+    let found = u64::from_le_bytes(found.try_into().unwrap());
+    let expected = fingerprint::<T>()?;
+    if found != expected {
+        return Err(Error::new(ErrorKind::TypeMismatch { expected, found }));
+    }
+    crate::from_bytes_with(body, options)
+}
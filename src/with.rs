@@ -0,0 +1,329 @@
+//! Per-field encoding adapters.
+//!
+//! Each module of this module can be used with `#[serde(with = "...")]` to
+//! encode a single field differently from the [`Options`] the value is
+//! serialized with. Adapters that change the encoding wrap the field in a
+//! newtype struct with a reserved name recognized by shaft; other formats see
+//! a plain newtype struct and encode the field as usual.
+//!
+//! # Examples
+//!
+//! ```
+//! // Bring serde Serialize/Deserialize derivable traits into scope.
+//! use serde::{Deserialize, Serialize};
+//!
+//! // Define custom struct with per-field encodings.
+//! #[derive(Debug, PartialEq, Deserialize, Serialize)]
+//! struct Record {
+//!     #[serde(with = "shaft::with::varint")]
+//!     id: u64,
+//!     #[serde(with = "shaft::with::u8_len")]
+//!     name: String,
+//!     #[serde(with = "shaft::with::be")]
+//!     checksum: u32,
+//! }
+//!
+//! // Create value to serialize.
+//! let value = Record {
+//!     id: 300,
+//!     name: "Ferris".to_owned(),
+//!     checksum: 1,
+//! };
+//!
+//! // Serialize value with default options.
+//! let bytes = shaft::to_bytes(&value).expect("serialization error");
+//! assert_eq!(bytes, [0xac, 0x02, 6, b'F', b'e', b'r', b'r', b'i', b's', 0, 0, 0, 1]);
+//!
+//! // Deserialize value.
+//! let result: Record = shaft::from_bytes(&bytes).expect("deserialization error");
+//! assert_eq!(result, value);
+//! ```
+
+use std::{fmt, marker::PhantomData};
+
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::options::{Endian, IntEncoding, LenWidth, Options};
+
+const VARINT: &str = "$shaft::with::varint";
+const FIXED: &str = "$shaft::with::fixed";
+const BE: &str = "$shaft::with::be";
+const U8_LEN: &str = "$shaft::with::u8_len";
+const U16_LEN: &str = "$shaft::with::u16_len";
+
+/// Returns the options overridden by an adapter, if the name of a newtype
+/// struct is reserved for one.
+pub(crate) fn adapt(name: &str, options: Options) -> Option<Options> {
+    match name {
+        VARINT => Some(options.with_int_encoding(IntEncoding::Varint)),
+        FIXED => Some(options.with_int_encoding(IntEncoding::Fixed)),
+        BE => Some(options.with_endian(Endian::Big)),
+        U8_LEN => Some(options.with_len_width(LenWidth::U8)),
+        U16_LEN => Some(options.with_len_width(LenWidth::U16)),
+        _ => None,
+    }
+}
+
+/// Returns the name of the adapter module (e.g. `varint`), if the name of a
+/// newtype struct is reserved for one.
+pub(crate) fn adapter(name: &str) -> Option<&str> {
+    adapt(name, Options::default())?;
+    name.strip_prefix("$shaft::with::")
+}
+
+fn serialize_as<T, S>(name: &'static str, value: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: ?Sized + Serialize,
+    S: Serializer,
+{
+    serializer.serialize_newtype_struct(name, value)
+}
+
+fn deserialize_as<'de, T, D>(name: &'static str, deserializer: D) -> Result<T, D::Error>
+where
+    T: Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    struct Visitor<T>(PhantomData<T>);
+
+    impl<'de, T: Deserialize<'de>> de::Visitor<'de> for Visitor<T> {
+        type Value = T;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("a newtype struct")
+        }
+
+        fn visit_newtype_struct<D>(self, deserializer: D) -> Result<T, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            T::deserialize(deserializer)
+        }
+    }
+
+    deserializer.deserialize_newtype_struct(name, Visitor(PhantomData))
+}
+
+/// Encode integers of the field as variable-length integers.
+pub mod varint {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: ?Sized + Serialize,
+        S: Serializer,
+    {
+        super::serialize_as(super::VARINT, value, serializer)
+    }
+
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+    where
+        T: Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        super::deserialize_as(super::VARINT, deserializer)
+    }
+}
+
+/// Encode integers of the field with their full width.
+pub mod fixed {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: ?Sized + Serialize,
+        S: Serializer,
+    {
+        super::serialize_as(super::FIXED, value, serializer)
+    }
+
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+    where
+        T: Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        super::deserialize_as(super::FIXED, deserializer)
+    }
+}
+
+/// Encode integers and floats of the field in big-endian byte order.
+///
+/// Under [`IntEncoding::Varint`], integers are still written as
+/// variable-length integers, which have no byte order, so only floats are
+/// affected.
+pub mod be {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: ?Sized + Serialize,
+        S: Serializer,
+    {
+        super::serialize_as(super::BE, value, serializer)
+    }
+
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+    where
+        T: Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        super::deserialize_as(super::BE, deserializer)
+    }
+}
+
+/// Encode lengths of the field, such as the length of a string, as `u8`.
+pub mod u8_len {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: ?Sized + Serialize,
+        S: Serializer,
+    {
+        super::serialize_as(super::U8_LEN, value, serializer)
+    }
+
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+    where
+        T: Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        super::deserialize_as(super::U8_LEN, deserializer)
+    }
+}
+
+/// Encode lengths of the field, such as the length of a string, as `u16`.
+pub mod u16_len {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: ?Sized + Serialize,
+        S: Serializer,
+    {
+        super::serialize_as(super::U16_LEN, value, serializer)
+    }
+
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+    where
+        T: Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        super::deserialize_as(super::U16_LEN, deserializer)
+    }
+}
+
+/// Encode a byte vector or slice as bytes rather than as a sequence of `u8`.
+pub mod bytes {
+    use std::fmt;
+
+    use serde::{de, Deserializer, Serializer};
+
+    pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: ?Sized + AsRef<[u8]>,
+        S: Serializer,
+    {
+        serializer.serialize_bytes(value.as_ref())
+    }
+
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+    where
+        T: From<Vec<u8>>,
+        D: Deserializer<'de>,
+    {
+        struct Visitor;
+
+        impl<'de> de::Visitor<'de> for Visitor {
+            type Value = Vec<u8>;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("bytes")
+            }
+
+            fn visit_bytes<E: de::Error>(self, value: &[u8]) -> Result<Vec<u8>, E> {
+                Ok(value.to_vec())
+            }
+
+            fn visit_byte_buf<E: de::Error>(self, value: Vec<u8>) -> Result<Vec<u8>, E> {
+                Ok(value)
+            }
+
+            fn visit_seq<A>(self, mut seq: A) -> Result<Vec<u8>, A::Error>
+            where
+                A: de::SeqAccess<'de>,
+            {
+                let mut bytes = Vec::with_capacity(seq.size_hint().unwrap_or(0).min(4096));
+                while let Some(byte) = seq.next_element()? {
+                    bytes.push(byte);
+                }
+                Ok(bytes)
+            }
+        }
+
+        deserializer.deserialize_byte_buf(Visitor).map(T::from)
+    }
+}
+
+/// Encode a sequence of exactly `N` elements without a length prefix.
+///
+/// Use it as `#[serde(with = "shaft::with::fixed_len::<N>")]` on a `Vec<T>`.
+/// Serialization fails if the length of the sequence is not `N`.
+#[allow(non_camel_case_types)]
+pub struct fixed_len<const N: usize>;
+
+impl<const N: usize> fixed_len<N> {
+    pub fn serialize<T, E, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: ?Sized + AsRef<[E]>,
+        E: Serialize,
+        S: Serializer,
+    {
+        use serde::ser::{Error, SerializeTuple};
+
+        let elements = value.as_ref();
+        if elements.len() != N {
+            return Err(S::Error::custom(format_args!(
+                "serialize: expected {N} elements, found {}",
+                elements.len()
+            )));
+        }
+        let mut tuple = serializer.serialize_tuple(N)?;
+        for element in elements {
+            tuple.serialize_element(element)?;
+        }
+        tuple.end()
+    }
+
+    pub fn deserialize<'de, E, D>(deserializer: D) -> Result<Vec<E>, D::Error>
+    where
+        E: Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        struct Visitor<E, const N: usize>(PhantomData<E>);
+
+        impl<'de, E: Deserialize<'de>, const N: usize> de::Visitor<'de> for Visitor<E, N> {
+            type Value = Vec<E>;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                write!(formatter, "a sequence of {N} elements")
+            }
+
+            fn visit_seq<A>(self, mut seq: A) -> Result<Vec<E>, A::Error>
+            where
+                A: de::SeqAccess<'de>,
+            {
+                let mut elements = Vec::with_capacity(N.min(4096));
+                for index in 0..N {
+                    match seq.next_element()? {
+                        Some(element) => elements.push(element),
+                        None => return Err(de::Error::invalid_length(index, &self)),
+                    }
+                }
+                Ok(elements)
+            }
+        }
+
+        deserializer.deserialize_tuple(N, Visitor::<E, N>(PhantomData))
+    }
+}
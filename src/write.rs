@@ -1,5 +1,12 @@
-use crate::error::Result;
+use std::io;
 
+use crate::error::{Error, Result};
+
+/// Sink of serialized bytes.
+///
+/// Values are given in their native representation and must be written as
+/// little-endian bytes, booleans as a single `0` or `1` byte. The serializer
+/// takes care of other endianness and of variable-length integers.
 pub trait Writer {
     fn write_bool(&mut self, value: bool) -> Result<()>;
     fn write_i8(&mut self, value: i8) -> Result<()>;
@@ -144,3 +151,76 @@ impl Writer for VecWriter {
         Ok(())
     }
 }
+
+/// Writer streaming serialized bytes into an [`io::Write`].
+pub struct IoWriter<W: io::Write> {
+    inner: W,
+}
+
+impl<W: io::Write> IoWriter<W> {
+    pub fn new(inner: W) -> Self {
+        Self { inner }
+    }
+
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> Result<()> {
+        self.inner.write_all(bytes).map_err(Error::from)
+    }
+}
+
+impl<W: io::Write> Writer for IoWriter<W> {
+    fn write_bool(&mut self, value: bool) -> Result<()> {
+        self.write(&[value as u8])
+    }
+
+    fn write_i8(&mut self, value: i8) -> Result<()> {
+        self.write(&value.to_le_bytes())
+    }
+
+    fn write_i16(&mut self, value: i16) -> Result<()> {
+        self.write(&value.to_le_bytes())
+    }
+
+    fn write_i32(&mut self, value: i32) -> Result<()> {
+        self.write(&value.to_le_bytes())
+    }
+
+    fn write_i64(&mut self, value: i64) -> Result<()> {
+        self.write(&value.to_le_bytes())
+    }
+
+    fn write_u8(&mut self, value: u8) -> Result<()> {
+        self.write(&value.to_le_bytes())
+    }
+
+    fn write_u16(&mut self, value: u16) -> Result<()> {
+        self.write(&value.to_le_bytes())
+    }
+
+    fn write_u32(&mut self, value: u32) -> Result<()> {
+        self.write(&value.to_le_bytes())
+    }
+
+    fn write_u64(&mut self, value: u64) -> Result<()> {
+        self.write(&value.to_le_bytes())
+    }
+
+    fn write_f32(&mut self, value: f32) -> Result<()> {
+        self.write(&value.to_le_bytes())
+    }
+
+    fn write_f64(&mut self, value: f64) -> Result<()> {
+        self.write(&value.to_le_bytes())
+    }
+
+    fn write_bytes(&mut self, value: &[u8]) -> Result<()> {
+        self.write(value)
+    }
+}
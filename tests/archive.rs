@@ -0,0 +1,142 @@
+use std::io::Cursor;
+
+use serde::{Deserialize, Serialize};
+use shaft::archive::{Archive, ArchiveReader, ArchiveWriter};
+use shaft::{Checksum, Endian, Error, ErrorKind, IntEncoding, Options};
+
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+struct Record {
+    id: u32,
+    name: String,
+}
+
+fn record(id: u32) -> Record {
+    Record {
+        id,
+        name: "x".repeat(id as usize % 7),
+    }
+}
+
+fn archive(options: Options, len: u32) -> Result<Vec<u8>, Error> {
+    let mut writer = ArchiveWriter::with_options(Vec::new(), options);
+    for id in 0..len {
+        writer.push(&record(id))?;
+    }
+    assert_eq!(writer.len(), len as usize);
+    writer.finish()
+}
+
+#[test]
+fn serialize_archive() -> Result<(), Error> {
+    let mut writer = ArchiveWriter::new(Vec::new());
+    writer.push(&1u8)?;
+    writer.push(&2u16)?;
+    let bytes = writer.finish()?;
+    assert_eq!(
+        bytes,
+        [
+            1, // record 0
+            2, 0, // record 1
+            0, 0, 0, 0, 0, 0, 0, 0, // offset 0
+            1, 0, 0, 0, 0, 0, 0, 0, // offset 1
+            3, 0, 0, 0, 0, 0, 0, 0, // table offset
+            2, 0, 0, 0, 0, 0, 0, 0, // record count
+            0, 0, 0, // options
+            1, b'S', b'H', b'F', b'A', // version and magic
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn get_archive_records() -> Result<(), Error> {
+    for options in [
+        Options::new(),
+        Options::new().with_endian(Endian::Big),
+        Options::new().with_int_encoding(IntEncoding::Varint),
+        Options::new().with_checksum(Checksum::Crc32c),
+    ] {
+        let bytes = archive(options, 100)?;
+        let archive = Archive::new(&bytes)?;
+        assert_eq!(archive.len(), 100);
+        assert_eq!(archive.options(), options);
+        for id in [0, 42, 99] {
+            assert_eq!(archive.get::<Record>(id as usize)?, record(id));
+        }
+        assert!(archive.get::<Record>(100).is_err());
+    }
+    Ok(())
+}
+
+#[test]
+fn iterate_archive_records() -> Result<(), Error> {
+    let bytes = archive(Options::new(), 10)?;
+    let archive = Archive::new(&bytes)?;
+    let records = archive.iter().collect::<Result<Vec<Record>, _>>()?;
+    assert_eq!(records, (0..10).map(record).collect::<Vec<_>>());
+    Ok(())
+}
+
+#[test]
+fn read_archive_from_reader() -> Result<(), Error> {
+    let options = Options::new().with_checksum(Checksum::XxHash64);
+    let bytes = archive(options, 100)?;
+    let mut reader = ArchiveReader::new(Cursor::new(bytes))?;
+    assert_eq!(reader.len(), 100);
+    assert_eq!(reader.options(), options);
+    assert_eq!(reader.get::<Record>(99)?, record(99));
+    assert_eq!(reader.get::<Record>(7)?, record(7));
+    assert!(reader.get::<Record>(100).is_err());
+    let records = reader.iter().collect::<Result<Vec<Record>, _>>()?;
+    assert_eq!(records, (0..100).map(record).collect::<Vec<_>>());
+    Ok(())
+}
+
+#[test]
+fn empty_archive() -> Result<(), Error> {
+    let bytes = archive(Options::new(), 0)?;
+    assert!(Archive::new(&bytes)?.is_empty());
+    assert!(ArchiveReader::new(Cursor::new(bytes))?.is_empty());
+    Ok(())
+}
+
+#[test]
+fn deserialize_invalid_archive() -> Result<(), Error> {
+    let bytes = archive(Options::new(), 3)?;
+    assert!(Archive::new(&bytes[1..]).is_err());
+    assert!(Archive::new(&bytes[..bytes.len() - 1]).is_err());
+    assert!(ArchiveReader::new(Cursor::new(&bytes[1..])).is_err());
+    assert!(Archive::new(b"SHFA").is_err());
+    Ok(())
+}
+
+#[test]
+fn deserialize_corrupted_record() -> Result<(), Error> {
+    let mut bytes = archive(Options::new().with_checksum(Checksum::Crc32c), 3)?;
+    bytes[0] ^= 1;
+    let archive = Archive::new(&bytes)?;
+    let error = archive.get::<Record>(0).unwrap_err();
+    assert!(matches!(error.kind(), ErrorKind::ChecksumMismatch { .. }));
+    assert_eq!(archive.get::<Record>(1)?, record(1));
+    Ok(())
+}
+
+/// Writer failing every write.
+struct Failing;
+
+impl std::io::Write for Failing {
+    fn write(&mut self, _: &[u8]) -> std::io::Result<usize> {
+        Err(std::io::Error::other("failing"))
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn serialize_io_error() {
+    let mut writer = ArchiveWriter::new(Failing);
+    let error = writer.push(&1u8).unwrap_err();
+    assert!(matches!(error.kind(), ErrorKind::Io(_)));
+}
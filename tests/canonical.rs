@@ -0,0 +1,142 @@
+use std::collections::{BTreeMap, HashMap};
+
+use serde::{Deserialize, Serialize};
+use shaft::{Error, FieldTag, IntEncoding, Options, StructEncoding};
+
+#[test]
+fn serialize_canonical_map() -> Result<(), Error> {
+    let options = Options::new().with_canonical(true);
+    let map: HashMap<u8, u8> = [(3, 30), (1, 10), (2, 20)].into_iter().collect();
+    let bytes = shaft::to_bytes_with(&map, options)?;
+    assert_eq!(bytes, [3, 0, 0, 0, 0, 0, 0, 0, 1, 10, 2, 20, 3, 30]);
+    for _ in 0..10 {
+        let other: HashMap<u8, u8> = map.clone().into_iter().collect();
+        assert_eq!(shaft::to_bytes_with(&other, options)?, bytes);
+    }
+    Ok(())
+}
+
+#[test]
+fn serialize_canonical_float() -> Result<(), Error> {
+    let options = Options::new().with_canonical(true);
+    let bytes = shaft::to_bytes_with(&(-0.0f32, f64::from_bits(0x7ff0_0000_0000_0001)), options)?;
+    assert_eq!(bytes[..4], 0u32.to_le_bytes());
+    assert_eq!(bytes[4..], 0x7ff8_0000_0000_0000u64.to_le_bytes());
+    Ok(())
+}
+
+#[test]
+fn serialize_duplicate_key() {
+    let options = Options::new().with_canonical(true);
+    let entries = [(1u8, 10u8), (1, 20)];
+    let map = serde_map(&entries);
+    assert!(shaft::to_bytes_with(&map, options).is_err());
+}
+
+#[test]
+fn roundtrip_canonical() -> Result<(), Error> {
+    let map: BTreeMap<String, Vec<f64>> = [
+        ("b".to_owned(), vec![1.5, -2.0]),
+        ("a".to_owned(), vec![0.0]),
+    ]
+    .into_iter()
+    .collect();
+    for int_encoding in [IntEncoding::Fixed, IntEncoding::Varint] {
+        let options = Options::new()
+            .with_canonical(true)
+            .with_int_encoding(int_encoding);
+        let bytes = shaft::to_bytes_with(&map, options)?;
+        let result: BTreeMap<String, Vec<f64>> = shaft::from_bytes_with(&bytes, options)?;
+        assert_eq!(result, map);
+    }
+    Ok(())
+}
+
+#[test]
+fn deserialize_non_canonical() -> Result<(), Error> {
+    let options = Options::new().with_canonical(true);
+
+    // Unsorted and duplicate keys.
+    let bytes = [2, 0, 0, 0, 0, 0, 0, 0, 2, 20, 1, 10];
+    assert!(shaft::from_bytes::<HashMap<u8, u8>>(&bytes).is_ok());
+    assert!(shaft::from_bytes_with::<HashMap<u8, u8>>(&bytes, options).is_err());
+    let bytes = [2, 0, 0, 0, 0, 0, 0, 0, 1, 10, 1, 20];
+    assert!(shaft::from_bytes_with::<HashMap<u8, u8>>(&bytes, options).is_err());
+
+    // Negative zero and non-canonical NaN.
+    let bytes = shaft::to_bytes(&-0.0f64)?;
+    assert!(shaft::from_bytes_with::<f64>(&bytes, options).is_err());
+    let bytes = shaft::to_bytes(&f32::from_bits(0x7fc0_0001))?;
+    assert!(shaft::from_bytes_with::<f32>(&bytes, options).is_err());
+
+    // Varint with a redundant trailing byte.
+    let options = options.with_int_encoding(IntEncoding::Varint);
+    assert_eq!(
+        shaft::from_bytes_with::<u32>(
+            &[0x81, 0x00],
+            Options::new().with_int_encoding(IntEncoding::Varint)
+        )?,
+        1
+    );
+    assert!(shaft::from_bytes_with::<u32>(&[0x81, 0x00], options).is_err());
+    Ok(())
+}
+
+#[test]
+fn deserialize_non_canonical_trailing_bytes() {
+    let options = Options::new().with_canonical(true);
+    assert_eq!(shaft::from_bytes::<u8>(&[7, 9]).unwrap(), 7);
+    assert!(shaft::from_bytes_with::<u8>(&[7, 9], options).is_err());
+    assert_eq!(shaft::from_bytes_with::<u8>(&[7], options).unwrap(), 7);
+}
+
+#[test]
+fn deserialize_non_canonical_fields() -> Result<(), Error> {
+    #[derive(Debug, PartialEq, Deserialize, Serialize)]
+    struct Struct {
+        a: u8,
+        b: u8,
+    }
+
+    for tag in [FieldTag::Index, FieldTag::Hash] {
+        let options = Options::new()
+            .with_canonical(true)
+            .with_struct_encoding(StructEncoding::Tagged(tag));
+        let bytes = shaft::to_bytes_with(&Struct { a: 1, b: 2 }, options)?;
+        assert_eq!(
+            shaft::from_bytes_with::<Struct>(&bytes, options)?,
+            Struct { a: 1, b: 2 }
+        );
+
+        // Each field is its tag, its length and its value.
+        let (a, b) = bytes[8..].split_at(13);
+
+        // Swapped fields.
+        let swapped = [&bytes[..8], b, a].concat();
+        let lenient = options.with_canonical(false);
+        assert_eq!(
+            shaft::from_bytes_with::<Struct>(&swapped, lenient)?,
+            Struct { a: 1, b: 2 }
+        );
+        assert!(shaft::from_bytes_with::<Struct>(&swapped, options).is_err());
+
+        // Duplicate field.
+        let mut duplicate = 3u64.to_le_bytes().to_vec();
+        duplicate.extend([a, a, b].concat());
+        assert!(shaft::from_bytes_with::<Struct>(&duplicate, options).is_err());
+    }
+    Ok(())
+}
+
+/// Map serialized from entries as given, including duplicate keys.
+fn serde_map(entries: &[(u8, u8)]) -> impl serde::Serialize + '_ {
+    struct Entries<'a>(&'a [(u8, u8)]);
+
+    impl serde::Serialize for Entries<'_> {
+        fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.collect_map(self.0.iter().map(|(key, value)| (key, value)))
+        }
+    }
+
+    Entries(entries)
+}
@@ -0,0 +1,57 @@
+use serde::{Deserialize, Serialize};
+use shaft::{Checksum, Error, ErrorKind, Options};
+
+#[test]
+fn serialize_crc32c() -> Result<(), Error> {
+    let options = Options::new().with_checksum(Checksum::Crc32c);
+    let bytes = shaft::to_bytes_with(
+        &(b'1', b'2', b'3', b'4', b'5', b'6', b'7', b'8', b'9'),
+        options,
+    )?;
+    assert_eq!(bytes[9..], 0xe306_9283u32.to_le_bytes());
+    Ok(())
+}
+
+#[test]
+fn serialize_xxhash64() -> Result<(), Error> {
+    let options = Options::new().with_checksum(Checksum::XxHash64);
+    let bytes = shaft::to_bytes_with(&(b'a', b'b', b'c'), options)?;
+    assert_eq!(bytes[3..], 0x44bc_2cf5_ad77_0999u64.to_le_bytes());
+    Ok(())
+}
+
+#[test]
+fn roundtrip_checksum() -> Result<(), Error> {
+    #[derive(Debug, PartialEq, Deserialize, Serialize)]
+    struct Struct {
+        name: String,
+        values: Vec<u64>,
+    }
+
+    let value = Struct {
+        name: "Ferris".to_owned(),
+        values: (0..100).collect(),
+    };
+    for checksum in [Checksum::None, Checksum::Crc32c, Checksum::XxHash64] {
+        let options = Options::new().with_checksum(checksum);
+        let bytes = shaft::to_bytes_with(&value, options)?;
+        let result: Struct = shaft::from_bytes_with(&bytes, options)?;
+        assert_eq!(result, value);
+    }
+    Ok(())
+}
+
+#[test]
+fn deserialize_checksum_mismatch() -> Result<(), Error> {
+    for checksum in [Checksum::Crc32c, Checksum::XxHash64] {
+        let options = Options::new().with_checksum(checksum);
+        let mut bytes = shaft::to_bytes_with(&(true, 'a'), options)?;
+        bytes[0] = 2;
+        let error = shaft::from_bytes_with::<(bool, char)>(&bytes, options).unwrap_err();
+        assert!(matches!(error.kind(), ErrorKind::ChecksumMismatch { .. }));
+
+        let result: Result<(bool, char), Error> = shaft::from_bytes_with(&bytes[..2], options);
+        assert!(result.is_err());
+    }
+    Ok(())
+}
@@ -0,0 +1,144 @@
+#![cfg(any(feature = "compression-lz4", feature = "compression-deflate"))]
+
+use std::io::{Read, Write};
+
+use serde::{Deserialize, Serialize};
+use shaft::compression::{self, Compression, Decoder, Encoder};
+use shaft::{Checksum, Error, ErrorKind, Options};
+
+fn algorithms() -> Vec<Compression> {
+    vec![
+        Compression::None,
+        #[cfg(feature = "compression-lz4")]
+        Compression::Lz4,
+        #[cfg(feature = "compression-deflate")]
+        Compression::Deflate,
+    ]
+}
+
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+struct Struct {
+    names: Vec<String>,
+    values: Vec<u64>,
+}
+
+#[test]
+fn roundtrip_compressed() -> Result<(), Error> {
+    let value = Struct {
+        names: vec!["Ferris".to_owned(); 1000],
+        values: (0..1000).collect(),
+    };
+    let plain = shaft::to_bytes(&value)?;
+    for compression in algorithms() {
+        let options = Options::new().with_checksum(Checksum::Crc32c);
+        let bytes = compression::to_bytes_compressed(&value, options, compression)?;
+        if compression != Compression::None {
+            assert!(bytes.len() < plain.len() / 2);
+        }
+        assert_eq!(shaft::envelope::options(&bytes)?, options);
+        let result: Struct = compression::from_bytes_compressed(&bytes)?;
+        assert_eq!(result, value);
+    }
+    Ok(())
+}
+
+#[test]
+fn deserialize_uncompressed_envelope() -> Result<(), Error> {
+    let value = Struct {
+        names: vec!["Ferris".to_owned(); 1000],
+        values: (0..1000).collect(),
+    };
+    let bytes = shaft::envelope::to_bytes(&value)?;
+    let result: Struct = compression::from_bytes_compressed(&bytes)?;
+    assert_eq!(result, value);
+    Ok(())
+}
+
+#[test]
+fn deserialize_compressed_with_envelope() -> Result<(), Error> {
+    for compression in algorithms() {
+        let bytes = compression::to_bytes_compressed(&1u32, Options::new(), compression)?;
+        let result: u32 = shaft::envelope::from_bytes(&bytes)?;
+        assert_eq!(result, 1);
+        let result: Result<u32, Error> = shaft::envelope::from_bytes_borrowed(&bytes);
+        assert_eq!(result.is_ok(), compression == Compression::None);
+    }
+    Ok(())
+}
+
+#[test]
+fn deserialize_limit() -> Result<(), Error> {
+    let value = vec![0u8; 1_000_000];
+    for compression in algorithms() {
+        let bytes = compression::to_bytes_compressed(&value, Options::new(), compression)?;
+        let result: Vec<u8> = compression::from_bytes_compressed_with_limit(&bytes, 1_000_008)?;
+        assert_eq!(result, value);
+        let result: Result<Vec<u8>, Error> =
+            compression::from_bytes_compressed_with_limit(&bytes, 1_000_007);
+        assert!(result.is_err());
+    }
+    Ok(())
+}
+
+#[test]
+fn deserialize_truncated() -> Result<(), Error> {
+    let value = Struct {
+        names: vec!["Ferris".to_owned(); 1000],
+        values: (0..1000).collect(),
+    };
+    for compression in algorithms() {
+        let bytes = compression::to_bytes_compressed(&value, Options::new(), compression)?;
+        let result: Result<Struct, Error> =
+            compression::from_bytes_compressed(&bytes[..bytes.len() - 8]);
+        let error = result.unwrap_err();
+        if compression != Compression::None {
+            assert!(matches!(error.kind(), ErrorKind::Io(_)));
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn stream_roundtrip() -> std::io::Result<()> {
+    let data: Vec<u8> = (0..100_000u32).map(|i| (i % 251) as u8).collect();
+    for compression in algorithms() {
+        let mut encoder = Encoder::new(Vec::new(), compression);
+        for chunk in data.chunks(777) {
+            encoder.write_all(chunk)?;
+        }
+        encoder.flush()?;
+        let bytes = encoder.finish()?;
+
+        let mut decoder = Decoder::new(bytes.as_slice(), compression);
+        let mut result = Vec::new();
+        let mut buf = [0; 100];
+        loop {
+            let len = decoder.read(&mut buf)?;
+            if len == 0 {
+                break;
+            }
+            result.extend_from_slice(&buf[..len]);
+        }
+        assert_eq!(result, data);
+    }
+    Ok(())
+}
+
+#[test]
+fn stream_serialize() -> Result<(), Error> {
+    let value = (
+        vec!["Ferris".to_owned(); 1000],
+        (0..1000).collect::<Vec<u64>>(),
+    );
+    let options = Options::new().with_checksum(Checksum::Crc32c);
+    for compression in algorithms() {
+        let encoder =
+            shaft::to_io_writer_with(Encoder::new(Vec::new(), compression), &value, options)?;
+        let bytes = encoder.finish()?;
+
+        let mut body = Vec::new();
+        Decoder::new(bytes.as_slice(), compression).read_to_end(&mut body)?;
+        assert_eq!(body, shaft::to_bytes_with(&value, options)?);
+    }
+    Ok(())
+}
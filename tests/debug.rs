@@ -0,0 +1,94 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use shaft::{debug, Checksum, Error, IntEncoding, Options};
+
+#[derive(Deserialize, Serialize)]
+enum Shape {
+    Point,
+    Circle { radius: f32 },
+}
+
+#[derive(Deserialize, Serialize)]
+struct Scene {
+    shapes: Vec<Shape>,
+    label: Option<String>,
+    tags: BTreeMap<u8, bool>,
+}
+
+#[test]
+fn annotate_nested() -> Result<(), Error> {
+    let scene = Scene {
+        shapes: vec![Shape::Point, Shape::Circle { radius: 1.5 }],
+        label: None,
+        tags: [(7, true)].into_iter().collect(),
+    };
+    let bytes = shaft::to_bytes(&scene)?;
+    let dump = debug::annotate::<Scene>(&bytes);
+    assert_eq!(
+        dump,
+        "0000..0008 .shapes len=2\n\
+         0008..000c .shapes[0] variant=Point\n\
+         000c..0010 .shapes[1] variant=Circle\n\
+         0010..0014 .shapes[1]::Circle.radius f32 1.5\n\
+         0014..0015 .label option=None\n\
+         0015..001d .tags len=1\n\
+         001d..001e .tags[0].key u8 7\n\
+         001e..001f .tags[0].value bool true\n"
+    );
+    Ok(())
+}
+
+#[test]
+fn annotate_error() -> Result<(), Error> {
+    let scene = Scene {
+        shapes: vec![Shape::Point, Shape::Circle { radius: 1.5 }],
+        label: None,
+        tags: [(7, true)].into_iter().collect(),
+    };
+    let mut bytes = shaft::to_bytes(&scene)?;
+    bytes[12] = 9;
+    let dump = debug::annotate::<Scene>(&bytes);
+    assert!(dump.starts_with("0000..0008 .shapes len=2\n"));
+    assert!(dump.ends_with(
+        "0010.. <error> invalid value: integer `9`, expected variant index 0 <= i < 2\n"
+    ));
+    Ok(())
+}
+
+#[test]
+fn annotate_with_options() -> Result<(), Error> {
+    let options = Options::new()
+        .with_int_encoding(IntEncoding::Varint)
+        .with_checksum(Checksum::Crc32c);
+    let bytes = shaft::to_bytes_with(&(300u32, "a"), options)?;
+    let dump = debug::annotate_with::<(u32, String)>(&bytes, options);
+    let lines: Vec<&str> = dump.lines().collect();
+    assert_eq!(lines[0], "0000..0002 [0] u32 300");
+    assert_eq!(lines[1], "0002..0003 [1] len=1");
+    assert_eq!(lines[2], "0003..0004 [1] str \"a\"");
+    assert!(lines[3].starts_with("0004..0008 <checksum> ["));
+    Ok(())
+}
+
+#[test]
+fn annotate_self_describing() -> Result<(), Error> {
+    #[derive(Deserialize, Serialize)]
+    #[serde(untagged)]
+    enum Untagged {
+        Integer(u32),
+    }
+
+    let bytes = shaft::to_bytes(&Untagged::Integer(1))?;
+    let dump = debug::annotate::<Untagged>(&bytes);
+    assert_eq!(
+        dump,
+        "0000.. <error> debug: deserialize_any not supported\n"
+    );
+    let dump = debug::annotate::<serde::de::IgnoredAny>(&bytes);
+    assert_eq!(
+        dump,
+        "0000.. <error> debug: deserialize_ignored_any not supported\n"
+    );
+    Ok(())
+}
@@ -0,0 +1,80 @@
+use serde::{Deserialize, Serialize};
+use shaft::{Endian, Error, IntEncoding, LenWidth, Options};
+
+#[test]
+fn serialize_big_endian() -> Result<(), Error> {
+    let options = Options::new().with_endian(Endian::Big);
+    let bytes = shaft::to_bytes_with(&(1u16, 2i32, 1.0f32), options)?;
+    assert_eq!(bytes, [0, 1, 0, 0, 0, 2, 0x3f, 0x80, 0, 0]);
+    Ok(())
+}
+
+#[test]
+fn serialize_varint() -> Result<(), Error> {
+    let options = Options::new().with_int_encoding(IntEncoding::Varint);
+    let bytes = shaft::to_bytes_with(&(1u8, 300u32, -1i64, 1i16, u64::MAX), options)?;
+    assert_eq!(
+        bytes,
+        [
+            1, // u8
+            0xac, 0x02, // u32
+            0x01, // i64
+            0x02, // i16
+            0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01, // u64
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn serialize_len_width() -> Result<(), Error> {
+    let options = Options::new().with_len_width(LenWidth::U16);
+    let bytes = shaft::to_bytes_with(&vec![1u8, 2], options)?;
+    assert_eq!(bytes, [2, 0, 1, 2]);
+
+    let options = Options::new().with_len_width(LenWidth::U8);
+    let result = shaft::to_bytes_with(&vec![0u8; 256], options);
+    assert!(result.is_err());
+    Ok(())
+}
+
+#[test]
+fn deserialize_varint_out_of_range() {
+    let options = Options::new().with_int_encoding(IntEncoding::Varint);
+    let result: Result<u16, Error> = shaft::from_bytes_with(&[0x80, 0x80, 0x04], options);
+    assert!(result.is_err());
+    let result: Result<u64, Error> = shaft::from_bytes_with(&[0xff; 10], options);
+    assert!(result.is_err());
+}
+
+#[test]
+fn roundtrip_options() -> Result<(), Error> {
+    #[derive(Debug, PartialEq, Deserialize, Serialize)]
+    struct Struct {
+        name: String,
+        values: Vec<i64>,
+        pairs: Vec<(char, f64)>,
+        level: Option<u32>,
+    }
+
+    let value = Struct {
+        name: "Ferris".to_owned(),
+        values: vec![i64::MIN, -1, 0, 1, i64::MAX],
+        pairs: vec![('a', 1.5), ('é', -2.0)],
+        level: Some(7),
+    };
+    for endian in [Endian::Little, Endian::Big] {
+        for encoding in [IntEncoding::Fixed, IntEncoding::Varint] {
+            for width in [LenWidth::U8, LenWidth::U16, LenWidth::U32, LenWidth::U64] {
+                let options = Options::new()
+                    .with_endian(endian)
+                    .with_int_encoding(encoding)
+                    .with_len_width(width);
+                let bytes = shaft::to_bytes_with(&value, options)?;
+                let result: Struct = shaft::from_bytes_with(&bytes, options)?;
+                assert_eq!(result, value);
+            }
+        }
+    }
+    Ok(())
+}
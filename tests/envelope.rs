@@ -0,0 +1,108 @@
+use std::net::{IpAddr, Ipv4Addr};
+
+use serde::{Deserialize, Serialize};
+use shaft::{
+    Checksum, Endian, Error, FieldTag, IntEncoding, LenWidth, Options, StructEncoding,
+    VariantEncoding,
+};
+
+#[test]
+fn serialize_header() -> Result<(), Error> {
+    let bytes = shaft::envelope::to_bytes(&1u8)?;
+    assert_eq!(bytes, [b'S', b'H', b'F', b'T', 1, 0, 0, 0, 0, 1]);
+
+    let options = Options::new()
+        .with_endian(Endian::Big)
+        .with_int_encoding(IntEncoding::Varint)
+        .with_len_width(LenWidth::U8)
+        .with_struct_encoding(StructEncoding::Tagged(FieldTag::Index))
+        .with_variant_encoding(VariantEncoding::Hash)
+        .with_checksum(Checksum::XxHash64);
+    let bytes = shaft::envelope::to_bytes_with(&1u8, options)?;
+    assert_eq!(bytes[5], 0b1010_1111);
+    assert_eq!(bytes[6], 2);
+    assert_eq!(shaft::envelope::options(&bytes)?, options);
+    Ok(())
+}
+
+#[test]
+fn roundtrip_envelope() -> Result<(), Error> {
+    #[derive(Debug, PartialEq, Deserialize, Serialize)]
+    enum Enum {
+        A { name: String, values: Vec<u32> },
+    }
+
+    let value = Enum::A {
+        name: "Ferris".to_owned(),
+        values: vec![1, 2, 3],
+    };
+    let options = Options::new()
+        .with_int_encoding(IntEncoding::Varint)
+        .with_struct_encoding(StructEncoding::Tagged(FieldTag::Hash))
+        .with_variant_encoding(VariantEncoding::Name);
+    let bytes = shaft::envelope::to_bytes_with(&value, options)?;
+    let result: Enum = shaft::envelope::from_bytes(&bytes)?;
+    assert_eq!(result, value);
+    Ok(())
+}
+
+#[test]
+fn deserialize_invalid_header() -> Result<(), Error> {
+    let bytes = shaft::to_bytes(&1u32)?;
+    let result: Result<u32, Error> = shaft::envelope::from_bytes(&bytes);
+    assert!(result.is_err());
+
+    let mut bytes = shaft::envelope::to_bytes(&1u32)?;
+    bytes[4] = 0xff;
+    let result: Result<u32, Error> = shaft::envelope::from_bytes(&bytes);
+    assert!(result.is_err());
+
+    let mut bytes = shaft::envelope::to_bytes(&1u32)?;
+    bytes[5] = 0b0011_0000;
+    let result: Result<u32, Error> = shaft::envelope::from_bytes(&bytes);
+    assert!(result.is_err());
+    Ok(())
+}
+
+#[test]
+fn roundtrip_human_readable() -> Result<(), Error> {
+    let address = IpAddr::V4(Ipv4Addr::LOCALHOST);
+    let options = Options::new().with_human_readable(true);
+    let bytes = shaft::envelope::to_bytes_with(&address, options)?;
+    assert_eq!(bytes[8], 1);
+    assert!(shaft::envelope::options(&bytes)?.human_readable());
+    let value: IpAddr = shaft::envelope::from_bytes(&bytes)?;
+    assert_eq!(value, address);
+    Ok(())
+}
+
+#[test]
+fn deserialize_corrupted_envelope() -> Result<(), Error> {
+    let options = Options::new().with_checksum(Checksum::Crc32c);
+    let mut bytes = shaft::envelope::to_bytes_with(&"Ferris", options)?;
+    let value: String = shaft::envelope::from_bytes(&bytes)?;
+    assert_eq!(value, "Ferris");
+
+    bytes[11] ^= 0x10;
+    let result: Result<String, Error> = shaft::envelope::from_bytes(&bytes);
+    assert!(result.is_err());
+    Ok(())
+}
+
+#[test]
+fn deserialize_borrowed() -> Result<(), Error> {
+    let bytes = shaft::envelope::to_bytes(&"Ferris")?;
+    let value: &str = shaft::envelope::from_bytes_borrowed(&bytes)?;
+    assert_eq!(value, "Ferris");
+    Ok(())
+}
+
+#[test]
+#[cfg(not(feature = "compression-lz4"))]
+fn deserialize_compression_not_enabled() -> Result<(), Error> {
+    let mut bytes = shaft::envelope::to_bytes(&1u8)?;
+    bytes[7] = 1;
+    let error = shaft::envelope::from_bytes::<u8>(&bytes).unwrap_err();
+    assert!(error.to_string().contains("not enabled"));
+    Ok(())
+}
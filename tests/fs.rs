@@ -0,0 +1,147 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use shaft::{Checksum, Endian, Error, ErrorKind, IntEncoding, Options};
+
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+struct Settings {
+    name: String,
+    values: Vec<u32>,
+}
+
+/// Temporary directory removed when dropped.
+struct TempDir(PathBuf);
+
+impl TempDir {
+    fn new(name: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!("shaft-fs-{}-{name}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        Self(dir)
+    }
+
+    fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+#[test]
+fn save_envelope() -> Result<(), Error> {
+    let dir = TempDir::new("envelope");
+    let path = dir.path().join("value.bin");
+    shaft::fs::save(&path, &7u16)?;
+    assert_eq!(fs::read(&path).unwrap(), shaft::envelope::to_bytes(&7u16)?);
+    Ok(())
+}
+
+#[test]
+fn roundtrip_save_load() -> Result<(), Error> {
+    let settings = Settings {
+        name: "Ferris".to_owned(),
+        values: (0..100).collect(),
+    };
+    let dir = TempDir::new("roundtrip");
+    let path = dir.path().join("settings.bin");
+    for options in [
+        Options::new(),
+        Options::new().with_endian(Endian::Big),
+        Options::new().with_int_encoding(IntEncoding::Varint),
+        Options::new().with_checksum(Checksum::Crc32c),
+    ] {
+        shaft::fs::save_with(&path, &settings, options)?;
+        assert_eq!(
+            shaft::envelope::options(&fs::read(&path).unwrap())?,
+            options
+        );
+        let result: Settings = shaft::fs::load(&path)?;
+        assert_eq!(result, settings);
+    }
+    Ok(())
+}
+
+#[test]
+fn save_replaces_file() -> Result<(), Error> {
+    let settings = Settings {
+        name: "Ferris".to_owned(),
+        values: (0..100).collect(),
+    };
+    let dir = TempDir::new("replace");
+    let path = dir.path().join("settings.bin");
+    fs::write(&path, b"previous").unwrap();
+    shaft::fs::save(&path, &settings)?;
+    assert_eq!(shaft::fs::load::<Settings>(&path)?, settings);
+    assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    Ok(())
+}
+
+#[test]
+fn save_io_error() {
+    let settings = Settings {
+        name: "Ferris".to_owned(),
+        values: (0..100).collect(),
+    };
+    let dir = TempDir::new("missing");
+    let path = dir.path().join("missing").join("settings.bin");
+    let error = shaft::fs::save(&path, &settings).unwrap_err();
+    assert!(matches!(error.kind(), ErrorKind::Io(_)));
+    assert!(std::error::Error::source(&error).is_some());
+}
+
+#[test]
+fn save_serialize_error_keeps_file() -> Result<(), Error> {
+    let settings = Settings {
+        name: "Ferris".to_owned(),
+        values: (0..100).collect(),
+    };
+    let dir = TempDir::new("serialize");
+    let path = dir.path().join("settings.bin");
+    shaft::fs::save(&path, &settings)?;
+    let options = Options::new().with_len_width(shaft::LenWidth::U8);
+    let error = shaft::fs::save_with(&path, &vec![0u8; 256], options).unwrap_err();
+    assert!(matches!(error.kind(), ErrorKind::Message(_)));
+    assert_eq!(shaft::fs::load::<Settings>(&path)?, settings);
+    assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    Ok(())
+}
+
+#[test]
+fn load_errors() {
+    let dir = TempDir::new("load");
+    let error = shaft::fs::load::<Settings>(dir.path().join("missing.bin")).unwrap_err();
+    assert!(matches!(error.kind(), ErrorKind::Io(_)));
+    let path = dir.path().join("invalid.bin");
+    fs::write(&path, b"invalid").unwrap();
+    let error = shaft::fs::load::<Settings>(&path).unwrap_err();
+    assert!(matches!(error.kind(), ErrorKind::Message(_)));
+}
+
+#[test]
+fn save_concurrent() -> Result<(), Error> {
+    let settings = Settings {
+        name: "Ferris".to_owned(),
+        values: (0..100).collect(),
+    };
+    let dir = TempDir::new("concurrent");
+    let path = dir.path().join("settings.bin");
+    std::thread::scope(|scope| {
+        for _ in 0..8 {
+            scope.spawn(|| {
+                for _ in 0..20 {
+                    shaft::fs::save(&path, &settings).unwrap();
+                }
+            });
+        }
+    });
+    assert_eq!(shaft::fs::load::<Settings>(&path)?, settings);
+    assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    Ok(())
+}
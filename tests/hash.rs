@@ -0,0 +1,58 @@
+use std::collections::{hash_map::DefaultHasher, HashMap};
+
+use serde::Serialize;
+use shaft::{Checksum, Error, HashSink, Options, StdHasher};
+
+#[derive(Serialize)]
+struct Struct {
+    name: String,
+    values: Vec<u64>,
+}
+
+#[test]
+fn hash_std_hasher() -> Result<(), Error> {
+    let value = Struct {
+        name: "Ferris".to_owned(),
+        values: (0..100).collect(),
+    };
+    let hasher: StdHasher<DefaultHasher> = shaft::hash(&value)?;
+    let other: StdHasher<DefaultHasher> = shaft::hash(&Struct {
+        name: "Ferris".to_owned(),
+        values: (0..100).collect(),
+    })?;
+    assert_eq!(hasher.finish(), other.finish());
+    let mut different = StdHasher(DefaultHasher::new());
+    different.update(b"Ferris");
+    assert_ne!(hasher.finish(), different.finish());
+    Ok(())
+}
+
+#[test]
+fn hash_with_options() -> Result<(), Error> {
+    let options = Options::new()
+        .with_canonical(true)
+        .with_checksum(Checksum::Crc32c);
+    let map: HashMap<String, u32> = (0..100).map(|index| (index.to_string(), index)).collect();
+    let reversed: HashMap<String, u32> = (0..100)
+        .rev()
+        .map(|index| (index.to_string(), index))
+        .collect();
+    let hasher = shaft::hash_with(&map, StdHasher(DefaultHasher::new()), options)?;
+    let other = shaft::hash_with(&reversed, StdHasher(DefaultHasher::new()), options)?;
+    assert_eq!(hasher.finish(), other.finish());
+    Ok(())
+}
+
+#[cfg(feature = "digest")]
+#[test]
+fn hash_sha256() -> Result<(), Error> {
+    use sha2::{Digest as _, Sha256};
+
+    let value = Struct {
+        name: "Ferris".to_owned(),
+        values: vec![1, 2, 3],
+    };
+    let hasher: Sha256 = shaft::hash(&value)?;
+    assert_eq!(hasher.finalize(), Sha256::digest(shaft::to_bytes(&value)?));
+    Ok(())
+}
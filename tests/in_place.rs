@@ -0,0 +1,76 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use shaft::{Checksum, Error, IntEncoding, Options};
+
+#[derive(Debug, Default, PartialEq, Deserialize, Serialize)]
+struct Message {
+    id: u32,
+    name: String,
+    tags: Vec<String>,
+    values: Vec<u64>,
+    extra: Option<String>,
+}
+
+fn message(id: u32) -> Message {
+    Message {
+        id,
+        name: format!("message {id}"),
+        tags: (0..id).map(|tag| format!("tag {tag}")).collect(),
+        values: (0..u64::from(id) * 10).collect(),
+        extra: Some("extra".to_owned()),
+    }
+}
+
+#[test]
+fn deserialize_in_place() -> Result<(), Error> {
+    let mut value = Message::default();
+    for id in [5, 3, 8, 0] {
+        let bytes = shaft::to_bytes(&message(id))?;
+        shaft::from_bytes_in_place(&mut value, &bytes)?;
+        assert_eq!(value, message(id));
+    }
+    Ok(())
+}
+
+#[test]
+fn deserialize_in_place_reuses_allocations() -> Result<(), Error> {
+    let mut value = message(8);
+    let name = value.name.as_ptr();
+    let tags = value.tags.as_ptr();
+    let tag = value.tags[0].as_ptr();
+    let values = value.values.as_ptr();
+    let bytes = shaft::to_bytes(&message(4))?;
+    shaft::from_bytes_in_place(&mut value, &bytes)?;
+    assert_eq!(value, message(4));
+    assert_eq!(value.name.as_ptr(), name);
+    assert_eq!(value.tags.as_ptr(), tags);
+    assert_eq!(value.tags[0].as_ptr(), tag);
+    assert_eq!(value.values.as_ptr(), values);
+    Ok(())
+}
+
+#[test]
+fn deserialize_in_place_with_options() -> Result<(), Error> {
+    let options = Options::new()
+        .with_int_encoding(IntEncoding::Varint)
+        .with_checksum(Checksum::Crc32c);
+    let mut value = message(2);
+    let bytes = shaft::to_bytes_with(&message(6), options)?;
+    shaft::from_bytes_in_place_with(&mut value, &bytes, options)?;
+    assert_eq!(value, message(6));
+    let mut map = BTreeMap::from([(1u8, "one".to_owned())]);
+    let bytes = shaft::to_bytes(&BTreeMap::from([(2u8, "two".to_owned())]))?;
+    shaft::from_bytes_in_place(&mut map, &bytes)?;
+    assert_eq!(map, BTreeMap::from([(2, "two".to_owned())]));
+    Ok(())
+}
+
+#[test]
+fn deserialize_in_place_error() -> Result<(), Error> {
+    let mut value = message(2);
+    let bytes = shaft::to_bytes(&message(3))?;
+    let error = shaft::from_bytes_in_place(&mut value, &bytes[..bytes.len() - 1]).unwrap_err();
+    assert_eq!(error.path(), Some(".extra"));
+    Ok(())
+}
@@ -0,0 +1,175 @@
+use std::cmp::Ordering;
+use std::fmt::Debug;
+
+use proptest::prelude::*;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use shaft::{
+    key::{from_key, to_key},
+    Error,
+};
+
+#[derive(Debug, Clone, PartialEq, PartialOrd, Deserialize, Serialize)]
+enum Enum {
+    Unit,
+    Newtype(i32),
+    Tuple(String, u8),
+    Struct { name: String, value: Option<i64> },
+}
+
+fn enum_strategy() -> impl Strategy<Value = Enum> {
+    prop_oneof![
+        Just(Enum::Unit),
+        any::<i32>().prop_map(Enum::Newtype),
+        (any::<String>(), any::<u8>()).prop_map(|(name, value)| Enum::Tuple(name, value)),
+        (any::<String>(), any::<Option<i64>>())
+            .prop_map(|(name, value)| Enum::Struct { name, value }),
+    ]
+}
+
+/// Check that keys are ordered as values and decode back to values.
+fn check<T>(a: &T, b: &T, ordering: Ordering) -> Result<(), TestCaseError>
+where
+    T: Debug + PartialEq + Serialize + DeserializeOwned,
+{
+    let key_a = to_key(a).unwrap();
+    let key_b = to_key(b).unwrap();
+    prop_assert_eq!(key_a.cmp(&key_b), ordering);
+    prop_assert_eq!(&from_key::<T>(&key_a).unwrap(), a);
+    prop_assert_eq!(&from_key::<T>(&key_b).unwrap(), b);
+    Ok(())
+}
+
+proptest! {
+    #[test]
+    fn order_integers(a: (u8, i8, u16, i16), b: (u8, i8, u16, i16)) {
+        check(&a, &b, a.cmp(&b))?;
+    }
+
+    #[test]
+    fn order_wide_integers(a: (i32, u32, i64, u64), b: (i32, u32, i64, u64)) {
+        check(&a, &b, a.cmp(&b))?;
+    }
+
+    #[test]
+    fn order_f32(a: f32, b: f32) {
+        let key_a = to_key(&a).unwrap();
+        let key_b = to_key(&b).unwrap();
+        prop_assert_eq!(key_a.cmp(&key_b), a.total_cmp(&b));
+        prop_assert_eq!(from_key::<f32>(&key_a).unwrap().to_bits(), a.to_bits());
+    }
+
+    #[test]
+    fn order_f64(a: f64, b: f64) {
+        let key_a = to_key(&a).unwrap();
+        let key_b = to_key(&b).unwrap();
+        prop_assert_eq!(key_a.cmp(&key_b), a.total_cmp(&b));
+        prop_assert_eq!(from_key::<f64>(&key_a).unwrap().to_bits(), a.to_bits());
+    }
+
+    #[test]
+    fn order_strings(a: (String, String), b: (String, String)) {
+        check(&a, &b, a.cmp(&b))?;
+    }
+
+    #[test]
+    fn order_bytes(a in prop::collection::vec(0..4u8, 0..8), b in prop::collection::vec(0..4u8, 0..8)) {
+        let key_a = to_key(&serde_bytes(&a)).unwrap();
+        let key_b = to_key(&serde_bytes(&b)).unwrap();
+        prop_assert_eq!(key_a.cmp(&key_b), a.cmp(&b));
+    }
+
+    #[test]
+    fn order_sequences(a: Vec<Option<String>>, b: Vec<Option<String>>) {
+        check(&a, &b, a.cmp(&b))?;
+    }
+
+    #[test]
+    fn order_enums(a in enum_strategy(), b in enum_strategy()) {
+        check(&a, &b, a.partial_cmp(&b).unwrap())?;
+    }
+}
+
+/// Byte array serialized with `serialize_bytes`.
+fn serde_bytes(bytes: &[u8]) -> impl Serialize + '_ {
+    struct Bytes<'a>(&'a [u8]);
+
+    impl Serialize for Bytes<'_> {
+        fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.serialize_bytes(self.0)
+        }
+    }
+
+    Bytes(bytes)
+}
+
+#[test]
+fn key_layout() -> Result<(), Error> {
+    assert_eq!(to_key(&-1i16)?, [0x7f, 0xff]);
+    assert_eq!(to_key(&"a\0")?, [b'a', 0x00, 0xff, 0x00, 0x01]);
+    assert_eq!(to_key(&vec![1u8, 2])?, [1, 1, 1, 2, 0]);
+    assert_eq!(to_key(&Some(1u8))?, [1, 1]);
+    Ok(())
+}
+
+#[test]
+fn from_key_borrowed() -> Result<(), Error> {
+    let bytes = to_key(&("Ferris", "a\0b".to_owned()))?;
+    let (name, escaped): (&str, String) = from_key(&bytes)?;
+    assert_eq!(name, "Ferris");
+    assert_eq!(escaped, "a\0b");
+    Ok(())
+}
+
+#[test]
+fn from_key_invalid() {
+    assert!(from_key::<String>(b"abc").is_err());
+    assert!(from_key::<String>(&[b'a', 0x00, 0x02]).is_err());
+    assert!(from_key::<u8>(&[1, 2]).is_err());
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(untagged)]
+enum Untagged {
+    Integer(u32),
+    String(String),
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(tag = "type")]
+enum Internal {
+    Unit,
+}
+
+/// Identifier deserialized with `deserialize_identifier`.
+#[derive(Debug)]
+struct Identifier;
+
+impl<'de> Deserialize<'de> for Identifier {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct Visitor;
+
+        impl serde::de::Visitor<'_> for Visitor {
+            type Value = Identifier;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                formatter.write_str("identifier")
+            }
+
+            fn visit_str<E>(self, _: &str) -> Result<Identifier, E> {
+                Ok(Identifier)
+            }
+        }
+
+        deserializer.deserialize_identifier(Visitor)
+    }
+}
+
+#[test]
+fn from_key_self_describing_error() -> Result<(), Error> {
+    assert!(from_key::<Untagged>(&to_key(&Untagged::Integer(1))?).is_err());
+    assert!(from_key::<Untagged>(&to_key(&Untagged::String("a".into()))?).is_err());
+    assert!(from_key::<Internal>(&to_key(&"Unit")?).is_err());
+    assert!(from_key::<Identifier>(&to_key(&"a")?).is_err());
+    assert!(from_key::<serde::de::IgnoredAny>(&to_key(&1u8)?).is_err());
+    Ok(())
+}
@@ -0,0 +1,90 @@
+use serde::{Deserialize, Serialize};
+use shaft::{debug, Checksum, Endian, Error, IntEncoding, Lazy, LenWidth, Options};
+
+#[derive(Deserialize, Serialize)]
+struct Message<'a> {
+    route: u8,
+    #[serde(borrow)]
+    body: Lazy<'a, Vec<u16>>,
+}
+
+#[test]
+fn serialize_lazy() -> Result<(), Error> {
+    let options = Options::new().with_len_width(LenWidth::U8);
+    let message = Message {
+        route: 3,
+        body: Lazy::with_options(&vec![1, 2], options)?,
+    };
+    let bytes = shaft::to_bytes_with(&message, options)?;
+    assert_eq!(bytes, [3, 5, 2, 1, 0, 2, 0]);
+    Ok(())
+}
+
+#[test]
+fn deserialize_lazy_borrowed() -> Result<(), Error> {
+    let message = Message {
+        route: 3,
+        body: Lazy::new(&vec![1, 2])?,
+    };
+    let bytes = shaft::to_bytes(&message)?;
+    let result: Message = shaft::from_bytes(&bytes)?;
+    assert_eq!(result.route, 3);
+    assert_eq!(result.body.as_bytes(), message.body.as_bytes());
+    assert!(bytes
+        .as_ptr_range()
+        .contains(&result.body.as_bytes().as_ptr()));
+    assert_eq!(result.body.get()?, [1, 2]);
+    Ok(())
+}
+
+#[test]
+fn forward_lazy_untouched() -> Result<(), Error> {
+    for options in [
+        Options::new(),
+        Options::new().with_endian(Endian::Big),
+        Options::new().with_int_encoding(IntEncoding::Varint),
+        Options::new().with_checksum(Checksum::Crc32c),
+    ] {
+        let message = Message {
+            route: 1,
+            body: Lazy::with_options(&vec![300; 10], options)?,
+        };
+        let bytes = shaft::to_bytes_with(&message, options)?;
+        let result: Message = shaft::from_bytes_with(&bytes, options)?;
+        assert_eq!(result.body.options(), options.with_checksum(Checksum::None));
+        assert_eq!(shaft::to_bytes_with(&result, options)?, bytes);
+        assert_eq!(result.body.get()?, [300; 10]);
+    }
+    Ok(())
+}
+
+#[test]
+fn lazy_borrowed_value() -> Result<(), Error> {
+    let lazy = Lazy::new(&"Ferris")?;
+    let value: &str = lazy.get()?;
+    assert_eq!(value, "Ferris");
+    Ok(())
+}
+
+#[test]
+fn lazy_invalid_body() -> Result<(), Error> {
+    let options = Options::new().with_len_width(LenWidth::U8);
+    let bytes = [3, 1, 2];
+    let result: Message = shaft::from_bytes_with(&bytes, options)?;
+    assert!(result.body.get().is_err());
+    Ok(())
+}
+
+#[test]
+fn annotate_lazy() -> Result<(), Error> {
+    let options = Options::new().with_len_width(LenWidth::U8);
+    let bytes = [3, 5, 2, 1, 0, 2, 0];
+    let dump = debug::annotate_with::<Message>(&bytes, options);
+    assert_eq!(
+        dump,
+        "0000..0001 .route u8 3\n\
+         0001..0002 .body len=5\n\
+         0002..0007 .body lazy 5 bytes\n"
+    );
+    Ok(())
+}
@@ -0,0 +1,75 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use shaft::{Error, FieldTag, Options, StructEncoding};
+
+#[derive(Debug, Deserialize, Serialize)]
+struct Item {
+    sku: char,
+    count: u8,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+enum Order {
+    Empty,
+    Items { items: Vec<Item> },
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct Orders {
+    orders: Vec<Order>,
+}
+
+#[test]
+fn error_path() -> Result<(), Error> {
+    let orders = Orders {
+        orders: vec![
+            Order::Empty,
+            Order::Items {
+                items: vec![Item { sku: 'a', count: 1 }, Item { sku: 'b', count: 2 }],
+            },
+        ],
+    };
+    let mut bytes = shaft::to_bytes(&orders)?;
+    // Replace the second sku with an invalid char.
+    let offset = bytes.len() - 5;
+    bytes[offset..offset + 4].copy_from_slice(&0xd800u32.to_le_bytes());
+    let error = shaft::from_bytes::<Orders>(&bytes).unwrap_err();
+    assert_eq!(error.path(), Some(".orders[1]::Items.items[1].sku"));
+    assert_eq!(error.offset(), Some(offset + 4));
+    let suffix = format!(" at .orders[1]::Items.items[1].sku (offset {})", offset + 4);
+    assert!(error.to_string().ends_with(&suffix));
+    Ok(())
+}
+
+#[test]
+fn error_path_map() -> Result<(), Error> {
+    let map: BTreeMap<u8, Vec<bool>> = [(1, vec![true]), (2, vec![false, true])]
+        .into_iter()
+        .collect();
+    let mut bytes = shaft::to_bytes(&map)?;
+    let last = bytes.len() - 1;
+    bytes[last] = 2;
+    let error = shaft::from_bytes::<BTreeMap<u8, Vec<bool>>>(&bytes).unwrap_err();
+    assert_eq!(error.path(), Some("[1].value[1]"));
+    Ok(())
+}
+
+#[test]
+fn error_path_tagged() -> Result<(), Error> {
+    let options = Options::new().with_struct_encoding(StructEncoding::Tagged(FieldTag::Index));
+    let mut bytes = shaft::to_bytes_with(&Item { sku: 'a', count: 1 }, options)?;
+    bytes[20..24].copy_from_slice(&0xd800u32.to_le_bytes());
+    let error = shaft::from_bytes_with::<Item>(&bytes, options).unwrap_err();
+    assert_eq!(error.path(), Some(".sku"));
+    assert_eq!(error.offset(), Some(24));
+    Ok(())
+}
+
+#[test]
+fn error_top_level() {
+    let error = shaft::from_bytes::<u32>(&[1, 2]).unwrap_err();
+    assert_eq!(error.path(), Some(""));
+    assert_eq!(error.offset(), Some(0));
+    assert!(error.to_string().ends_with(" at offset 0"));
+}
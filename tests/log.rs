@@ -0,0 +1,223 @@
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use shaft::log::{self, LogReader, LogWriter, SyncPolicy};
+use shaft::{Endian, Error, ErrorKind, IntEncoding, Options};
+
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+struct Entry {
+    key: String,
+    value: u32,
+}
+
+fn entry(value: u32) -> Entry {
+    Entry {
+        key: format!("key {value}"),
+        value,
+    }
+}
+
+/// Temporary directory removed when dropped.
+struct TempDir(PathBuf);
+
+impl TempDir {
+    fn new(name: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!("shaft-log-{}-{name}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        Self(dir)
+    }
+
+    fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+fn write_log(path: &Path, options: Options, len: u32) -> Result<(), Error> {
+    let mut writer = LogWriter::open_with(path, options, SyncPolicy::Never)?;
+    for value in 0..len {
+        writer.append(&entry(value))?;
+    }
+    writer.sync()
+}
+
+fn read_log(path: &Path) -> Result<Vec<Entry>, Error> {
+    LogReader::open(path)?.collect()
+}
+
+#[test]
+fn serialize_log() -> Result<(), Error> {
+    let dir = TempDir::new("serialize");
+    let path = dir.path().join("serialize.log");
+    let mut writer = LogWriter::open(&path)?;
+    writer.append(&7u16)?;
+    drop(writer);
+    assert_eq!(
+        fs::read(&path).unwrap(),
+        [
+            b'S', b'H', b'F', b'L', 1, 0, 0, 0, // header
+            2, 0, 0, 0, // record length
+            0x97, 0xbe, 0x0c, 0x8b, // record checksum
+            0x24, 0x83, 0x54, 0x87, // frame checksum
+            7, 0, // record
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn roundtrip_log() -> Result<(), Error> {
+    let dir = TempDir::new("roundtrip");
+    for (index, options) in [
+        Options::new(),
+        Options::new().with_endian(Endian::Big),
+        Options::new().with_int_encoding(IntEncoding::Varint),
+    ]
+    .into_iter()
+    .enumerate()
+    {
+        let path = dir.path().join(format!("roundtrip-{index}.log"));
+        write_log(&path, options, 10)?;
+        let reader = LogReader::<Entry>::open(&path)?;
+        assert_eq!(reader.options(), options);
+        let entries = reader.collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(entries, (0..10).map(entry).collect::<Vec<_>>());
+    }
+    Ok(())
+}
+
+#[test]
+fn reopen_log_appends() -> Result<(), Error> {
+    let dir = TempDir::new("reopen");
+    let path = dir.path().join("reopen.log");
+    write_log(&path, Options::new(), 3)?;
+    let mut writer = LogWriter::open(&path)?;
+    assert_eq!(writer.len(), 3);
+    writer.append(&entry(3))?;
+    drop(writer);
+    assert_eq!(read_log(&path)?, (0..4).map(entry).collect::<Vec<_>>());
+    Ok(())
+}
+
+#[test]
+fn reopen_log_options_mismatch() -> Result<(), Error> {
+    let dir = TempDir::new("mismatch");
+    let path = dir.path().join("mismatch.log");
+    write_log(&path, Options::new(), 1)?;
+    let options = Options::new().with_endian(Endian::Big);
+    assert!(LogWriter::<Entry>::open_with(&path, options, SyncPolicy::Always).is_err());
+    Ok(())
+}
+
+#[test]
+fn recover_torn_record() -> Result<(), Error> {
+    let dir = TempDir::new("torn");
+    let path = dir.path().join("torn.log");
+    write_log(&path, Options::new(), 5)?;
+    let len = fs::metadata(&path).unwrap().len();
+    let file = OpenOptions::new().write(true).open(&path).unwrap();
+    file.set_len(len - 3).unwrap();
+    drop(file);
+    assert!(read_log(&path).is_err());
+    assert_eq!(log::recover(&path)?, 4);
+    assert_eq!(read_log(&path)?, (0..4).map(entry).collect::<Vec<_>>());
+    assert_eq!(log::recover(&path)?, 4);
+    Ok(())
+}
+
+#[test]
+fn recover_corrupted_last_record() -> Result<(), Error> {
+    let dir = TempDir::new("corrupted-last");
+    let path = dir.path().join("corrupted-last.log");
+    write_log(&path, Options::new(), 5)?;
+    let mut bytes = fs::read(&path).unwrap();
+    let len = bytes.len();
+    bytes[len - 1] ^= 1;
+    fs::write(&path, &bytes).unwrap();
+    assert_eq!(log::recover(&path)?, 4);
+    assert_eq!(read_log(&path)?, (0..4).map(entry).collect::<Vec<_>>());
+    Ok(())
+}
+
+#[test]
+fn recover_corrupted_record() -> Result<(), Error> {
+    let dir = TempDir::new("corrupted");
+    let path = dir.path().join("corrupted.log");
+    write_log(&path, Options::new(), 5)?;
+    let mut bytes = fs::read(&path).unwrap();
+    let len = bytes.len();
+    bytes[len - 1] ^= 1;
+    bytes.extend([1, 2, 3]);
+    fs::write(&path, &bytes).unwrap();
+    assert!(log::recover(&path).is_err());
+    assert!(LogWriter::<Entry>::open(&path).is_err());
+    assert_eq!(fs::read(&path).unwrap(), bytes);
+    let result: Result<Vec<_>, _> = LogReader::<Entry>::open(&path)?.collect();
+    assert!(result.is_err());
+    Ok(())
+}
+
+#[test]
+fn recover_corrupted_length() -> Result<(), Error> {
+    let dir = TempDir::new("length");
+    let path = dir.path().join("length.log");
+    write_log(&path, Options::new(), 5)?;
+    let mut bytes = fs::read(&path).unwrap();
+    let first = u32::from_le_bytes(bytes[8..12].try_into().unwrap()) as usize;
+    bytes[8 + 12 + first + 2] ^= 1;
+    fs::write(&path, &bytes).unwrap();
+    assert!(log::recover(&path).is_err());
+    assert_eq!(fs::read(&path).unwrap(), bytes);
+    Ok(())
+}
+
+#[test]
+fn open_log_recovers() -> Result<(), Error> {
+    let dir = TempDir::new("open");
+    let path = dir.path().join("open.log");
+    write_log(&path, Options::new(), 2)?;
+    let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+    file.write_all(&[9, 0, 0]).unwrap();
+    drop(file);
+    let mut writer = LogWriter::open(&path)?;
+    assert_eq!(writer.len(), 2);
+    writer.append(&entry(2))?;
+    drop(writer);
+    assert_eq!(read_log(&path)?, (0..3).map(entry).collect::<Vec<_>>());
+    Ok(())
+}
+
+#[test]
+fn open_log_torn_header() -> Result<(), Error> {
+    let dir = TempDir::new("header");
+    let path = dir.path().join("header.log");
+    fs::write(&path, b"SHF").unwrap();
+    let mut writer = LogWriter::open(&path)?;
+    assert!(writer.is_empty());
+    writer.append(&entry(0))?;
+    drop(writer);
+    assert_eq!(read_log(&path)?, [entry(0)]);
+    fs::write(&path, b"data").unwrap();
+    assert!(LogWriter::<Entry>::open(&path).is_err());
+    Ok(())
+}
+
+#[test]
+fn open_log_io_error() {
+    let dir = TempDir::new("missing");
+    let path = dir.path().join("missing").join("missing.log");
+    let error = LogReader::<Entry>::open(&path).err().unwrap();
+    assert!(matches!(error.kind(), ErrorKind::Io(_)));
+    let error = LogWriter::<Entry>::open(&path).err().unwrap();
+    assert!(matches!(error.kind(), ErrorKind::Io(_)));
+}
@@ -0,0 +1,105 @@
+#![cfg(feature = "mmap")]
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use shaft::mmap::Mapped;
+use shaft::{Checksum, Endian, Error, ErrorKind, IntEncoding, Options};
+
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+struct Dataset<'a> {
+    name: &'a str,
+    samples: &'a [u8],
+    #[serde(borrow)]
+    labels: Vec<&'a str>,
+}
+
+/// Temporary directory removed when dropped.
+struct TempDir(PathBuf);
+
+impl TempDir {
+    fn new(name: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!("shaft-mmap-{}-{name}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        Self(dir)
+    }
+
+    fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+#[test]
+fn get_mapped_borrowed() -> Result<(), Error> {
+    let dataset = Dataset {
+        name: "Ferris",
+        samples: &[1, 2, 3, 4],
+        labels: vec!["crab"; 3],
+    };
+    let dir = TempDir::new("borrowed");
+    let path = dir.path().join("borrowed.bin");
+    fs::write(&path, shaft::to_bytes(&dataset)?).unwrap();
+    let mapped: Mapped<Dataset> = unsafe { Mapped::open(&path) }?;
+    let result = mapped.get()?;
+    assert_eq!(result, dataset);
+    let range = mapped.as_bytes().as_ptr_range();
+    assert!(range.contains(&result.name.as_ptr()));
+    assert!(range.contains(&result.samples.as_ptr()));
+    assert!(range.contains(&result.labels[2].as_ptr()));
+    Ok(())
+}
+
+#[test]
+fn get_mapped_with_options() -> Result<(), Error> {
+    let dataset = Dataset {
+        name: "Ferris",
+        samples: &[1, 2, 3, 4],
+        labels: vec!["crab"; 3],
+    };
+    let dir = TempDir::new("options");
+    let path = dir.path().join("options.bin");
+    for options in [
+        Options::new().with_endian(Endian::Big),
+        Options::new().with_int_encoding(IntEncoding::Varint),
+        Options::new().with_checksum(Checksum::Crc32c),
+    ] {
+        fs::write(&path, shaft::to_bytes_with(&dataset, options)?).unwrap();
+        let mapped: Mapped<Dataset> = unsafe { Mapped::open_with(&path, options) }?;
+        assert_eq!(mapped.options(), options);
+        assert_eq!(mapped.get()?, dataset);
+    }
+    Ok(())
+}
+
+#[test]
+fn get_mapped_invalid() -> Result<(), Error> {
+    let dataset = Dataset {
+        name: "Ferris",
+        samples: &[1, 2, 3, 4],
+        labels: vec!["crab"; 3],
+    };
+    let dir = TempDir::new("invalid");
+    let path = dir.path().join("invalid.bin");
+    let bytes = shaft::to_bytes(&dataset)?;
+    fs::write(&path, &bytes[..bytes.len() - 1]).unwrap();
+    let mapped: Mapped<Dataset> = unsafe { Mapped::open(&path) }?;
+    assert!(mapped.get().is_err());
+    Ok(())
+}
+
+#[test]
+fn open_mapped_missing() {
+    let dir = TempDir::new("missing");
+    let result = unsafe { Mapped::<Dataset>::open(dir.path().join("missing.bin")) };
+    assert!(matches!(result.err().unwrap().kind(), ErrorKind::Io(_)));
+}
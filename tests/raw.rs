@@ -0,0 +1,108 @@
+use serde::{Deserialize, Serialize};
+use shaft::{debug, Checksum, Endian, Error, IntEncoding, LenWidth, Options, Raw};
+
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+struct Header {
+    route: u8,
+    name: String,
+}
+
+#[derive(Deserialize, Serialize)]
+struct Message<'a> {
+    #[serde(borrow)]
+    header: Raw<'a, Header>,
+    body: u16,
+}
+
+#[test]
+fn serialize_raw() -> Result<(), Error> {
+    let header = Header {
+        route: 3,
+        name: "ab".to_owned(),
+    };
+    let options = Options::new().with_len_width(LenWidth::U8);
+    let message = Message {
+        header: Raw::with_options(&header, options)?,
+        body: 1,
+    };
+    let bytes = shaft::to_bytes_with(&message, options)?;
+    assert_eq!(bytes, [3, 2, b'a', b'b', 1, 0]);
+    Ok(())
+}
+
+#[test]
+fn serialize_raw_from_encoded() -> Result<(), Error> {
+    let options = Options::new().with_len_width(LenWidth::U8);
+    let message = Message {
+        header: Raw::from_encoded(&[3, 0][..], options),
+        body: 1,
+    };
+    let bytes = shaft::to_bytes_with(&message, options)?;
+    assert_eq!(bytes, [3, 0, 1, 0]);
+    Ok(())
+}
+
+#[test]
+fn deserialize_raw_span() -> Result<(), Error> {
+    let header = Header {
+        route: 3,
+        name: "ab".to_owned(),
+    };
+    let options = Options::new().with_len_width(LenWidth::U8);
+    let bytes = [3, 2, b'a', b'b', 1, 0];
+    let message: Message = shaft::from_bytes_with(&bytes, options)?;
+    assert_eq!(message.header.as_bytes(), &bytes[..4]);
+    assert_eq!(message.header.as_bytes().as_ptr(), bytes.as_ptr());
+    assert_eq!(message.header.get()?, header);
+    assert_eq!(message.body, 1);
+    Ok(())
+}
+
+#[test]
+fn splice_raw_untouched() -> Result<(), Error> {
+    let header = Header {
+        route: 3,
+        name: "ab".to_owned(),
+    };
+    for options in [
+        Options::new(),
+        Options::new().with_endian(Endian::Big),
+        Options::new().with_int_encoding(IntEncoding::Varint),
+        Options::new().with_checksum(Checksum::Crc32c),
+    ] {
+        let message = Message {
+            header: Raw::with_options(&header, options)?,
+            body: 300,
+        };
+        let bytes = shaft::to_bytes_with(&message, options)?;
+        let result: Message = shaft::from_bytes_with(&bytes, options)?;
+        assert_eq!(
+            result.header.options(),
+            options.with_checksum(Checksum::None)
+        );
+        assert_eq!(result.header.as_bytes(), message.header.as_bytes());
+        assert_eq!(shaft::to_bytes_with(&result, options)?, bytes);
+        assert_eq!(result.body, 300);
+    }
+    Ok(())
+}
+
+#[test]
+fn deserialize_raw_invalid() {
+    let options = Options::new().with_len_width(LenWidth::U8);
+    let bytes = [3, 2, b'a'];
+    let result: Result<Message, Error> = shaft::from_bytes_with(&bytes, options);
+    assert!(result.is_err());
+}
+
+#[test]
+fn annotate_raw() {
+    let options = Options::new().with_len_width(LenWidth::U8);
+    let bytes = [3, 2, b'a', b'b', 1, 0];
+    let dump = debug::annotate_with::<Message>(&bytes, options);
+    assert_eq!(
+        dump,
+        "0000..0004 .header raw 4 bytes\n\
+         0004..0006 .body u16 1\n"
+    );
+}
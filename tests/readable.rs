@@ -0,0 +1,42 @@
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6};
+
+use serde::{de::DeserializeOwned, Serialize};
+use shaft::{Error, Options};
+
+fn roundtrip<T>(value: &T, options: Options) -> Result<Vec<u8>, Error>
+where
+    T: std::fmt::Debug + PartialEq + Serialize + DeserializeOwned,
+{
+    let bytes = shaft::to_bytes_with(value, options)?;
+    let result: T = shaft::from_bytes_with(&bytes, options)?;
+    assert_eq!(&result, value);
+    Ok(bytes)
+}
+
+#[test]
+fn roundtrip_net_types() -> Result<(), Error> {
+    let v4 = Ipv4Addr::new(192, 168, 0, 1);
+    let v6 = Ipv6Addr::LOCALHOST;
+    for options in [Options::new(), Options::new().with_human_readable(true)] {
+        roundtrip(&v4, options)?;
+        roundtrip(&v6, options)?;
+        roundtrip(&IpAddr::V4(v4), options)?;
+        roundtrip(&IpAddr::V6(v6), options)?;
+        roundtrip(&SocketAddr::V4(SocketAddrV4::new(v4, 8080)), options)?;
+        roundtrip(&SocketAddr::V6(SocketAddrV6::new(v6, 443, 0, 0)), options)?;
+    }
+    Ok(())
+}
+
+#[test]
+fn serialize_compact_net_types() -> Result<(), Error> {
+    let address = SocketAddrV4::new(Ipv4Addr::new(10, 0, 0, 1), 8080);
+    let bytes = shaft::to_bytes(&address)?;
+    assert_eq!(bytes, [10, 0, 0, 1, 0x90, 0x1f]);
+
+    let options = Options::new().with_human_readable(true);
+    let bytes = shaft::to_bytes_with(&address, options)?;
+    assert_eq!(bytes[..8], 13u64.to_le_bytes());
+    assert_eq!(&bytes[8..], b"10.0.0.1:8080");
+    Ok(())
+}
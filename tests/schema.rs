@@ -0,0 +1,557 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+use shaft::schema::{
+    self, ChangeKind, Compatibility, Container, Field, Format, Payload, Schema, Variant,
+};
+use shaft::{Error, FieldTag, Options, StructEncoding, VariantEncoding};
+
+fn named(name: &str) -> Format {
+    Format::Named(name.to_owned())
+}
+
+#[test]
+fn trace_primitive() -> Result<(), Error> {
+    let schema = Schema::trace::<u32>()?;
+    assert_eq!(schema.root(), &Format::U32);
+    assert_eq!(schema.containers().count(), 0);
+    Ok(())
+}
+
+#[test]
+fn trace_compound() -> Result<(), Error> {
+    let schema = Schema::trace::<(Option<String>, Vec<u8>, HashMap<String, i64>)>()?;
+    assert_eq!(
+        schema.root(),
+        &Format::Tuple(vec![
+            Format::Option(Box::new(Format::Str)),
+            Format::Seq(Box::new(Format::U8)),
+            Format::Map(Box::new(Format::Str), Box::new(Format::I64)),
+        ])
+    );
+    Ok(())
+}
+
+#[test]
+fn trace_struct() -> Result<(), Error> {
+    #[derive(Deserialize)]
+    #[allow(dead_code)]
+    struct Struct<'a> {
+        name: &'a str,
+        score: u32,
+        position: Position,
+    }
+    #[derive(Deserialize)]
+    #[allow(dead_code)]
+    struct Position(f32, f32);
+
+    let schema = Schema::trace::<Struct>()?;
+    assert_eq!(schema.root(), &named("Struct"));
+    assert_eq!(
+        schema.container("Struct"),
+        Some(&Container::Struct(vec![
+            Field::new("name", Format::Str),
+            Field::new("score", Format::U32),
+            Field::new("position", named("Position")),
+        ]))
+    );
+    assert_eq!(
+        schema.container("Position"),
+        Some(&Container::TupleStruct(vec![Format::F32, Format::F32]))
+    );
+    Ok(())
+}
+
+#[test]
+fn trace_enum() -> Result<(), Error> {
+    #[derive(Deserialize)]
+    #[allow(dead_code)]
+    enum Enum {
+        A,
+        B(u8),
+        C(u8, u16),
+        D { value: bool },
+    }
+
+    let schema = Schema::trace::<Enum>()?;
+    assert_eq!(
+        schema.container("Enum"),
+        Some(&Container::Enum(vec![
+            Variant::new("A", Payload::Unit),
+            Variant::new("B", Payload::Newtype(Format::U8)),
+            Variant::new("C", Payload::Tuple(vec![Format::U8, Format::U16])),
+            Variant::new(
+                "D",
+                Payload::Struct(vec![Field::new("value", Format::Bool)])
+            ),
+        ]))
+    );
+    Ok(())
+}
+
+#[test]
+fn trace_reserved_newtypes() -> Result<(), Error> {
+    #[derive(Deserialize, serde::Serialize)]
+    #[allow(dead_code)]
+    struct Struct<'a> {
+        #[serde(with = "shaft::with::varint")]
+        id: u64,
+        #[serde(with = "shaft::with::varint")]
+        count: u16,
+        #[serde(borrow)]
+        raw: shaft::Raw<'a, (u8, String)>,
+        #[serde(borrow)]
+        lazy: shaft::Lazy<'a, u32>,
+    }
+
+    let schema = Schema::trace::<Struct>()?;
+    assert_eq!(
+        schema.container("Struct"),
+        Some(&Container::Struct(vec![
+            Field::new("id", adapted("varint", Format::U64)),
+            Field::new("count", adapted("varint", Format::U16)),
+            Field::new("raw", Format::Tuple(vec![Format::U8, Format::Str])),
+            Field::new("lazy", Format::Bytes),
+        ]))
+    );
+    assert_eq!(schema.containers().count(), 1);
+
+    let value = Struct {
+        id: 300,
+        count: 2,
+        raw: shaft::Raw::new(&(1u8, "a".to_owned()))?,
+        lazy: shaft::Lazy::new(&7u32)?,
+    };
+    let bytes = shaft::typed::to_bytes(&value)?;
+    let result: Struct = shaft::typed::from_bytes(&bytes)?;
+    assert_eq!((result.id, result.count), (300, 2));
+    Ok(())
+}
+
+#[test]
+fn trace_recursive_struct() -> Result<(), Error> {
+    #[derive(Deserialize)]
+    #[allow(dead_code)]
+    struct Node {
+        value: u32,
+        children: Vec<Node>,
+        parent: Option<Box<Node>>,
+    }
+
+    let schema = Schema::trace::<Node>()?;
+    assert_eq!(
+        schema.container("Node"),
+        Some(&Container::Struct(vec![
+            Field::new("value", Format::U32),
+            Field::new("children", Format::Seq(Box::new(named("Node")))),
+            Field::new("parent", Format::Option(Box::new(named("Node")))).with_optional(true),
+        ]))
+    );
+    Ok(())
+}
+
+#[test]
+fn trace_recursive_enum() -> Result<(), Error> {
+    #[derive(Deserialize)]
+    #[allow(dead_code)]
+    enum List {
+        Cons(u32, Box<List>),
+        Nil,
+    }
+
+    let schema = Schema::trace::<List>()?;
+    assert_eq!(
+        schema.container("List"),
+        Some(&Container::Enum(vec![
+            Variant::new("Cons", Payload::Tuple(vec![Format::U32, named("List")])),
+            Variant::new("Nil", Payload::Unit),
+        ]))
+    );
+    Ok(())
+}
+
+#[test]
+fn trace_conflicting_generics() {
+    #[derive(Deserialize)]
+    #[allow(dead_code)]
+    struct Wrapper<T>(T, T);
+
+    let result = Schema::trace::<(Wrapper<u8>, Wrapper<u16>)>();
+    assert!(result.is_err());
+}
+
+fn adapted(adapter: &str, format: Format) -> Format {
+    Format::Adapted(adapter.to_owned(), Box::new(format))
+}
+
+fn player(fields: Vec<Field>) -> Schema {
+    Schema::new(named("Player")).with_container("Player", Container::Struct(fields))
+}
+
+fn shape(variants: &[&str]) -> Schema {
+    let variants = variants
+        .iter()
+        .map(|name| Variant::new(*name, Payload::Unit))
+        .collect();
+    Schema::new(named("Shape")).with_container("Shape", Container::Enum(variants))
+}
+
+#[test]
+fn check_compat_identical() -> Result<(), Error> {
+    #[derive(Deserialize)]
+    #[allow(dead_code)]
+    struct Struct {
+        values: Vec<(u8, String)>,
+    }
+
+    let old = Schema::trace::<Struct>()?;
+    let new = Schema::trace::<Struct>()?;
+    let report = schema::check_compat(&old, &new);
+    assert!(report.changes().is_empty());
+    assert_eq!(report.compatibility(), Compatibility::Compatible);
+    Ok(())
+}
+
+#[test]
+fn check_compat_field_renamed() {
+    let old = player(vec![Field::new("name", Format::Str)]);
+    let new = player(vec![Field::new("nickname", Format::Str)]);
+    let report = schema::check_compat(&old, &new);
+    assert_eq!(report.compatibility(), Compatibility::Compatible);
+    assert_eq!(
+        report.changes()[0].kind(),
+        &ChangeKind::FieldRenamed {
+            old: "name".to_owned(),
+            new: "nickname".to_owned()
+        }
+    );
+}
+
+#[test]
+fn check_compat_field_moved() {
+    let old = player(vec![
+        Field::new("name", Format::Str),
+        Field::new("score", Format::U32),
+    ]);
+    let new = player(vec![
+        Field::new("score", Format::U32),
+        Field::new("name", Format::Str),
+    ]);
+    let report = schema::check_compat(&old, &new);
+    assert_eq!(report.compatibility(), Compatibility::Breaking);
+    assert_eq!(
+        report.changes()[0].kind(),
+        &ChangeKind::FieldMoved {
+            name: "name".to_owned(),
+            old_index: 0,
+            new_index: 1
+        }
+    );
+}
+
+#[test]
+fn check_compat_field_appended() {
+    let old = player(vec![Field::new("name", Format::Str)]);
+    let new = player(vec![
+        Field::new("name", Format::Str),
+        Field::new("score", Format::U32),
+    ]);
+    let report = schema::check_compat(&old, &new);
+    assert_eq!(report.compatibility(), Compatibility::Breaking);
+    assert_eq!(
+        report.changes()[0].kind(),
+        &ChangeKind::FieldAppended {
+            name: "score".to_owned()
+        }
+    );
+}
+
+#[test]
+fn check_compat_integer_width() {
+    let old = player(vec![Field::new("score", Format::U32)]);
+    let new = player(vec![Field::new("score", Format::U64)]);
+    let report = schema::check_compat(&old, &new);
+    assert_eq!(report.compatibility(), Compatibility::Breaking);
+    assert_eq!(report.changes()[0].path(), ".score");
+    assert_eq!(
+        report.changes()[0].kind(),
+        &ChangeKind::IntegerWidthChanged {
+            old: Format::U32,
+            new: Format::U64
+        }
+    );
+}
+
+#[test]
+fn check_compat_adapter_changed() -> Result<(), Error> {
+    #[derive(Deserialize)]
+    #[serde(rename = "Player")]
+    #[allow(dead_code)]
+    struct Old {
+        score: u32,
+        #[serde(with = "shaft::with::u8_len")]
+        name: String,
+    }
+    #[derive(Deserialize)]
+    #[serde(rename = "Player")]
+    #[allow(dead_code)]
+    struct New {
+        #[serde(with = "shaft::with::varint")]
+        score: u32,
+        #[serde(with = "shaft::with::u16_len")]
+        name: String,
+    }
+
+    let old = Schema::trace::<Old>()?;
+    let new = Schema::trace::<New>()?;
+    let report = schema::check_compat(&old, &new);
+    assert_eq!(report.compatibility(), Compatibility::Breaking);
+    assert_eq!(
+        report.to_string(),
+        ".score: format changed from u32 to with::varint<u32> (breaking)\n\
+         .name: format changed from with::u8_len<str> to with::u16_len<str> (breaking)\n"
+    );
+    assert_ne!(old.fingerprint(), new.fingerprint());
+    let report = schema::check_compat(&new, &Schema::trace::<New>()?);
+    assert!(report.changes().is_empty());
+    Ok(())
+}
+
+#[test]
+fn check_compat_variant_appended() {
+    let report = schema::check_compat(&shape(&["Circle"]), &shape(&["Circle", "Square"]));
+    assert_eq!(report.compatibility(), Compatibility::ForwardOnly);
+    assert!(report.is_compatible());
+}
+
+#[test]
+fn check_compat_variant_renamed() {
+    let report = schema::check_compat(&shape(&["Circle", "Square"]), &shape(&["Round", "Square"]));
+    assert_eq!(report.compatibility(), Compatibility::Compatible);
+    assert_eq!(
+        report.changes()[0].kind(),
+        &ChangeKind::VariantRenamed {
+            old: "Circle".to_owned(),
+            new: "Round".to_owned()
+        }
+    );
+}
+
+#[test]
+fn check_compat_variant_removed() {
+    let report = schema::check_compat(&shape(&["Circle", "Square"]), &shape(&["Square"]));
+    assert_eq!(report.compatibility(), Compatibility::Breaking);
+    assert!(!report.is_compatible());
+}
+
+#[test]
+fn check_compat_newtype_transparent() {
+    let old = Schema::new(Format::U32);
+    let new = Schema::new(named("Meters"))
+        .with_container("Meters", Container::NewtypeStruct(Format::U32));
+    let report = schema::check_compat(&old, &new);
+    assert!(report.changes().is_empty());
+}
+
+#[test]
+fn check_compat_recursive() -> Result<(), Error> {
+    #[derive(Deserialize)]
+    #[allow(dead_code)]
+    struct Node {
+        children: Vec<Node>,
+        value: u8,
+    }
+
+    let old = Schema::trace::<Node>()?;
+    let new = player(vec![
+        Field::new("children", Format::Seq(Box::new(named("Player")))),
+        Field::new("value", Format::U16),
+    ]);
+    let report = schema::check_compat(&old, &new);
+    assert_eq!(
+        report.to_string(),
+        ".: container `Node` renamed to `Player` (compatible)\n\
+         .value: integer width changed from u8 to u16 (breaking)\n"
+    );
+    Ok(())
+}
+
+#[test]
+fn check_compat_tagged_hash() {
+    let options = Options::new().with_struct_encoding(StructEncoding::Tagged(FieldTag::Hash));
+    let old = player(vec![
+        Field::new("name", Format::Str),
+        Field::new("score", Format::U32),
+    ]);
+    let new = player(vec![
+        Field::new("score", Format::U32),
+        Field::new("name", Format::Str),
+        Field::new("level", Format::Option(Box::new(Format::U8))).with_optional(true),
+    ]);
+    let report = schema::check_compat_with(&old, &new, options);
+    assert_eq!(report.compatibility(), Compatibility::ForwardOnly);
+}
+
+#[test]
+fn check_compat_tagged_index() {
+    let options = Options::new().with_struct_encoding(StructEncoding::Tagged(FieldTag::Index));
+    let old = player(vec![
+        Field::new("name", Format::Str),
+        Field::new("score", Format::U32),
+    ]);
+    let new = player(vec![Field::new("nickname", Format::Str)]);
+    let report = schema::check_compat_with(&old, &new, options);
+    assert_eq!(report.compatibility(), Compatibility::ForwardOnly);
+    assert_eq!(report.changes().len(), 2);
+}
+
+#[test]
+fn check_compat_trailing_defaults() {
+    let options = Options::new().with_trailing_defaults(true);
+    let old = Schema::new(Format::Tuple(vec![named("Player")])).with_container(
+        "Player",
+        Container::Struct(vec![Field::new("name", Format::Str)]),
+    );
+    let new = Schema::new(Format::Tuple(vec![named("Player")])).with_container(
+        "Player",
+        Container::Struct(vec![
+            Field::new("name", Format::Str),
+            Field::new("score", Format::U32).with_default(true),
+        ]),
+    );
+    let report = schema::check_compat_with(&old, &new, options);
+    assert_eq!(report.compatibility(), Compatibility::ForwardOnly);
+
+    let old = Schema::new(Format::Seq(Box::new(named("Player")))).with_container(
+        "Player",
+        Container::Struct(vec![Field::new("name", Format::Str)]),
+    );
+    let new = Schema::new(Format::Seq(Box::new(named("Player")))).with_container(
+        "Player",
+        Container::Struct(vec![
+            Field::new("name", Format::Str),
+            Field::new("score", Format::U32).with_default(true),
+        ]),
+    );
+    let report = schema::check_compat_with(&old, &new, options);
+    assert_eq!(report.compatibility(), Compatibility::Breaking);
+}
+
+#[test]
+fn trace_missing_fields() -> Result<(), Error> {
+    #[derive(Deserialize)]
+    #[allow(dead_code)]
+    struct Struct {
+        a: u32,
+        b: Option<u8>,
+        #[serde(default)]
+        c: u16,
+        d: Shape,
+    }
+    #[derive(Deserialize)]
+    #[allow(dead_code)]
+    enum Shape {
+        Point,
+        Circle {
+            radius: f32,
+            #[serde(default)]
+            filled: bool,
+        },
+    }
+
+    let schema = Schema::trace::<Struct>()?;
+    assert_eq!(
+        schema.container("Struct"),
+        Some(&Container::Struct(vec![
+            Field::new("a", Format::U32),
+            Field::new("b", Format::Option(Box::new(Format::U8))).with_optional(true),
+            // Fields after it cannot be missing from positional input.
+            Field::new("c", Format::U16).with_optional(true),
+            Field::new("d", named("Shape")),
+        ]))
+    );
+    assert_eq!(
+        schema.container("Shape"),
+        Some(&Container::Enum(vec![
+            Variant::new("Point", Payload::Unit),
+            Variant::new(
+                "Circle",
+                Payload::Struct(vec![
+                    Field::new("radius", Format::F32),
+                    Field::new("filled", Format::Bool)
+                        .with_optional(true)
+                        .with_default(true),
+                ])
+            ),
+        ]))
+    );
+    Ok(())
+}
+
+#[test]
+fn check_compat_field_required() -> Result<(), Error> {
+    #[derive(Deserialize)]
+    #[serde(rename = "Player")]
+    #[allow(dead_code)]
+    struct Old {
+        name: String,
+    }
+    #[derive(Deserialize)]
+    #[serde(rename = "Player")]
+    #[allow(dead_code)]
+    struct Required {
+        name: String,
+        score: u32,
+    }
+    #[derive(Deserialize)]
+    #[serde(rename = "Player")]
+    #[allow(dead_code)]
+    struct Optional {
+        name: String,
+        score: Option<u32>,
+    }
+    #[derive(Deserialize)]
+    #[serde(rename = "Player")]
+    #[allow(dead_code)]
+    struct Default {
+        name: String,
+        #[serde(default)]
+        score: u32,
+    }
+
+    let old = Schema::trace::<Old>()?;
+    let required = Schema::trace::<Required>()?;
+    let optional = Schema::trace::<Optional>()?;
+    let default = Schema::trace::<Default>()?;
+    let tagged = Options::new().with_struct_encoding(StructEncoding::Tagged(FieldTag::Hash));
+    let trailing = Options::new().with_trailing_defaults(true);
+    for (new, options, compatibility) in [
+        (&required, tagged, Compatibility::Breaking),
+        (&optional, tagged, Compatibility::ForwardOnly),
+        (&default, tagged, Compatibility::ForwardOnly),
+        (&required, trailing, Compatibility::Breaking),
+        (&optional, trailing, Compatibility::Breaking),
+        (&default, trailing, Compatibility::ForwardOnly),
+    ] {
+        let report = schema::check_compat_with(&old, new, options);
+        assert_eq!(report.compatibility(), compatibility);
+    }
+    Ok(())
+}
+
+#[test]
+fn check_compat_variant_names() {
+    let old = shape(&["Circle", "Square"]);
+    let new = shape(&["Point", "Square", "Circle"]);
+    let report = schema::check_compat(&old, &new);
+    assert_eq!(report.compatibility(), Compatibility::Breaking);
+    for encoding in [VariantEncoding::Name, VariantEncoding::Hash] {
+        let options = Options::new().with_variant_encoding(encoding);
+        let report = schema::check_compat_with(&old, &new, options);
+        assert_eq!(report.compatibility(), Compatibility::ForwardOnly);
+    }
+
+    let options = Options::new().with_variant_encoding(VariantEncoding::Name);
+    let report = schema::check_compat_with(&shape(&["Circle"]), &shape(&["Round"]), options);
+    assert_eq!(report.compatibility(), Compatibility::Breaking);
+}
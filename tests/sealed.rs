@@ -0,0 +1,84 @@
+#![cfg(feature = "crypto")]
+
+use serde::{Deserialize, Serialize};
+use shaft::sealed::{self, Key};
+use shaft::{Error, ErrorKind, Options};
+
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+struct Token {
+    user: String,
+    secret: u64,
+}
+
+fn is_authentication_error<T>(result: Result<T, Error>) -> bool {
+    matches!(
+        result.map_err(|error| matches!(error.kind(), ErrorKind::AuthenticationFailed)),
+        Err(true)
+    )
+}
+
+#[test]
+fn roundtrip_sealed() -> Result<(), Error> {
+    let token = Token {
+        user: "Ferris".to_owned(),
+        secret: 42,
+    };
+    let key = Key::from_bytes([7; 32]);
+    let options = Options::new().with_trailing_defaults(true);
+    let bytes = sealed::to_bytes_with(&token, &key, b"user:1", options)?;
+    let result: Token = sealed::from_bytes_with(&bytes, &key, b"user:1", options)?;
+    assert_eq!(result, token);
+    Ok(())
+}
+
+#[test]
+fn serialize_random_nonce() -> Result<(), Error> {
+    let token = Token {
+        user: "Ferris".to_owned(),
+        secret: 42,
+    };
+    let key = Key::generate();
+    let first = sealed::to_bytes(&token, &key)?;
+    let second = sealed::to_bytes(&token, &key)?;
+    assert_ne!(first[..12], second[..12]);
+    assert_eq!(first.len(), 12 + shaft::to_bytes(&token)?.len() + 16);
+    Ok(())
+}
+
+#[test]
+fn deserialize_tampered() -> Result<(), Error> {
+    let token = Token {
+        user: "Ferris".to_owned(),
+        secret: 42,
+    };
+    let key = Key::generate();
+    let bytes = sealed::to_bytes(&token, &key)?;
+    for index in [0, 12, bytes.len() - 1] {
+        let mut tampered = bytes.clone();
+        tampered[index] ^= 1;
+        assert!(is_authentication_error(sealed::from_bytes::<Token>(
+            &tampered, &key
+        )));
+    }
+    assert!(is_authentication_error(sealed::from_bytes::<Token>(
+        &bytes[..8],
+        &key
+    )));
+    Ok(())
+}
+
+#[test]
+fn deserialize_wrong_key_or_associated_data() -> Result<(), Error> {
+    let token = Token {
+        user: "Ferris".to_owned(),
+        secret: 42,
+    };
+    let key = Key::generate();
+    let bytes = sealed::to_bytes_with(&token, &key, b"user:1", Options::new())?;
+    let result =
+        sealed::from_bytes_with::<Token>(&bytes, &Key::generate(), b"user:1", Options::new());
+    assert!(is_authentication_error(result));
+    let result = sealed::from_bytes_with::<Token>(&bytes, &key, b"user:2", Options::new());
+    assert!(is_authentication_error(result));
+    Ok(())
+}
@@ -0,0 +1,137 @@
+use std::{collections::HashMap, io::Cursor};
+
+use serde::de::{Deserialize, DeserializeSeed};
+use serde::Serialize;
+use shaft::{Checksum, Deserializer, Endian, Error, Options, SliceReader};
+
+/// Seed mapping strings to indices in a table of interned strings.
+struct Intern<'s>(&'s mut HashMap<String, usize>);
+
+impl<'de> DeserializeSeed<'de> for Intern<'_> {
+    type Value = usize;
+
+    fn deserialize<D: serde::Deserializer<'de>>(self, deserializer: D) -> Result<usize, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        let len = self.0.len();
+        Ok(*self.0.entry(value).or_insert(len))
+    }
+}
+
+/// Seed deserializing a sequence of `u32` scaled by a factor.
+struct Scale(u32);
+
+impl<'de> DeserializeSeed<'de> for Scale {
+    type Value = Vec<u32>;
+
+    fn deserialize<D: serde::Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<Vec<u32>, D::Error> {
+        let values = Vec::<u32>::deserialize(deserializer)?;
+        Ok(values.into_iter().map(|value| value * self.0).collect())
+    }
+}
+
+#[test]
+fn from_bytes_seed() -> Result<(), Error> {
+    let mut table = HashMap::new();
+    for (name, index) in [("a", 0), ("b", 1), ("a", 0), ("c", 2)] {
+        let bytes = shaft::to_bytes(&name)?;
+        assert_eq!(shaft::from_bytes_seed(Intern(&mut table), &bytes)?, index);
+    }
+    Ok(())
+}
+
+#[test]
+fn from_bytes_seed_with() -> Result<(), Error> {
+    for options in [
+        Options::new(),
+        Options::new().with_endian(Endian::Big),
+        Options::new().with_checksum(Checksum::Crc32c),
+    ] {
+        let bytes = shaft::to_bytes_with(&vec![1u32, 2, 3], options)?;
+        let values = shaft::from_bytes_seed_with(Scale(10), &bytes, options)?;
+        assert_eq!(values, [10, 20, 30]);
+    }
+    Ok(())
+}
+
+#[test]
+fn from_bytes_seed_error_located() {
+    let bytes = [3, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0];
+    let error = shaft::from_bytes_seed(Scale(2), &bytes).unwrap_err();
+    assert_eq!(error.offset(), Some(12));
+}
+
+#[test]
+fn from_reader_seed() -> Result<(), Error> {
+    let options = Options::new().with_checksum(Checksum::Crc32c);
+    let bytes = shaft::to_bytes_with(&vec![4u32, 5], options)?;
+    let values = shaft::from_reader_seed_with(Scale(3), Cursor::new(&bytes), options)?;
+    assert_eq!(values, [12, 15]);
+    let mut table = HashMap::new();
+    let bytes = shaft::to_bytes(&"Ferris")?;
+    assert_eq!(shaft::from_reader_seed(Intern(&mut table), &bytes[..])?, 0);
+    Ok(())
+}
+
+#[derive(Serialize)]
+struct Header {
+    version: u8,
+    count: u16,
+}
+
+#[test]
+fn drive_deserializer() -> Result<(), Error> {
+    let mut bytes = shaft::to_bytes(&Header {
+        version: 1,
+        count: 2,
+    })?;
+    bytes.extend(shaft::to_bytes(&"x")?);
+    bytes.extend(shaft::to_bytes(&"y")?);
+    let mut deserializer = Deserializer::from_bytes(&bytes);
+    let (version, count) = <(u8, u16)>::deserialize(&mut deserializer)?;
+    assert_eq!((version, count), (1, 2));
+    assert_eq!(deserializer.position(), 3);
+    let mut table = HashMap::new();
+    for index in 0..count {
+        assert_eq!(
+            Intern(&mut table).deserialize(&mut deserializer)?,
+            index as usize
+        );
+    }
+    assert_eq!(deserializer.position(), bytes.len());
+    deserializer.end()
+}
+
+#[test]
+fn drive_deserializer_with_options() -> Result<(), Error> {
+    let options = Options::new().with_endian(Endian::Big);
+    let bytes = shaft::to_bytes_with(&0x0102u16, options)?;
+    let mut deserializer = Deserializer::with_options(SliceReader::new(&bytes), options);
+    assert_eq!(deserializer.options(), options);
+    assert_eq!(u16::deserialize(&mut deserializer)?, 0x0102);
+    deserializer.end()
+}
+
+#[test]
+fn end_trailing_bytes() -> Result<(), Error> {
+    let bytes = [7, 0, 1];
+    let mut deserializer = Deserializer::from_bytes(&bytes);
+    assert_eq!(u16::deserialize(&mut deserializer)?, 7);
+    let error = deserializer.end().unwrap_err();
+    assert_eq!(error.offset(), Some(2));
+    Ok(())
+}
+
+#[test]
+fn from_bytes_with_checksum() -> Result<(), Error> {
+    let options = Options::new().with_checksum(Checksum::Crc32c);
+    let mut bytes = shaft::to_bytes_with(&7u16, options)?;
+    let mut deserializer = Deserializer::from_bytes_with(&bytes, options)?;
+    assert_eq!(u16::deserialize(&mut deserializer)?, 7);
+    deserializer.end()?;
+    bytes[0] ^= 1;
+    assert!(Deserializer::from_bytes_with(&bytes, options).is_err());
+    Ok(())
+}
@@ -0,0 +1,225 @@
+use std::io::{Cursor, Read};
+
+use serde::{Deserialize, Serialize};
+use shaft::{Checksum, Endian, Error, ErrorKind, IntEncoding, LenWidth, Options, SeqWriter};
+
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+struct Event {
+    id: u32,
+    name: String,
+}
+
+fn events(len: u32) -> Vec<Event> {
+    (0..len)
+        .map(|id| Event {
+            id,
+            name: "x".repeat(id as usize % 100),
+        })
+        .collect()
+}
+
+fn all_options() -> [Options; 4] {
+    [
+        Options::new(),
+        Options::new().with_endian(Endian::Big),
+        Options::new().with_int_encoding(IntEncoding::Varint),
+        Options::new().with_checksum(Checksum::XxHash64),
+    ]
+}
+
+/// Reader returning at most a few bytes per read.
+struct Trickle<R>(R);
+
+impl<R: Read> Read for Trickle<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let len = buf.len().min(3);
+        self.0.read(&mut buf[..len])
+    }
+}
+
+#[test]
+fn iter_seq_slice() -> Result<(), Error> {
+    for options in all_options() {
+        let bytes = shaft::to_bytes_with(&events(100), options)?;
+        let iter = shaft::iter_seq_with::<Event>(&bytes, options)?;
+        assert_eq!(iter.size_hint(), (0, Some(100)));
+        assert_eq!(iter.collect::<Result<Vec<_>, _>>()?, events(100));
+    }
+    Ok(())
+}
+
+#[test]
+fn iter_seq_borrowed() -> Result<(), Error> {
+    let bytes = shaft::to_bytes(&vec!["a", "bc"])?;
+    let values = shaft::iter_seq::<&str>(&bytes)?.collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(values, ["a", "bc"]);
+    Ok(())
+}
+
+#[test]
+fn iter_seq_error_path() -> Result<(), Error> {
+    let bytes = shaft::to_bytes(&events(3))?;
+    let mut iter = shaft::iter_seq::<Event>(&bytes[..bytes.len() - 1])?;
+    assert!(iter.next().unwrap().is_ok());
+    assert!(iter.next().unwrap().is_ok());
+    let error = iter.next().unwrap().unwrap_err();
+    assert_eq!(error.path(), Some("[2].name"));
+    assert!(iter.next().is_none());
+    Ok(())
+}
+
+#[test]
+fn iter_seq_reader() -> Result<(), Error> {
+    for options in all_options() {
+        let bytes = shaft::to_bytes_with(&events(1000), options)?;
+        let iter = shaft::iter_seq_from_reader_with::<_, Event>(Cursor::new(&bytes), options)?;
+        assert_eq!(iter.size_hint(), (0, Some(1000)));
+        assert_eq!(iter.collect::<Result<Vec<_>, _>>()?, events(1000));
+        let iter = shaft::iter_seq_from_reader_with::<_, Event>(Trickle(&bytes[..]), options)?;
+        assert_eq!(iter.collect::<Result<Vec<_>, _>>()?, events(1000));
+    }
+    Ok(())
+}
+
+#[test]
+fn iter_seq_reader_large_element() -> Result<(), Error> {
+    let value = vec![vec![7u8; 100_000], vec![8u8; 3]];
+    let bytes = shaft::to_bytes(&value)?;
+    let iter = shaft::iter_seq_from_reader::<_, Vec<u8>>(Cursor::new(bytes))?;
+    assert_eq!(iter.collect::<Result<Vec<_>, _>>()?, value);
+    Ok(())
+}
+
+#[test]
+fn iter_seq_reader_errors() -> Result<(), Error> {
+    let bytes = shaft::to_bytes(&events(3))?;
+    let mut iter = shaft::iter_seq_from_reader::<_, Event>(&bytes[..bytes.len() - 1])?;
+    assert!(iter.next().unwrap().is_ok());
+    assert!(iter.next().unwrap().is_ok());
+    let error = iter.next().unwrap().unwrap_err();
+    assert_eq!(error.path(), Some("[2].name"));
+    let slice_error = shaft::iter_seq::<Event>(&bytes[..bytes.len() - 1])?
+        .nth(2)
+        .unwrap()
+        .unwrap_err();
+    assert_eq!(error.offset(), slice_error.offset());
+    assert!(iter.next().is_none());
+
+    let options = Options::new().with_checksum(Checksum::Crc32c);
+    let mut bytes = shaft::to_bytes_with(&events(3), options)?;
+    let len = bytes.len();
+    bytes[len - 1] ^= 1;
+    let results =
+        shaft::iter_seq_from_reader_with::<_, Event>(&bytes[..], options)?.collect::<Vec<_>>();
+    assert!(results[..2].iter().all(Result::is_ok));
+    let error = results[2].as_ref().unwrap_err();
+    assert!(matches!(error.kind(), ErrorKind::ChecksumMismatch { .. }));
+    Ok(())
+}
+
+#[test]
+fn iter_seq_untrusted_len() -> Result<(), Error> {
+    let bytes = u64::MAX.to_le_bytes();
+    let results = shaft::iter_seq::<u64>(&bytes)?.collect::<Vec<_>>();
+    assert_eq!(results.len(), 1);
+    assert!(results[0].is_err());
+    let results = shaft::iter_seq_from_reader::<_, u64>(&bytes[..])?.collect::<Vec<_>>();
+    assert_eq!(results.len(), 1);
+    assert!(results[0].is_err());
+    Ok(())
+}
+
+#[test]
+fn iter_seq_reader_invalid_element() -> Result<(), Error> {
+    let mut bytes = 2u64.to_le_bytes().to_vec();
+    bytes.extend(0u32.to_le_bytes());
+    bytes.extend(1u64.to_le_bytes());
+    bytes.push(0xff);
+    // The reader never ends, so the invalid element must be reported without
+    // buffering the rest of it.
+    let reader = (&bytes[..]).chain(std::io::repeat(0));
+    let mut iter = shaft::iter_seq_from_reader::<_, Event>(reader)?;
+    let error = iter.next().unwrap().unwrap_err();
+    assert_eq!(error.path(), Some("[0].name"));
+    assert!(iter.next().is_none());
+    Ok(())
+}
+
+#[test]
+fn iter_seq_reader_trailing_defaults() -> Result<(), Error> {
+    #[derive(Debug, PartialEq, Deserialize, Serialize)]
+    struct Record {
+        a: u32,
+        #[serde(default)]
+        b: u8,
+    }
+
+    let options = Options::new().with_trailing_defaults(true);
+    let records: Vec<_> = (0..3000).map(|a| Record { a, b: 1 }).collect();
+    let bytes = shaft::to_bytes_with(&records, options)?;
+    // Elements straddle the end of the read buffer, which must not be
+    // mistaken for missing trailing fields.
+    let iter = shaft::iter_seq_from_reader_with::<_, Record>(Cursor::new(bytes), options)?;
+    assert_eq!(iter.collect::<Result<Vec<_>, _>>()?, records);
+    Ok(())
+}
+
+#[test]
+fn seq_writer() -> Result<(), Error> {
+    for options in all_options() {
+        let mut writer = SeqWriter::with_options(Vec::new(), 100, options)?;
+        for event in events(100) {
+            writer.push(&event)?;
+        }
+        assert_eq!(writer.count(), 100);
+        let bytes = writer.finish()?;
+        assert_eq!(bytes, shaft::to_bytes_with(&events(100), options)?);
+    }
+    Ok(())
+}
+
+#[test]
+fn seq_writer_count_mismatch() -> Result<(), Error> {
+    let mut writer = SeqWriter::new(Vec::new(), 1)?;
+    writer.push(&1u8)?;
+    assert!(writer.push(&2u8).is_err());
+    let writer = SeqWriter::new(Vec::new(), 2)?;
+    assert!(writer.finish().is_err());
+    Ok(())
+}
+
+#[test]
+fn seq_writer_backpatched() -> Result<(), Error> {
+    for options in [
+        Options::new(),
+        Options::new().with_endian(Endian::Big),
+        Options::new()
+            .with_int_encoding(IntEncoding::Varint)
+            .with_len_width(LenWidth::U8),
+    ] {
+        let mut cursor = Cursor::new(vec![0xff]);
+        cursor.set_position(1);
+        let mut writer = SeqWriter::backpatched(cursor, options)?;
+        for event in events(10) {
+            writer.push(&event)?;
+        }
+        let cursor = writer.finish_backpatched()?;
+        assert_eq!(cursor.position() as usize, cursor.get_ref().len());
+        assert_eq!(cursor.get_ref()[0], 0xff);
+        assert_eq!(
+            cursor.get_ref()[1..],
+            shaft::to_bytes_with(&events(10), options)?
+        );
+    }
+    Ok(())
+}
+
+#[test]
+fn seq_writer_backpatched_unsupported() {
+    let options = Options::new().with_int_encoding(IntEncoding::Varint);
+    assert!(SeqWriter::backpatched(Cursor::new(Vec::new()), options).is_err());
+    let options = Options::new().with_checksum(Checksum::Crc32c);
+    assert!(SeqWriter::backpatched(Cursor::new(Vec::new()), options).is_err());
+    let writer = SeqWriter::backpatched(Cursor::new(Vec::new()), Options::new()).unwrap();
+    assert!(writer.finish().is_err());
+}
@@ -0,0 +1,252 @@
+use serde::{Deserialize, Serialize};
+use shaft::{Error, FieldTag, Options, StructEncoding};
+
+fn hash() -> Options {
+    Options::new().with_struct_encoding(StructEncoding::Tagged(FieldTag::Hash))
+}
+
+fn index() -> Options {
+    Options::new().with_struct_encoding(StructEncoding::Tagged(FieldTag::Index))
+}
+
+#[test]
+fn serialize_struct_index() -> Result<(), Error> {
+    #[derive(Serialize)]
+    struct Struct {
+        a: u8,
+        b: u16,
+    }
+
+    let value = Struct { a: 1, b: 2 };
+    let bytes = shaft::to_bytes_with(&value, index())?;
+    assert_eq!(
+        bytes,
+        [
+            2, 0, 0, 0, 0, 0, 0, 0, // field count
+            0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 1, // field a
+            1, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 2, 0, // field b
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn serialize_struct_hash() -> Result<(), Error> {
+    #[derive(Serialize)]
+    struct Struct {
+        a: u8,
+    }
+
+    let value = Struct { a: 1 };
+    let bytes = shaft::to_bytes_with(&value, hash())?;
+    assert_eq!(
+        bytes,
+        [
+            1, 0, 0, 0, 0, 0, 0, 0, // field count
+            0x2c, 0x29, 0x0c, 0xe4, 1, 0, 0, 0, 0, 0, 0, 0, 1, // field a
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn serialize_struct_skip_index() -> Result<(), Error> {
+    #[derive(Serialize)]
+    struct Struct {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        a: Option<u8>,
+        b: u8,
+    }
+
+    let value = Struct { a: None, b: 2 };
+    let bytes = shaft::to_bytes_with(&value, index())?;
+    assert_eq!(
+        bytes,
+        [
+            1, 0, 0, 0, 0, 0, 0, 0, // field count
+            1, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 2, // field b
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn roundtrip_struct_skip_index() -> Result<(), Error> {
+    #[derive(Debug, PartialEq, Deserialize, Serialize)]
+    struct Struct {
+        a: u8,
+        #[serde(skip)]
+        b: u8,
+        c: u8,
+    }
+
+    let value = Struct { a: 1, b: 2, c: 3 };
+    let bytes = shaft::to_bytes_with(&value, index())?;
+    let result: Struct = shaft::from_bytes_with(&bytes, index())?;
+    assert_eq!(result, Struct { a: 1, b: 0, c: 3 });
+    Ok(())
+}
+
+#[test]
+fn deserialize_skip_deserializing_index() -> Result<(), Error> {
+    #[derive(Debug, PartialEq, Deserialize, Serialize)]
+    struct Struct {
+        #[serde(skip_deserializing)]
+        a: u8,
+        b: u8,
+        c: u8,
+    }
+
+    // Only `b` and `c` are known when deserializing, so index tags shift.
+    let value = Struct { a: 1, b: 2, c: 3 };
+    let bytes = shaft::to_bytes_with(&value, index())?;
+    let result: Struct = shaft::from_bytes_with(&bytes, index())?;
+    assert_eq!(result, Struct { a: 0, b: 1, c: 2 });
+
+    let bytes = shaft::to_bytes_with(&value, hash())?;
+    let result: Struct = shaft::from_bytes_with(&bytes, hash())?;
+    assert_eq!(result, Struct { a: 0, b: 2, c: 3 });
+    Ok(())
+}
+
+#[test]
+fn roundtrip_struct() -> Result<(), Error> {
+    #[derive(Debug, PartialEq, Deserialize, Serialize)]
+    struct Struct {
+        name: String,
+        inner: Inner,
+        values: Vec<Inner>,
+    }
+    #[derive(Debug, PartialEq, Deserialize, Serialize)]
+    struct Inner {
+        a: u8,
+        b: Option<bool>,
+    }
+
+    let value = Struct {
+        name: "Ferris".to_owned(),
+        inner: Inner { a: 1, b: None },
+        values: vec![Inner {
+            a: 2,
+            b: Some(true),
+        }],
+    };
+    for options in [hash(), index()] {
+        let bytes = shaft::to_bytes_with(&value, options)?;
+        let result: Struct = shaft::from_bytes_with(&bytes, options)?;
+        assert_eq!(result, value);
+    }
+    Ok(())
+}
+
+#[test]
+fn roundtrip_struct_variant() -> Result<(), Error> {
+    #[derive(Debug, PartialEq, Deserialize, Serialize)]
+    enum Enum {
+        A { a: u8, b: String },
+    }
+
+    let value = Enum::A {
+        a: 1,
+        b: "b".to_owned(),
+    };
+    let bytes = shaft::to_bytes_with(&value, hash())?;
+    let result: Enum = shaft::from_bytes_with(&bytes, hash())?;
+    assert_eq!(result, value);
+    Ok(())
+}
+
+#[test]
+fn deserialize_reordered_fields() -> Result<(), Error> {
+    #[derive(Serialize)]
+    struct Old {
+        a: u8,
+        b: String,
+    }
+    #[derive(Deserialize)]
+    struct New {
+        b: String,
+        a: u8,
+    }
+
+    let bytes = shaft::to_bytes_with(
+        &Old {
+            a: 1,
+            b: "b".to_owned(),
+        },
+        hash(),
+    )?;
+    let value: New = shaft::from_bytes_with(&bytes, hash())?;
+    assert_eq!(value.a, 1);
+    assert_eq!(value.b, "b");
+    Ok(())
+}
+
+#[test]
+fn deserialize_unknown_fields() -> Result<(), Error> {
+    #[derive(Serialize)]
+    struct Old {
+        a: u8,
+        b: Vec<u32>,
+        c: u8,
+    }
+    #[derive(Deserialize)]
+    struct New {
+        a: u8,
+        c: u8,
+    }
+
+    let old = Old {
+        a: 1,
+        b: vec![1, 2, 3],
+        c: 3,
+    };
+    let bytes = shaft::to_bytes_with(&old, hash())?;
+    let value: New = shaft::from_bytes_with(&bytes, hash())?;
+    assert_eq!(value.a, 1);
+    assert_eq!(value.c, 3);
+    Ok(())
+}
+
+#[test]
+fn deserialize_missing_fields() -> Result<(), Error> {
+    #[derive(Serialize)]
+    struct Old {
+        a: u8,
+    }
+    #[derive(Deserialize)]
+    struct New {
+        a: u8,
+        b: Option<u8>,
+        #[serde(default)]
+        c: Vec<u8>,
+    }
+
+    for options in [hash(), index()] {
+        let bytes = shaft::to_bytes_with(&Old { a: 1 }, options)?;
+        let value: New = shaft::from_bytes_with(&bytes, options)?;
+        assert_eq!(value.a, 1);
+        assert_eq!(value.b, None);
+        assert!(value.c.is_empty());
+    }
+    Ok(())
+}
+
+#[test]
+fn deserialize_missing_required_field() -> Result<(), Error> {
+    #[derive(Serialize)]
+    struct Old {
+        a: u8,
+    }
+    #[derive(Debug, Deserialize)]
+    #[allow(dead_code)]
+    struct New {
+        a: u8,
+        b: u8,
+    }
+
+    let bytes = shaft::to_bytes_with(&Old { a: 1 }, hash())?;
+    let result: Result<New, Error> = shaft::from_bytes_with(&bytes, hash());
+    assert!(result.is_err());
+    Ok(())
+}
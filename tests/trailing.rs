@@ -0,0 +1,122 @@
+use serde::{Deserialize, Serialize};
+use shaft::{Error, FieldTag, Options, StructEncoding};
+
+fn options() -> Options {
+    Options::new().with_trailing_defaults(true)
+}
+
+#[derive(Serialize)]
+struct Old {
+    a: u8,
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+struct New {
+    a: u8,
+    #[serde(default)]
+    b: Option<u8>,
+    #[serde(default)]
+    c: String,
+}
+
+#[test]
+fn deserialize_missing_trailing_fields() -> Result<(), Error> {
+    let bytes = shaft::to_bytes(&Old { a: 1 })?;
+    let value: New = shaft::from_bytes_with(&bytes, options())?;
+    assert_eq!(
+        value,
+        New {
+            a: 1,
+            b: None,
+            c: String::new()
+        }
+    );
+    Ok(())
+}
+
+#[test]
+fn deserialize_present_trailing_fields() -> Result<(), Error> {
+    let bytes = [1, 1, 2, 1, 0, 0, 0, 0, 0, 0, 0, b'c'];
+    let value: New = shaft::from_bytes_with(&bytes, options())?;
+    assert_eq!(
+        value,
+        New {
+            a: 1,
+            b: Some(2),
+            c: "c".to_owned()
+        }
+    );
+    Ok(())
+}
+
+#[test]
+fn deserialize_missing_trailing_fields_disabled() -> Result<(), Error> {
+    let bytes = shaft::to_bytes(&Old { a: 1 })?;
+    let result: Result<New, Error> = shaft::from_bytes(&bytes);
+    assert!(result.is_err());
+    Ok(())
+}
+
+#[test]
+fn deserialize_missing_required_trailing_field() -> Result<(), Error> {
+    #[derive(Debug, Deserialize)]
+    #[allow(dead_code)]
+    struct Required {
+        a: u8,
+        b: Option<u8>,
+    }
+
+    let bytes = shaft::to_bytes(&Old { a: 1 })?;
+    let result: Result<Required, Error> = shaft::from_bytes_with(&bytes, options());
+    assert!(result.is_err());
+    Ok(())
+}
+
+#[test]
+fn deserialize_missing_nested_trailing_fields() -> Result<(), Error> {
+    #[derive(Serialize)]
+    struct OldOuter {
+        a: u8,
+        inner: Old,
+    }
+    #[derive(Debug, Deserialize)]
+    struct NewOuter {
+        a: u8,
+        inner: New,
+    }
+
+    let bytes = shaft::to_bytes(&OldOuter {
+        a: 1,
+        inner: Old { a: 2 },
+    })?;
+    let value: NewOuter = shaft::from_bytes_with(&bytes, options())?;
+    assert_eq!(value.a, 1);
+    assert_eq!(value.inner.a, 2);
+    assert_eq!(value.inner.b, None);
+    Ok(())
+}
+
+#[test]
+fn deserialize_missing_trailing_fields_in_tagged_field() -> Result<(), Error> {
+    #[derive(Serialize)]
+    struct OldOuter {
+        inner: (Old,),
+        z: u8,
+    }
+    #[derive(Debug, Deserialize)]
+    struct NewOuter {
+        inner: (New,),
+        z: u8,
+    }
+
+    let options = options().with_struct_encoding(StructEncoding::Tagged(FieldTag::Hash));
+    let old = OldOuter {
+        inner: (Old { a: 2 },),
+        z: 3,
+    };
+    let bytes = shaft::to_bytes_with(&old, options)?;
+    let value: NewOuter = shaft::from_bytes_with(&bytes, options)?;
+    assert_eq!(value.inner.0.b, None);
+    assert_eq!(value.z, 3);
+    Ok(())
+}
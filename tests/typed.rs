@@ -0,0 +1,114 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use serde::{Deserialize, Deserializer, Serialize};
+use shaft::{Error, ErrorKind};
+
+#[test]
+fn serialize_fingerprint() -> Result<(), Error> {
+    let bytes = shaft::typed::to_bytes(&1u8)?;
+    let fingerprint = shaft::typed::fingerprint::<u8>()?;
+    assert_eq!(bytes[..8], fingerprint.to_le_bytes());
+    assert_eq!(bytes[8..], [1]);
+    Ok(())
+}
+
+#[test]
+fn roundtrip_typed() -> Result<(), Error> {
+    #[derive(Debug, PartialEq, Deserialize, Serialize)]
+    struct Node {
+        value: u32,
+        children: Vec<Node>,
+    }
+
+    let value = Node {
+        value: 1,
+        children: vec![Node {
+            value: 2,
+            children: Vec::new(),
+        }],
+    };
+    let bytes = shaft::typed::to_bytes(&value)?;
+    let result: Node = shaft::typed::from_bytes(&bytes)?;
+    assert_eq!(result, value);
+    Ok(())
+}
+
+#[test]
+fn deserialize_type_mismatch() -> Result<(), Error> {
+    #[derive(Serialize, Deserialize)]
+    struct Old {
+        a: u32,
+        b: u32,
+    }
+    #[derive(Debug, Deserialize)]
+    #[allow(dead_code)]
+    struct New {
+        a: u64,
+    }
+
+    let bytes = shaft::typed::to_bytes(&Old { a: 1, b: 2 })?;
+    let error = shaft::typed::from_bytes::<New>(&bytes).unwrap_err();
+    match error.kind() {
+        ErrorKind::TypeMismatch { expected, found } => {
+            assert_eq!(*expected, shaft::typed::fingerprint::<New>()?);
+            assert_eq!(*found, shaft::typed::fingerprint::<Old>()?);
+        }
+        kind => panic!("unexpected error kind: {kind:?}"),
+    }
+    Ok(())
+}
+
+#[test]
+fn fingerprint_covers_names() -> Result<(), Error> {
+    #[derive(Deserialize)]
+    #[allow(dead_code)]
+    struct A {
+        score: u32,
+    }
+    #[derive(Deserialize)]
+    #[allow(dead_code)]
+    struct B {
+        score: u32,
+    }
+    #[derive(Deserialize)]
+    #[allow(dead_code)]
+    struct C {
+        level: u32,
+    }
+    #[derive(Deserialize)]
+    #[allow(dead_code)]
+    enum D {
+        Score(u32),
+    }
+
+    let a = shaft::typed::fingerprint::<A>()?;
+    assert_eq!(a, shaft::typed::fingerprint::<B>()?);
+    assert_ne!(a, shaft::typed::fingerprint::<C>()?);
+    assert_ne!(a, shaft::typed::fingerprint::<D>()?);
+    Ok(())
+}
+
+#[test]
+fn fingerprint_cached() -> Result<(), Error> {
+    static TRACES: AtomicUsize = AtomicUsize::new(0);
+
+    #[derive(Debug, PartialEq, Serialize)]
+    struct Counted(u32);
+
+    impl<'de> Deserialize<'de> for Counted {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            TRACES.fetch_add(1, Ordering::Relaxed);
+            u32::deserialize(deserializer).map(Counted)
+        }
+    }
+
+    let fingerprint = shaft::typed::fingerprint::<Counted>()?;
+    let traces = TRACES.load(Ordering::Relaxed);
+    assert!(traces > 0);
+    let bytes = shaft::typed::to_bytes(&Counted(7))?;
+    assert_eq!(bytes[..8], fingerprint.to_le_bytes());
+    assert_eq!(shaft::typed::from_bytes::<Counted>(&bytes)?, Counted(7));
+    // Decoding the body deserializes once more, tracing does not.
+    assert_eq!(TRACES.load(Ordering::Relaxed), traces + 1);
+    Ok(())
+}
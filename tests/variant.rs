@@ -0,0 +1,131 @@
+use serde::{Deserialize, Serialize};
+use shaft::{Error, Options, VariantEncoding};
+
+fn name() -> Options {
+    Options::new().with_variant_encoding(VariantEncoding::Name)
+}
+
+fn hash() -> Options {
+    Options::new().with_variant_encoding(VariantEncoding::Hash)
+}
+
+#[test]
+fn serialize_variant_name() -> Result<(), Error> {
+    #[derive(Serialize)]
+    enum Enum {
+        A(u8),
+    }
+
+    let bytes = shaft::to_bytes_with(&Enum::A(1), name())?;
+    assert_eq!(
+        bytes,
+        [
+            1, 0, 0, 0, 0, 0, 0, 0, b'A', // variant name
+            1,    // variant value
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn serialize_variant_hash() -> Result<(), Error> {
+    #[derive(Serialize)]
+    enum Enum {
+        A,
+    }
+
+    let bytes = shaft::to_bytes_with(&Enum::A, hash())?;
+    assert_eq!(bytes, [0xcc, 0xf6, 0x0b, 0xc4]);
+    Ok(())
+}
+
+#[test]
+fn roundtrip_variants() -> Result<(), Error> {
+    #[derive(Debug, PartialEq, Deserialize, Serialize)]
+    enum Enum {
+        A,
+        B(u8),
+        C(u8, String),
+        D { a: u8, b: Option<bool> },
+    }
+
+    let values = vec![
+        Enum::A,
+        Enum::B(1),
+        Enum::C(2, "c".to_owned()),
+        Enum::D {
+            a: 3,
+            b: Some(true),
+        },
+    ];
+    for options in [name(), hash()] {
+        let bytes = shaft::to_bytes_with(&values, options)?;
+        let result: Vec<Enum> = shaft::from_bytes_with(&bytes, options)?;
+        assert_eq!(result, values);
+    }
+    Ok(())
+}
+
+#[test]
+fn deserialize_reordered_variants() -> Result<(), Error> {
+    #[derive(Serialize)]
+    enum Old {
+        A,
+        B(u8),
+    }
+    #[derive(Debug, PartialEq, Deserialize)]
+    enum New {
+        C,
+        B(u8),
+        A,
+    }
+
+    for options in [name(), hash()] {
+        let bytes = shaft::to_bytes_with(&(Old::A, Old::B(1)), options)?;
+        let value: (New, New) = shaft::from_bytes_with(&bytes, options)?;
+        assert_eq!(value, (New::A, New::B(1)));
+    }
+    Ok(())
+}
+
+#[test]
+fn deserialize_unknown_variant() -> Result<(), Error> {
+    #[derive(Serialize)]
+    enum Old {
+        A,
+    }
+    #[derive(Debug, Deserialize)]
+    enum New {
+        B,
+    }
+
+    for options in [name(), hash()] {
+        let bytes = shaft::to_bytes_with(&Old::A, options)?;
+        let result: Result<New, Error> = shaft::from_bytes_with(&bytes, options);
+        assert!(result.is_err());
+    }
+    Ok(())
+}
+
+#[test]
+fn deserialize_variant_alias_other() -> Result<(), Error> {
+    #[derive(Serialize)]
+    enum Old {
+        A,
+        B(u8),
+        C,
+    }
+    #[derive(Debug, PartialEq, Deserialize)]
+    enum New {
+        #[serde(alias = "A")]
+        Renamed,
+        B(u8),
+        #[serde(other)]
+        Unknown,
+    }
+
+    let bytes = shaft::to_bytes_with(&(Old::A, Old::B(1), Old::C), name())?;
+    let value: (New, New, New) = shaft::from_bytes_with(&bytes, name())?;
+    assert_eq!(value, (New::Renamed, New::B(1), New::Unknown));
+    Ok(())
+}
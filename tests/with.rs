@@ -0,0 +1,79 @@
+use serde::{Deserialize, Serialize};
+use shaft::{Endian, Error, IntEncoding, LenWidth, Options};
+
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+struct Record {
+    #[serde(with = "shaft::with::fixed")]
+    id: u64,
+    #[serde(with = "shaft::with::varint")]
+    count: u32,
+    #[serde(with = "shaft::with::u16_len")]
+    name: String,
+    #[serde(with = "shaft::with::bytes")]
+    payload: Vec<u8>,
+    #[serde(with = "shaft::with::fixed_len::<3>")]
+    position: Vec<i16>,
+    #[serde(with = "shaft::with::be")]
+    tags: Vec<u16>,
+}
+
+#[test]
+fn serialize_with_adapters() -> Result<(), Error> {
+    let record = Record {
+        id: 1,
+        count: 300,
+        name: "ab".to_owned(),
+        payload: vec![7],
+        position: vec![1, -1, 2],
+        tags: vec![0x0102],
+    };
+    let options = Options::new().with_len_width(LenWidth::U8);
+    let bytes = shaft::to_bytes_with(&record, options)?;
+    assert_eq!(
+        bytes,
+        [
+            1, 0, 0, 0, 0, 0, 0, 0, // id
+            0xac, 0x02, // count
+            2, 0, b'a', b'b', // name
+            1, 7, // payload
+            1, 0, 0xff, 0xff, 2, 0, // position
+            1, 0x01, 0x02, // tags
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn roundtrip_with_adapters() -> Result<(), Error> {
+    let record = Record {
+        id: 1,
+        count: 300,
+        name: "ab".to_owned(),
+        payload: vec![7],
+        position: vec![1, -1, 2],
+        tags: vec![0x0102],
+    };
+    for options in [
+        Options::new(),
+        Options::new().with_endian(Endian::Big),
+        Options::new().with_int_encoding(IntEncoding::Varint),
+    ] {
+        let bytes = shaft::to_bytes_with(&record, options)?;
+        let result: Record = shaft::from_bytes_with(&bytes, options)?;
+        assert_eq!(result, record);
+    }
+    Ok(())
+}
+
+#[test]
+fn serialize_fixed_len_mismatch() {
+    let record = Record {
+        id: 1,
+        count: 300,
+        name: "ab".to_owned(),
+        payload: vec![7],
+        position: vec![1, -1, 2, 3],
+        tags: vec![0x0102],
+    };
+    assert!(shaft::to_bytes(&record).is_err());
+}